@@ -30,6 +30,7 @@ use criterion::{
 use std::time::Duration;
 
 use dark_hypercore::{CLOB, MatchingEngine, Order, Side};
+use dark_hypercore::orderbook::ClobPool;
 
 // ============================================================================
 // HELPER FUNCTIONS - Deterministic order generation
@@ -195,7 +196,7 @@ fn bench_order_operations(c: &mut Criterion) {
     // Benchmark: Add order to empty book
     group.bench_function("add_to_empty", |b| {
         b.iter_batched(
-            || CLOB::new(),
+            CLOB::new,
             |mut clob| {
                 let order = make_buy_order(1, 5_000_000_000_000, 100_000_000);
                 black_box(clob.add_order(order))
@@ -238,6 +239,25 @@ fn bench_order_operations(c: &mut Criterion) {
         );
     });
     
+    // Benchmark: Tight add/cancel loop at one price, with and without
+    // empty-level retention
+    for retain in [false, true] {
+        group.bench_with_input(
+            BenchmarkId::new("add_cancel_same_price", if retain { "retained" } else { "removed" }),
+            &retain,
+            |b, &retain| {
+                let mut clob = CLOB::with_capacity(1000).with_level_retention(retain);
+                populate_bids(&mut clob, 100, 4_999_000_000_000, 100_000_000, 100_000_000);
+                let mut id = 1_000_000;
+                b.iter(|| {
+                    id += 1;
+                    clob.add_order(make_buy_order(id, 4_500_000_000_000, 100_000_000));
+                    black_box(clob.cancel_order(id))
+                });
+            },
+        );
+    }
+    
     group.finish();
 }
 
@@ -286,6 +306,54 @@ fn bench_throughput(c: &mut Criterion) {
     group.finish();
 }
 
+/// Benchmark: batches on a fresh book each time vs a recycled book
+///
+/// Mirrors per-block simulation, where every batch starts from an empty
+/// book. The pooled variant reuses one book's allocations.
+fn bench_pool(c: &mut Criterion) {
+    let mut group = c.benchmark_group("pool");
+    
+    for batch_size in [1_000, 10_000] {
+        group.throughput(Throughput::Elements(batch_size as u64));
+        let orders = generate_order_batch(batch_size, 42);
+        
+        group.bench_with_input(
+            BenchmarkId::new("fresh", batch_size),
+            &orders,
+            |b, orders| {
+                b.iter(|| {
+                    let mut clob = CLOB::with_capacity(batch_size * 2);
+                    let mut engine = MatchingEngine::new();
+                    for order in orders.iter().cloned() {
+                        black_box(engine.match_order(&mut clob, order, 0));
+                    }
+                    clob.order_count()
+                });
+            }
+        );
+        
+        group.bench_with_input(
+            BenchmarkId::new("pooled", batch_size),
+            &orders,
+            |b, orders| {
+                let mut pool = ClobPool::new(batch_size * 2);
+                b.iter(|| {
+                    let mut clob = pool.acquire();
+                    let mut engine = MatchingEngine::new();
+                    for order in orders.iter().cloned() {
+                        black_box(engine.match_order(&mut clob, order, 0));
+                    }
+                    let count = clob.order_count();
+                    pool.release(clob);
+                    count
+                });
+            }
+        );
+    }
+    
+    group.finish();
+}
+
 // ============================================================================
 // BENCHMARK: Memory Efficiency
 // ============================================================================
@@ -361,6 +429,7 @@ criterion_group!(
     bench_single_match,
     bench_order_operations,
     bench_throughput,
+    bench_pool,
     bench_large_book,
     bench_determinism
 );
@@ -0,0 +1,19 @@
+//! Fuzz target: arbitrary add/cancel/match sequences.
+//!
+//! Each 4-byte chunk of input is decoded into one book operation. After
+//! every step the harness runs `CLOB::verify_invariants()` and panics on
+//! any violation, so libFuzzer reports linked-list corruption, count
+//! desync, and quantity drift as crashes.
+//!
+//! ```bash
+//! cargo run --manifest-path fuzz/Cargo.toml --bin gen_corpus
+//! cargo fuzz run book_ops
+//! ```
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    dark_hypercore_fuzz::run_ops(data);
+});
@@ -0,0 +1,53 @@
+//! Generate the seed corpus for the `book_ops` fuzz target.
+//!
+//! Uses the same seeded ChaCha8 generator shape as `tests/stress_test.rs`
+//! (50/50 sides, prices around a base, 30% cancel rate), mapped onto the
+//! fuzzer's 4-byte operation encoding.
+//!
+//! ```bash
+//! cargo run --manifest-path fuzz/Cargo.toml --bin gen_corpus
+//! ```
+
+use std::fs;
+use std::path::Path;
+
+use dark_hypercore::Side;
+use dark_hypercore_fuzz::{Op, PRICE_TICKS};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+/// Seeds used by the stress tests, plus a few neighbours
+const SEEDS: [u64; 6] = [42, 43, 12345, 12346, 1, 7];
+
+/// Operations per corpus entry
+const OPS_PER_ENTRY: usize = 512;
+
+/// Fraction of steps that cancel instead of submitting
+const CANCEL_RATE: f64 = 0.3;
+
+fn main() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("corpus").join("book_ops");
+    fs::create_dir_all(&dir).expect("Failed to create corpus directory");
+    
+    for seed in SEEDS {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let mut bytes = Vec::with_capacity(OPS_PER_ENTRY * 4);
+        
+        for _ in 0..OPS_PER_ENTRY {
+            let op = if rng.gen_bool(CANCEL_RATE) {
+                Op::Cancel { id_hint: rng.gen() }
+            } else {
+                Op::Submit {
+                    side: if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell },
+                    price_tick: rng.gen_range(0..PRICE_TICKS),
+                    lots: rng.gen(),
+                }
+            };
+            bytes.extend_from_slice(&op.encode());
+        }
+        
+        let path = dir.join(format!("seed-{}", seed));
+        fs::write(&path, &bytes).expect("Failed to write corpus entry");
+        println!("wrote {} ({} ops)", path.display(), OPS_PER_ENTRY);
+    }
+}
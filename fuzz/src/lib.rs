@@ -0,0 +1,133 @@
+//! Shared harness for the Dark HyperCore fuzz targets.
+//!
+//! ## Input Format
+//!
+//! Input bytes are consumed in 4-byte chunks; a trailing partial chunk is
+//! ignored. The first byte selects the operation, the rest are operands:
+//!
+//! | op % 4 | Operation | Operands |
+//! |--------|-----------|----------|
+//! | 0 | `match_order` | side bit, price tick, lots |
+//! | 1 | `cancel_order` | order ID hint |
+//! | 2 | `reduce_order` | order ID hint, lots |
+//! | 3 | `cancel_price_level` | side bit, price tick |
+//!
+//! Prices are confined to a 32-tick band so orders collide on levels and
+//! cross often. Order ID hints resolve to recently submitted IDs.
+
+use dark_hypercore::{MatchingEngine, Order, Side, CLOB};
+
+/// Lowest price in the fuzzed band: 50000.00000000
+pub const BASE_PRICE: u64 = 5_000_000_000_000;
+
+/// Price tick: 1.00000000
+pub const TICK: u64 = 100_000_000;
+
+/// Number of distinct price ticks
+pub const PRICE_TICKS: u8 = 32;
+
+/// Quantity lot: 0.01000000
+pub const LOT: u64 = 1_000_000;
+
+/// Bytes consumed per operation
+pub const OP_BYTES: usize = 4;
+
+/// A single decoded book operation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    /// Match an incoming limit order (remainder rests)
+    Submit { side: Side, price_tick: u8, lots: u8 },
+    /// Cancel a recently submitted order
+    Cancel { id_hint: u8 },
+    /// Shrink a recently submitted order
+    Reduce { id_hint: u8, lots: u8 },
+    /// Cancel an entire price level
+    CancelLevel { side: Side, price_tick: u8 },
+}
+
+impl Op {
+    /// Decode one operation from a 4-byte chunk
+    pub fn decode(chunk: [u8; OP_BYTES]) -> Self {
+        let side = if chunk[1] & 1 == 0 { Side::Buy } else { Side::Sell };
+        match chunk[0] % 4 {
+            0 => Op::Submit { side, price_tick: chunk[2] % PRICE_TICKS, lots: chunk[3] },
+            1 => Op::Cancel { id_hint: chunk[1] },
+            2 => Op::Reduce { id_hint: chunk[1], lots: chunk[2] },
+            _ => Op::CancelLevel { side, price_tick: chunk[2] % PRICE_TICKS },
+        }
+    }
+    
+    /// Encode this operation as a 4-byte chunk (inverse of `decode`)
+    pub fn encode(self) -> [u8; OP_BYTES] {
+        match self {
+            Op::Submit { side, price_tick, lots } => [0, side.to_u8(), price_tick, lots],
+            Op::Cancel { id_hint } => [1, id_hint, 0, 0],
+            Op::Reduce { id_hint, lots } => [2, id_hint, lots, 0],
+            Op::CancelLevel { side, price_tick } => [3, side.to_u8(), price_tick, 0],
+        }
+    }
+}
+
+/// Decode every complete 4-byte chunk of the input
+pub fn decode_ops(data: &[u8]) -> impl Iterator<Item = Op> + '_ {
+    data.chunks_exact(OP_BYTES).map(|chunk| {
+        let mut bytes = [0u8; OP_BYTES];
+        bytes.copy_from_slice(chunk);
+        Op::decode(bytes)
+    })
+}
+
+/// Price for a tick offset within the fuzzed band
+#[inline]
+fn tick_price(price_tick: u8) -> u64 {
+    BASE_PRICE + price_tick as u64 * TICK
+}
+
+/// Resolve an ID hint to one of the IDs issued so far (most recent first)
+#[inline]
+fn resolve_id(next_id: u64, id_hint: u8) -> Option<u64> {
+    let issued = next_id - 1;
+    if issued == 0 {
+        return None;
+    }
+    Some(next_id - 1 - (id_hint as u64 % issued))
+}
+
+/// Run an operation sequence, checking invariants after every step
+///
+/// # Panics
+///
+/// Panics if any step leaves the book violating an invariant.
+pub fn run_ops(data: &[u8]) {
+    let mut clob = CLOB::with_capacity(data.len() / OP_BYTES + 1);
+    let mut engine = MatchingEngine::new();
+    let mut next_id = 1u64;
+    
+    for (step, op) in decode_ops(data).enumerate() {
+        match op {
+            Op::Submit { side, price_tick, lots } => {
+                let quantity = (lots as u64 + 1) * LOT;
+                let order = Order::new(next_id, next_id % 7, side, tick_price(price_tick), quantity, step as u64);
+                next_id += 1;
+                engine.match_order(&mut clob, order, step as u64);
+            }
+            Op::Cancel { id_hint } => {
+                if let Some(id) = resolve_id(next_id, id_hint) {
+                    clob.cancel_order(id);
+                }
+            }
+            Op::Reduce { id_hint, lots } => {
+                if let Some(id) = resolve_id(next_id, id_hint) {
+                    clob.reduce_order(id, (lots as u64 + 1) * LOT);
+                }
+            }
+            Op::CancelLevel { side, price_tick } => {
+                clob.cancel_price_level(side, tick_price(price_tick));
+            }
+        }
+        
+        if let Err(violation) = clob.verify_invariants() {
+            panic!("invariant violated after step {} ({:?}): {}", step, op, violation);
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! Uniform-price call auction.
+//!
+//! Instead of matching continuously, orders are collected on a book with
+//! [`CLOB::add_order`] (which never matches) and then cleared together by
+//! [`MatchingEngine::run_auction`] at a single price.
+//!
+//! ## Clearing Price
+//!
+//! Every resting price is a candidate. At each candidate the executable
+//! volume is `min(demand, supply)`, where demand counts bids willing to pay
+//! at least the candidate and supply counts asks willing to sell at it.
+//! The clearing price is the candidate with:
+//!
+//! 1. The largest executable volume, then
+//! 2. The smallest imbalance `|demand - supply|`, then
+//! 3. The lowest numeric price
+//!
+//! ## Allocation
+//!
+//! Each side fills best price first. Levels that fit in the remaining
+//! volume fill completely; the marginal level (at the clearing price)
+//! shares what is left via [`allocate_pro_rata`]. Buy and sell fills are
+//! then paired in priority order into trades at the clearing price, with
+//! the sell order recorded as the maker.
+
+use crate::engine::{allocate_pro_rata, MatchingEngine};
+use crate::orderbook::{PriceOrder, CLOB};
+use crate::types::{Side, Trade};
+
+/// Outcome of a call auction.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AuctionResult {
+    /// Uniform clearing price, or None if the book did not cross
+    pub clearing_price: Option<u64>,
+    
+    /// Total quantity executed (fixed-point)
+    pub volume: u64,
+    
+    /// Trades, all at the clearing price
+    pub trades: Vec<Trade>,
+}
+
+/// A resting order as seen by the auction
+#[derive(Debug, Clone, Copy)]
+struct Resting {
+    id: u64,
+    user_id: u64,
+    remaining: u64,
+    client_tag: [u8; 16],
+}
+
+/// One side's levels in priority order, each with its orders head to tail
+type SideQueue = Vec<(u64, Vec<Resting>)>;
+
+impl MatchingEngine {
+    /// Clear the whole book at one volume-maximizing price
+    ///
+    /// # Arguments
+    ///
+    /// * `clob` - Book holding the collected (possibly crossed) orders
+    /// * `timestamp` - Timestamp recorded on every trade
+    ///
+    /// # Returns
+    ///
+    /// The clearing price and the trades executed. Filled quantity is
+    /// removed from the book; unfilled orders keep resting. Nothing trades,
+    /// and the default result is returned, if the book has too few trade
+    /// IDs left for the auction's fills.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(10);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 10_200_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 200, Side::Sell, 9_900_000_000, 100_000_000, 0));
+    ///
+    /// let result = MatchingEngine::new().run_auction(&mut clob, 1);
+    /// assert_eq!(result.clearing_price, Some(9_900_000_000));
+    /// assert_eq!(result.volume, 100_000_000);
+    /// assert!(clob.is_empty());
+    /// ```
+    pub fn run_auction(&self, clob: &mut CLOB, timestamp: u64) -> AuctionResult {
+        let bids = side_queue(clob, Side::Buy);
+        let asks = side_queue(clob, Side::Sell);
+        
+        let (clearing_price, volume) = match clearing_price(clob, &bids, &asks) {
+            Some(found) => found,
+            None => return AuctionResult::default(),
+        };
+        
+        let buys = allocate_side(&bids, volume);
+        let sells = allocate_side(&asks, volume);
+        
+        // Each pairing closes out at least one fill, so this bounds the trades
+        if clob.trade_ids_left() < (buys.len() + sells.len()) as u64 {
+            return AuctionResult::default();
+        }
+        
+        // Pair fills in priority order
+        let mut trades = Vec::new();
+        let (mut b, mut s) = (0, 0);
+        let (mut buy_left, mut sell_left) = (buys[0].1, sells[0].1);
+        while b < buys.len() && s < sells.len() {
+            let quantity = buy_left.min(sell_left);
+            let (buyer, seller) = (buys[b].0, sells[s].0);
+            trades.push(Trade::new(
+                clob.next_trade_id().expect("trade IDs checked above"),
+                seller.id,
+                buyer.id,
+                seller.user_id,
+                buyer.user_id,
+                clearing_price,
+                quantity,
+                timestamp,
+            )
+            .with_client_tags(seller.client_tag, buyer.client_tag));
+            
+            buy_left -= quantity;
+            sell_left -= quantity;
+            if buy_left == 0 {
+                b += 1;
+                buy_left = buys.get(b).map_or(0, |f| f.1);
+            }
+            if sell_left == 0 {
+                s += 1;
+                sell_left = sells.get(s).map_or(0, |f| f.1);
+            }
+        }
+        
+        for (order, filled) in buys.iter().chain(&sells) {
+            if let Some(resting) = clob.get_key(order.id).and_then(|key| clob.get_order_mut(key)) {
+                resting.record_fill(*filled, clearing_price);
+            }
+            clob.reduce_order(order.id, *filled);
+            if *filled == order.remaining {
+                clob.record_filled(order.id);
+            }
+        }
+        
+        AuctionResult {
+            clearing_price: Some(clearing_price),
+            volume,
+            trades,
+        }
+    }
+}
+
+/// Collect one side's resting orders in price-time priority
+fn side_queue(clob: &CLOB, side: Side) -> SideQueue {
+    let inverse = clob.price_order() == PriceOrder::Inverse;
+    let levels: Vec<_> = match (side, inverse) {
+        (Side::Buy, false) => clob.bids().values().collect(),
+        (Side::Buy, true) => clob.bids().values().rev().collect(),
+        (Side::Sell, false) => clob.asks().values().collect(),
+        (Side::Sell, true) => clob.asks().values().rev().collect(),
+    };
+    
+    levels
+        .into_iter()
+        .filter(|level| !level.is_empty())
+        .map(|level| {
+            let mut orders = Vec::with_capacity(level.order_count);
+            let mut current = level.head;
+            while let Some(node) = current.and_then(|key| clob.orders().get(key)) {
+                orders.push(Resting {
+                    id: node.order.id,
+                    user_id: node.order.user_id,
+                    remaining: node.order.remaining,
+                    client_tag: node.order.client_tag,
+                });
+                current = node.next;
+            }
+            (level.price, orders)
+        })
+        .collect()
+}
+
+/// Find the clearing price and executable volume, if the book crosses
+fn clearing_price(clob: &CLOB, bids: &SideQueue, asks: &SideQueue) -> Option<(u64, u64)> {
+    let level_total = |orders: &[Resting]| -> u64 { orders.iter().map(|o| o.remaining).sum() };
+    let price_order = clob.price_order();
+    
+    let mut candidates: Vec<u64> = bids.iter().chain(asks).map(|(price, _)| *price).collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    
+    // (volume, imbalance, price) of the best candidate so far
+    let mut best: Option<(u64, u64, u64)> = None;
+    for price in candidates {
+        let demand: u64 = bids
+            .iter()
+            .filter(|(bid, _)| price_order.crosses(Side::Buy, *bid, price))
+            .map(|(_, orders)| level_total(orders))
+            .sum();
+        let supply: u64 = asks
+            .iter()
+            .filter(|(ask, _)| price_order.crosses(Side::Sell, *ask, price))
+            .map(|(_, orders)| level_total(orders))
+            .sum();
+        
+        let volume = demand.min(supply);
+        if volume == 0 {
+            continue;
+        }
+        let imbalance = demand.abs_diff(supply);
+        let better = match best {
+            None => true,
+            Some((v, i, _)) => volume > v || (volume == v && imbalance < i),
+        };
+        if better {
+            best = Some((volume, imbalance, price));
+        }
+    }
+    
+    best.map(|(volume, _, price)| (price, volume))
+}
+
+/// Allocate `volume` across one side, best level first, pro-rata at the margin
+fn allocate_side(levels: &SideQueue, volume: u64) -> Vec<(Resting, u64)> {
+    let mut fills = Vec::new();
+    let mut left = volume;
+    
+    for (_, orders) in levels {
+        if left == 0 {
+            break;
+        }
+        let total: u64 = orders.iter().map(|o| o.remaining).sum();
+        if total <= left {
+            fills.extend(orders.iter().map(|o| (*o, o.remaining)));
+            left -= total;
+            continue;
+        }
+        
+        let makers: Vec<(u64, u64)> = orders.iter().map(|o| (o.id, o.remaining)).collect();
+        let shares = allocate_pro_rata(left, &makers);
+        fills.extend(orders.iter().zip(shares).filter(|(_, q)| *q > 0).map(|(o, q)| (*o, q)));
+        left = 0;
+    }
+    
+    fills
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::price::SCALE;
+    use crate::types::Order;
+    
+    #[test]
+    fn test_auction_clears_at_volume_maximizing_price() {
+        let mut clob = CLOB::with_capacity(20);
+        
+        // Demand: 3 @ 102, 2 @ 101, 1 @ 100
+        clob.add_order(Order::new(1, 10, Side::Buy, 102 * SCALE, 3 * SCALE, 0));
+        clob.add_order(Order::new(2, 11, Side::Buy, 101 * SCALE, 2 * SCALE, 0));
+        clob.add_order(Order::new(3, 12, Side::Buy, 100 * SCALE, SCALE, 0));
+        
+        // Supply: 2 @ 99, 2 @ 100, 3 + 1 @ 101
+        clob.add_order(Order::new(4, 20, Side::Sell, 99 * SCALE, 2 * SCALE, 0));
+        clob.add_order(Order::new(5, 21, Side::Sell, 100 * SCALE, 2 * SCALE, 0));
+        clob.add_order(Order::new(6, 22, Side::Sell, 101 * SCALE, 3 * SCALE, 0));
+        clob.add_order(Order::new(7, 23, Side::Sell, 101 * SCALE, SCALE, 0));
+        
+        // Executable: 99 -> 2, 100 -> 4, 101 -> 5, 102 -> 3
+        let result = MatchingEngine::new().run_auction(&mut clob, 42);
+        
+        assert_eq!(result.clearing_price, Some(101 * SCALE));
+        assert_eq!(result.volume, 5 * SCALE);
+        
+        let legs: Vec<_> = result
+            .trades
+            .iter()
+            .map(|t| (t.taker_order_id, t.maker_order_id, t.quantity))
+            .collect();
+        assert_eq!(legs, vec![
+            (1, 4, 2 * SCALE),
+            (1, 5, SCALE),
+            (2, 5, SCALE),
+            // The last unit at 101 splits 3:1 across the two asks
+            (2, 6, 75_000_000),
+            (2, 7, 25_000_000),
+        ]);
+        assert!(result.trades.iter().all(|t| t.price == 101 * SCALE && t.timestamp == 42));
+        
+        // Book is uncrossed with 3 units left at 101
+        assert_eq!(clob.best_bid(), Some(100 * SCALE));
+        assert_eq!(clob.best_ask(), Some(101 * SCALE));
+        assert_eq!(clob.level_summary(Side::Sell, 101 * SCALE).unwrap().total_quantity, 3 * SCALE);
+        assert!(clob.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_auction_uncrossed_book_is_noop() {
+        let mut clob = CLOB::with_capacity(10);
+        clob.add_order(Order::new(1, 10, Side::Buy, 99 * SCALE, SCALE, 0));
+        clob.add_order(Order::new(2, 20, Side::Sell, 100 * SCALE, SCALE, 0));
+        let root = clob.compute_state_root();
+        
+        let result = MatchingEngine::new().run_auction(&mut clob, 1);
+        
+        assert_eq!(result, AuctionResult::default());
+        assert_eq!(clob.compute_state_root(), root);
+    }
+}
@@ -0,0 +1,214 @@
+//! Batch matching with a single deterministic record of the outcome.
+//!
+//! [`MatchingEngine::match_batch`] runs a block's orders in sequence and
+//! returns a [`BatchResult`]: every per-order result, all trades in
+//! execution order, the resulting state root and the BBO before and after.
+//! That is everything a block producer publishes and a verifier checks,
+//! and [`BatchResult::to_bytes`] gives it one canonical encoding.
+//!
+//! ## Encoding
+//!
+//! All integers are little-endian, as in SSZ. An empty BBO side is
+//! written as price and size 0.
+//!
+//! | Field | Size |
+//! |-------|------|
+//! | State root | 32 bytes |
+//! | BBO before: bid price, bid size, ask price, ask size | 4 x u64 |
+//! | BBO after | 4 x u64 |
+//! | Result count | u64 |
+//! | Each result: order (SSZ), flags u8, reject code u8, remaining u64, unfilled u64, trade count u64 | |
+//! | Trades | [`Trade::encode_batch`] |
+//!
+//! Result flags are bit 0 fully filled, bit 1 rested, bit 2 replaced a
+//! resting order, bit 3 changed the BBO. The reject code is 0 for an
+//! accepted order, otherwise 1 + the [`RejectReason`] in declaration order.
+
+use crate::engine::{MatchingEngine, MatchResult, RejectReason};
+use crate::orderbook::{TopOfBook, CLOB};
+use crate::types::{Order, Trade};
+
+/// Everything one `match_batch` call produced, in deterministic order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BatchResult {
+    /// One result per input order, in input order
+    pub results: Vec<MatchResult>,
+    
+    /// Every trade of the batch, in execution order
+    pub trades: Vec<Trade>,
+    
+    /// Book state root after the batch
+    pub state_root: [u8; 32],
+    
+    /// Best bid and ask before the first order
+    pub bbo_before: TopOfBook,
+    
+    /// Best bid and ask after the last order
+    pub bbo_after: TopOfBook,
+}
+
+impl MatchingEngine {
+    /// Match a batch of orders in sequence and collect the full outcome
+    ///
+    /// Equivalent to one [`MatchingEngine::decay_priorities`] pass at
+    /// `timestamp` followed by `match_order` for each order in turn.
+    ///
+    /// # Arguments
+    ///
+    /// * `clob` - The order book
+    /// * `orders` - The batch, in execution order
+    /// * `timestamp` - Call timestamp passed to every match
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let batch = MatchingEngine::new().match_batch(&mut clob, vec![
+    ///     Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0),
+    ///     Order::new(2, 101, Side::Buy, 5_000_000_000_000, 100_000_000, 1),
+    /// ], 2);
+    ///
+    /// assert_eq!(batch.results.len(), 2);
+    /// assert_eq!(batch.trades.len(), 1);
+    /// assert_eq!(batch.state_root, clob.compute_state_root());
+    /// ```
+    pub fn match_batch(
+        &mut self,
+        clob: &mut CLOB,
+        orders: impl IntoIterator<Item = Order>,
+        timestamp: u64,
+    ) -> BatchResult {
+        let bbo_before = clob.top_of_book();
+        
+        // Priority decay is a block-boundary step, applied once up front
+        self.decay_priorities(clob, timestamp);
+        
+        let results: Vec<MatchResult> = orders
+            .into_iter()
+            .map(|order| self.match_order(clob, order, timestamp))
+            .collect();
+        let trades = results.iter().flat_map(|r| r.trades.iter().cloned()).collect();
+        
+        BatchResult {
+            results,
+            trades,
+            state_root: clob.compute_state_root(),
+            bbo_before,
+            bbo_after: clob.top_of_book(),
+        }
+    }
+}
+
+impl BatchResult {
+    /// Encode the batch outcome canonically (see the module docs)
+    ///
+    /// Identical batches on identical books encode to identical bytes, so
+    /// a verifier can compare or hash the encoding directly.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        out.extend_from_slice(&self.state_root);
+        encode_bbo(&mut out, &self.bbo_before);
+        encode_bbo(&mut out, &self.bbo_after);
+        
+        out.extend_from_slice(&(self.results.len() as u64).to_le_bytes());
+        for result in &self.results {
+            let order = ssz_rs::serialize(&result.order).expect("fixed-size Order always serializes");
+            out.extend_from_slice(&order);
+            
+            let flags = result.fully_filled as u8
+                | (result.resting_key.is_some() as u8) << 1
+                | (result.replaced.is_some() as u8) << 2
+                | (result.bbo_changed as u8) << 3;
+            out.push(flags);
+            out.push(reject_code(result.rejected));
+            out.extend_from_slice(&result.remaining.to_le_bytes());
+            out.extend_from_slice(&result.unfilled.to_le_bytes());
+            out.extend_from_slice(&(result.trades.len() as u64).to_le_bytes());
+        }
+        
+        out.extend_from_slice(&Trade::encode_batch(&self.trades));
+        out
+    }
+}
+
+/// Append a BBO as four u64s, 0 for an empty side
+fn encode_bbo(out: &mut Vec<u8>, bbo: &TopOfBook) {
+    for value in [bbo.bid_price, bbo.bid_size, bbo.ask_price, bbo.ask_size] {
+        out.extend_from_slice(&value.unwrap_or(0).to_le_bytes());
+    }
+}
+
+/// Stable wire code for a rejection, 0 for none
+fn reject_code(reason: Option<RejectReason>) -> u8 {
+    match reason {
+        None => 0,
+        Some(RejectReason::StaleTimestamp) => 1,
+        Some(RejectReason::DuplicateOrderId) => 2,
+        Some(RejectReason::SizeTooLarge) => 3,
+        Some(RejectReason::SizeTooSmall) => 4,
+        Some(RejectReason::LevelFull) => 5,
+        Some(RejectReason::ValueOutOfRange) => 6,
+        Some(RejectReason::NoReferencePrice) => 7,
+        Some(RejectReason::SpreadTooNarrow) => 8,
+        Some(RejectReason::TooManyUsers) => 9,
+        Some(RejectReason::NoLiquidity) => 10,
+        Some(RejectReason::OrderIdsExhausted) => 11,
+        Some(RejectReason::TradeIdsExhausted) => 12,
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    
+    fn batch() -> Vec<Order> {
+        vec![
+            Order::new(1, 100, Side::Sell, 5_010_000_000_000, 200_000_000, 1),
+            Order::new(2, 101, Side::Sell, 5_020_000_000_000, 100_000_000, 2),
+            Order::new(3, 102, Side::Buy, 4_990_000_000_000, 150_000_000, 3),
+            Order::new(4, 103, Side::Buy, 5_020_000_000_000, 250_000_000, 4),
+            Order::new(3, 104, Side::Buy, 5_000_000_000_000, 100_000_000, 5),
+            Order::market(5, 105, Side::Sell, 300_000_000, 6),
+        ]
+    }
+    
+    fn run() -> (BatchResult, CLOB) {
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(90, 1, Side::Buy, 4_980_000_000_000, 100_000_000, 0));
+        let mut engine = MatchingEngine::new().with_monotonic_timestamps(true);
+        let result = engine.match_batch(&mut clob, batch(), 1000);
+        (result, clob)
+    }
+    
+    #[test]
+    fn test_batch_result_is_reproducible() {
+        let (first, clob) = run();
+        let (second, _) = run();
+        
+        assert_eq!(first, second);
+        assert_eq!(first.to_bytes(), second.to_bytes());
+        
+        // Contents line up with the book and the per-order results
+        assert_eq!(first.results.len(), 6);
+        assert_eq!(first.results[4].rejected, Some(RejectReason::DuplicateOrderId));
+        assert_eq!(first.trades.len(), first.results.iter().map(|r| r.trades.len()).sum::<usize>());
+        assert!(first.trades.windows(2).all(|w| w[0].id < w[1].id));
+        assert_eq!(first.state_root, clob.compute_state_root());
+        assert_eq!(first.bbo_before.bid_price, Some(4_980_000_000_000));
+        assert_eq!(first.bbo_after, clob.top_of_book());
+        
+        // A different batch encodes differently
+        let mut other = first.clone();
+        other.results[0].remaining -= 1;
+        assert_ne!(other.to_bytes(), first.to_bytes());
+    }
+}
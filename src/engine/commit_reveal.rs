@@ -0,0 +1,242 @@
+//! Commit-reveal intake for sealed-bid batches.
+//!
+//! ## Protocol
+//!
+//! 1. **Commit**: a client submits `Commitment::of(&order, &salt)`, the
+//!    SHA-256 of the order's SSZ encoding followed by a 32-byte salt.
+//! 2. **Reveal**: once commits close, the client sends the order and salt.
+//!    A reveal is accepted only if it hashes to a pending commitment.
+//! 3. **Match**: [`CommitmentBook::match_revealed_batch`] runs every
+//!    revealed order through the engine in *commit* order and discards
+//!    commitments that were never revealed.
+//!
+//! Execution order is fixed when commits close, before any order contents
+//! are public, so nobody can react to a revealed order by jumping ahead
+//! of it. The salt keeps small order spaces from being brute-forced.
+
+use std::collections::HashMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::engine::{MatchingEngine, MatchResult};
+use crate::orderbook::CLOB;
+use crate::types::Order;
+
+/// SHA-256 commitment to an order and salt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Commitment(pub [u8; 32]);
+
+impl Commitment {
+    /// Commit to an order with a client-chosen salt
+    pub fn of(order: &Order, salt: &[u8; 32]) -> Self {
+        let encoded = ssz_rs::serialize(order).expect("fixed-size Order always serializes");
+        
+        let mut hasher = Sha256::new();
+        hasher.update(&encoded);
+        hasher.update(salt);
+        Self(hasher.finalize().into())
+    }
+}
+
+/// Error submitting a commitment or reveal.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SealedOrderError {
+    /// The same commitment was submitted twice in one batch
+    #[error("commitment {} already submitted", hex::encode(.0 .0))]
+    DuplicateCommitment(Commitment),
+    
+    /// The reveal does not hash to any pending commitment
+    #[error("reveal of order {0} matches no commitment")]
+    UnknownCommitment(u64),
+    
+    /// The commitment was already revealed
+    #[error("commitment {} already revealed", hex::encode(.0 .0))]
+    AlreadyRevealed(Commitment),
+}
+
+/// Outcome of matching a sealed batch.
+#[derive(Debug, Clone)]
+pub struct SealedBatchResult {
+    /// One result per revealed order, in commit order
+    pub results: Vec<MatchResult>,
+    
+    /// Commitments that were never revealed, in commit order
+    pub unrevealed: Vec<Commitment>,
+}
+
+/// Collects commitments and reveals for one sealed batch at a time.
+///
+/// ## Example
+///
+/// ```
+/// use dark_hypercore::engine::{Commitment, CommitmentBook, MatchingEngine};
+/// use dark_hypercore::orderbook::CLOB;
+/// use dark_hypercore::types::{Order, Side};
+///
+/// let mut clob = CLOB::with_capacity(100);
+/// let mut engine = MatchingEngine::new();
+/// let mut book = CommitmentBook::new();
+///
+/// let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+/// let salt = [7u8; 32];
+/// book.commit(Commitment::of(&order, &salt)).unwrap();
+/// book.reveal(order, &salt).unwrap();
+///
+/// let batch = book.match_revealed_batch(&mut engine, &mut clob, 1);
+/// assert_eq!(batch.results.len(), 1);
+/// assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+/// ```
+#[derive(Debug, Default)]
+pub struct CommitmentBook {
+    /// Commitments in submission order, with the revealed order if any
+    commitments: Vec<(Commitment, Option<Order>)>,
+    
+    /// Commitment to its position in `commitments`
+    index: HashMap<Commitment, usize>,
+}
+
+impl CommitmentBook {
+    /// Create an empty commitment book
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Submit a commitment for the current batch
+    ///
+    /// # Returns
+    ///
+    /// The commitment's position in the batch's execution order
+    pub fn commit(&mut self, commitment: Commitment) -> Result<usize, SealedOrderError> {
+        if self.index.contains_key(&commitment) {
+            return Err(SealedOrderError::DuplicateCommitment(commitment));
+        }
+        let position = self.commitments.len();
+        self.commitments.push((commitment, None));
+        self.index.insert(commitment, position);
+        Ok(position)
+    }
+    
+    /// Reveal a committed order
+    ///
+    /// # Errors
+    ///
+    /// Rejects a reveal whose order and salt do not hash to a pending
+    /// commitment, or whose commitment was already revealed.
+    pub fn reveal(&mut self, order: Order, salt: &[u8; 32]) -> Result<(), SealedOrderError> {
+        let commitment = Commitment::of(&order, salt);
+        let position = *self
+            .index
+            .get(&commitment)
+            .ok_or(SealedOrderError::UnknownCommitment(order.id))?;
+        
+        let slot = &mut self.commitments[position].1;
+        if slot.is_some() {
+            return Err(SealedOrderError::AlreadyRevealed(commitment));
+        }
+        *slot = Some(order);
+        Ok(())
+    }
+    
+    /// Get the number of commitments in the current batch
+    #[inline]
+    pub fn commitment_count(&self) -> usize {
+        self.commitments.len()
+    }
+    
+    /// Get the number of commitments revealed so far
+    pub fn revealed_count(&self) -> usize {
+        self.commitments.iter().filter(|(_, order)| order.is_some()).count()
+    }
+    
+    /// Match every revealed order in commit order and start a new batch
+    ///
+    /// # Arguments
+    ///
+    /// * `engine` - Engine to match with
+    /// * `clob` - Book to match against
+    /// * `timestamp` - Call timestamp passed to every match
+    pub fn match_revealed_batch(
+        &mut self,
+        engine: &mut MatchingEngine,
+        clob: &mut CLOB,
+        timestamp: u64,
+    ) -> SealedBatchResult {
+        self.index.clear();
+        
+        let mut results = Vec::new();
+        let mut unrevealed = Vec::new();
+        for (commitment, order) in self.commitments.drain(..) {
+            match order {
+                Some(order) => results.push(engine.match_order(clob, order, timestamp)),
+                None => unrevealed.push(commitment),
+            }
+        }
+        
+        SealedBatchResult { results, unrevealed }
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    
+    #[test]
+    fn test_mismatched_reveal_rejected() {
+        let mut book = CommitmentBook::new();
+        let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+        let salt = [1u8; 32];
+        book.commit(Commitment::of(&order, &salt)).unwrap();
+        
+        // Altered price, or the right order with the wrong salt
+        let mut altered = order.clone();
+        altered.price += 1;
+        assert_eq!(book.reveal(altered, &salt), Err(SealedOrderError::UnknownCommitment(1)));
+        assert_eq!(book.reveal(order.clone(), &[2u8; 32]), Err(SealedOrderError::UnknownCommitment(1)));
+        assert_eq!(book.revealed_count(), 0);
+        
+        book.reveal(order.clone(), &salt).unwrap();
+        assert_eq!(
+            book.reveal(order.clone(), &salt),
+            Err(SealedOrderError::AlreadyRevealed(Commitment::of(&order, &salt)))
+        );
+        assert_eq!(
+            book.commit(Commitment::of(&order, &salt)),
+            Err(SealedOrderError::DuplicateCommitment(Commitment::of(&order, &salt)))
+        );
+    }
+    
+    #[test]
+    fn test_valid_reveals_match_in_commit_order() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        let mut book = CommitmentBook::new();
+        
+        let ask = Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0);
+        let bid = Order::new(2, 200, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+        let ghost = Order::new(3, 300, Side::Buy, 5_100_000_000_000, 100_000_000, 0);
+        let (ask_salt, bid_salt, ghost_salt) = ([1u8; 32], [2u8; 32], [3u8; 32]);
+        
+        book.commit(Commitment::of(&ask, &ask_salt)).unwrap();
+        book.commit(Commitment::of(&ghost, &ghost_salt)).unwrap();
+        book.commit(Commitment::of(&bid, &bid_salt)).unwrap();
+        
+        // Reveal order does not change execution order
+        book.reveal(bid, &bid_salt).unwrap();
+        book.reveal(ask, &ask_salt).unwrap();
+        
+        let batch = book.match_revealed_batch(&mut engine, &mut clob, 10);
+        
+        assert_eq!(batch.results.len(), 2);
+        assert!(batch.results[0].trades.is_empty());
+        assert_eq!(batch.results[1].trades.len(), 1);
+        assert_eq!(batch.results[1].trades[0].maker_order_id, 1);
+        assert_eq!(batch.unrevealed, vec![Commitment::of(&ghost, &ghost_salt)]);
+        assert!(clob.is_empty());
+        assert_eq!(book.commitment_count(), 0);
+    }
+}
@@ -0,0 +1,278 @@
+//! Maker/taker fee computation.
+//!
+//! ## Sign Convention
+//!
+//! Fees are signed basis points on trade notional. A positive fee is paid
+//! by the user to the venue; a negative fee is a rebate paid out by the venue.
+//!
+//! ## Rebate Floor
+//!
+//! A maker rebate larger than the taker fee would have the venue pay out more
+//! than it collects. `FeeSchedule` caps the maker rebate so that
+//! `maker_fee + taker_fee >= 0`, either for every trade individually or for
+//! a batch of trades as a whole (see [`RebateFloor`]).
+//!
+//! Only maker rebates are capped. A taker rebate (negative `taker_bps`) is
+//! a deliberate incentive and is paid as configured; it funds nothing, so a
+//! maker rebate on a trade with a rebated taker is cut to zero, and a
+//! positive maker fee is always charged as configured.
+//!
+//! ## Determinism
+//!
+//! All amounts are fixed-point integers (scaled by 10^8, like prices).
+//! Fees are rounded toward zero, and batch clamping walks trades in order.
+
+use crate::types::price::SCALE;
+use crate::types::Trade;
+
+/// Basis points per unit (1 bps = 1 / 10_000)
+pub const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Scope over which the rebate floor `maker_fee + taker_fee >= 0` is enforced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RebateFloor {
+    /// Each trade must be fee-neutral or better on its own (default)
+    #[default]
+    PerTrade,
+    
+    /// Only the totals over a batch must be fee-neutral or better
+    ///
+    /// Rebates are funded in trade order from the fees collected in the
+    /// batch; later rebates are cut once the budget runs out.
+    PerBatch,
+}
+
+/// Fees charged on a single trade, in quote fixed-point units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct TradeFees {
+    /// Fee charged to the maker (negative = rebate)
+    pub maker_fee: i64,
+    
+    /// Fee charged to the taker (negative = rebate)
+    pub taker_fee: i64,
+}
+
+impl TradeFees {
+    /// Net fee collected by the venue (`maker_fee + taker_fee`, saturating)
+    #[inline]
+    pub fn net(&self) -> i64 {
+        self.maker_fee.saturating_add(self.taker_fee)
+    }
+}
+
+/// Maker/taker fee rates with a rebate floor.
+///
+/// ## Example
+///
+/// ```
+/// use dark_hypercore::engine::FeeSchedule;
+/// use dark_hypercore::types::Trade;
+///
+/// // -10 bps maker rebate, 1 bps taker fee
+/// let schedule = FeeSchedule::new(-10, 1);
+///
+/// // 1.0 @ 50000.0 = 50000.0 notional
+/// let trade = Trade::new(1, 1, 2, 100, 200, 5_000_000_000_000, 100_000_000, 0);
+/// let fees = schedule.compute(&trade);
+///
+/// // Taker pays 5.0; the 50.0 rebate is capped at 5.0
+/// assert_eq!(fees.taker_fee, 500_000_000);
+/// assert_eq!(fees.maker_fee, -500_000_000);
+/// assert_eq!(fees.net(), 0);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct FeeSchedule {
+    /// Maker fee in basis points (negative = rebate)
+    pub maker_bps: i64,
+    
+    /// Taker fee in basis points (negative = rebate)
+    pub taker_bps: i64,
+    
+    /// Scope of the rebate floor
+    pub floor: RebateFloor,
+}
+
+impl FeeSchedule {
+    /// Create a fee schedule with a per-trade rebate floor
+    ///
+    /// # Arguments
+    ///
+    /// * `maker_bps` - Maker fee in basis points (negative = rebate)
+    /// * `taker_bps` - Taker fee in basis points (negative = rebate)
+    pub fn new(maker_bps: i64, taker_bps: i64) -> Self {
+        Self {
+            maker_bps,
+            taker_bps,
+            floor: RebateFloor::PerTrade,
+        }
+    }
+    
+    /// Set the scope of the rebate floor
+    pub fn with_floor(mut self, floor: RebateFloor) -> Self {
+        self.floor = floor;
+        self
+    }
+    
+    /// Compute unclamped fees for a trade
+    pub fn raw_fees(&self, trade: &Trade) -> TradeFees {
+        let notional = (trade.notional_raw() / SCALE as u128) as i128;
+        TradeFees {
+            maker_fee: bps_of(notional, self.maker_bps),
+            taker_fee: bps_of(notional, self.taker_bps),
+        }
+    }
+    
+    /// Compute fees for a single trade with the maker rebate capped
+    ///
+    /// The per-trade floor is always applied here, regardless of
+    /// `floor`, since a lone trade is its own batch.
+    ///
+    /// # Returns
+    ///
+    /// Fees whose maker rebate is at most the taker fee collected, so
+    /// `maker_fee + taker_fee >= 0` unless the taker itself is rebated
+    pub fn compute(&self, trade: &Trade) -> TradeFees {
+        let mut fees = self.raw_fees(trade);
+        if fees.maker_fee < 0 {
+            fees.maker_fee = fees.maker_fee.max(-fees.taker_fee.max(0));
+        }
+        fees
+    }
+    
+    /// Compute fees for a batch of trades, applying the configured floor
+    ///
+    /// # Returns
+    ///
+    /// One `TradeFees` per trade, in the same order. Under
+    /// [`RebateFloor::PerTrade`] every entry has a non-negative net; under
+    /// [`RebateFloor::PerBatch`] total maker rebates never exceed the total
+    /// fees collected in the batch.
+    pub fn compute_batch(&self, trades: &[Trade]) -> Vec<TradeFees> {
+        match self.floor {
+            RebateFloor::PerTrade => trades.iter().map(|t| self.compute(t)).collect(),
+            RebateFloor::PerBatch => {
+                let mut fees: Vec<TradeFees> = trades.iter().map(|t| self.raw_fees(t)).collect();
+                
+                // Everything collected in the batch funds the rebates
+                let mut budget: i64 = fees
+                    .iter()
+                    .fold(0i64, |sum, f| sum.saturating_add(f.maker_fee.max(0)).saturating_add(f.taker_fee));
+                
+                for f in fees.iter_mut().filter(|f| f.maker_fee < 0) {
+                    let rebate = (-f.maker_fee).min(budget.max(0));
+                    f.maker_fee = -rebate;
+                    budget -= rebate;
+                }
+                fees
+            }
+        }
+    }
+}
+
+/// Apply a basis-point rate to a notional, rounding toward zero
+///
+/// Notionals near `MAX_VALUE * MAX_VALUE` give fees beyond `i64`; they
+/// saturate at `±i64::MAX` (never `i64::MIN`, so a fee can always be negated).
+#[inline]
+fn bps_of(notional: i128, bps: i64) -> i64 {
+    let fee = notional.saturating_mul(bps as i128) / BPS_DENOMINATOR;
+    fee.clamp(-(i64::MAX as i128), i64::MAX as i128) as i64
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::price::MAX_FIXED;
+    
+    /// 1.0 @ 50000.0 -> 50000.0 notional; 1 bps = 5.0
+    fn trade(id: u64) -> Trade {
+        Trade::new(id, id, id + 1000, 100, 200, 5_000_000_000_000, 100_000_000, 0)
+    }
+    
+    #[test]
+    fn test_standard_schedule_unclamped() {
+        let schedule = FeeSchedule::new(-1, 3);
+        let fees = schedule.compute(&trade(1));
+        
+        assert_eq!(fees.maker_fee, -500_000_000);
+        assert_eq!(fees.taker_fee, 1_500_000_000);
+        assert_eq!(fees.net(), 1_000_000_000);
+    }
+    
+    #[test]
+    fn test_aggressive_rebate_clamped_per_trade() {
+        let schedule = FeeSchedule::new(-10, 1);
+        
+        let raw = schedule.raw_fees(&trade(1));
+        assert!(raw.net() < 0);
+        
+        let fees = schedule.compute(&trade(1));
+        assert_eq!(fees.taker_fee, 500_000_000);
+        assert_eq!(fees.maker_fee, -500_000_000);
+        assert_eq!(fees.net(), 0);
+        
+        for fees in schedule.compute_batch(&[trade(1), trade(2), trade(3)]) {
+            assert!(fees.net() >= 0);
+        }
+    }
+    
+    #[test]
+    fn test_taker_rebate() {
+        // A maker fee is charged as configured, whatever the taker gets
+        let fees = FeeSchedule::new(2, -5).compute(&trade(1));
+        assert_eq!(fees, TradeFees { maker_fee: 1_000_000_000, taker_fee: -2_500_000_000 });
+        
+        // A rebated taker funds no maker rebate
+        let fees = FeeSchedule::new(-1, -5).compute(&trade(1));
+        assert_eq!(fees, TradeFees { maker_fee: 0, taker_fee: -2_500_000_000 });
+    }
+    
+    #[test]
+    fn test_aggressive_rebate_clamped_per_batch() {
+        let schedule = FeeSchedule::new(-10, 1).with_floor(RebateFloor::PerBatch);
+        let trades = [trade(1), trade(2), trade(3)];
+        
+        let fees = schedule.compute_batch(&trades);
+        let net: i64 = fees.iter().map(TradeFees::net).sum();
+        assert_eq!(net, 0);
+        
+        // 15.0 collected funds the first trade's rebate fully, then runs out
+        assert_eq!(fees[0].maker_fee, -1_500_000_000);
+        assert_eq!(fees[1].maker_fee, 0);
+        assert_eq!(fees[2].maker_fee, 0);
+    }
+    
+    #[test]
+    fn test_fees_at_max_value() {
+        // One unit at the largest price still fits exactly
+        let unit = Trade::new(1, 1, 2, 100, 200, MAX_FIXED, SCALE, 0);
+        let fees = FeeSchedule::new(-10, 10).compute(&unit);
+        assert_eq!(fees.taker_fee, (MAX_FIXED / 1_000) as i64);
+        assert_eq!(fees.net(), 0);
+        
+        // MAX_VALUE at MAX_VALUE saturates instead of wrapping
+        let extreme = Trade::new(2, 1, 2, 100, 200, MAX_FIXED, MAX_FIXED, 0);
+        let fees = FeeSchedule::new(-10, 10).compute(&extreme);
+        assert_eq!(fees, TradeFees { maker_fee: -i64::MAX, taker_fee: i64::MAX });
+        assert_eq!(FeeSchedule::new(i64::MAX, 1).raw_fees(&extreme).maker_fee, i64::MAX);
+        
+        let batch = FeeSchedule::new(-10, 10).with_floor(RebateFloor::PerBatch).compute_batch(&[extreme.clone(), extreme]);
+        assert_eq!(batch[0].net(), 0);
+        assert!(batch.iter().map(|f| f.net()).all(|net| net >= 0));
+    }
+    
+    #[test]
+    fn test_per_batch_leaves_affordable_rebates() {
+        let schedule = FeeSchedule::new(-1, 3).with_floor(RebateFloor::PerBatch);
+        let trades = [trade(1), trade(2)];
+        
+        assert_eq!(
+            schedule.compute_batch(&trades),
+            vec![schedule.raw_fees(&trades[0]), schedule.raw_fees(&trades[1])]
+        );
+    }
+}
@@ -6,10 +6,16 @@
 //!
 //! 1. Get the best opposite price level
 //! 2. Check if prices are compatible for matching
-//! 3. Match against orders at that level (FIFO)
+//! 3. Match against orders at that level in queue order (FIFO by default)
 //! 4. Continue until no more matches or order is filled
 //! 5. If remaining quantity, add to book (limit orders)
 //!
+//! Time priority belongs to a resting order, not to a client's intent.
+//! Anything that re-adds size to the book (a re-submitted order, or a
+//! future hidden-size order replenishing its visible slice) enqueues a
+//! new order at the tail of its level, behind everything that arrived
+//! while the previous one rested.
+//!
 //! ## Determinism
 //!
 //! Critical for consensus - the matching engine must be 100% deterministic:
@@ -21,16 +27,80 @@
 //! - Synchronous execution only
 
 use std::cmp::Reverse;
-use crate::orderbook::CLOB;
-use crate::types::{Order, Side, Trade};
+use std::collections::{HashMap, VecDeque};
+use crate::orderbook::level::decrement_count;
+use crate::orderbook::{BookEvent, CLOB, PriceLevel, PriceOrder};
+use crate::types::price::{midpoint, mul_div, MAX_FIXED, MAX_NOTIONAL, SCALE};
+use crate::types::{AnonTrade, Order, OrderType, Side, Trade};
+
+/// Reason an incoming order was rejected before matching.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RejectReason {
+    /// Order timestamp is earlier than the last accepted order's timestamp
+    StaleTimestamp,
+    
+    /// An order with the same ID is still resting on the book (and cannot
+    /// be replaced: replacement is off, or the order belongs to another user)
+    DuplicateOrderId,
+    
+    /// Order quantity is above the engine's maximum
+    SizeTooLarge,
+    
+    /// Order quantity is below the engine's minimum
+    SizeTooSmall,
+    
+    /// The order's price level already holds `CLOB::max_level_orders` orders
+    LevelFull,
+    
+    /// Price or quantity is above `price::MAX_FIXED`, or the notional is
+    /// above `price::MAX_NOTIONAL`
+    ValueOutOfRange,
+    
+    /// A pegged order has no reference price and the book has no mid
+    NoReferencePrice,
+    
+    /// A resting order would narrow the spread below the engine's minimum
+    SpreadTooNarrow,
+    
+    /// The order is from a new user and the book already holds orders
+    /// from the engine's maximum number of distinct users
+    TooManyUsers,
+    
+    /// A market order found the opposite side of the book empty
+    NoLiquidity,
+    
+    /// The order needs an auto-assigned ID and the book's ID range is used up
+    OrderIdsExhausted,
+    
+    /// The order could trade more times than the book has trade IDs left
+    TradeIdsExhausted,
+}
+
+/// Which timestamp a generated `Trade` records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TradeTimestampSource {
+    /// The `timestamp` argument passed to `match_order` (default)
+    #[default]
+    Call,
+    
+    /// The incoming (taker) order's timestamp
+    Taker,
+    
+    /// The resting (maker) order's timestamp
+    Maker,
+}
 
 /// Result of matching a single order against the book.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MatchResult {
     /// The original order (may be modified with filled quantity)
     pub order: Order,
     
-    /// Trades generated by the match
+    /// Trades generated by the match, in execution order
+    ///
+    /// Best price first, then queue order within each level (FIFO unless
+    /// the book is LIFO), with ascending trade IDs. Trade roots and
+    /// downstream consumers rely on this order.
     pub trades: Vec<Trade>,
     
     /// Whether the order was fully filled
@@ -41,6 +111,46 @@ pub struct MatchResult {
     
     /// Slab key if the order was added to the book (partial/no fill)
     pub resting_key: Option<usize>,
+    
+    /// Set if the order was rejected before reaching the book
+    pub rejected: Option<RejectReason>,
+    
+    /// Quantity discarded without trading or resting
+    ///
+    /// For market orders, whatever was left when the book ran out. With a
+    /// lot size configured, also the taker's sub-lot remainder. With a
+    /// minimum spread, the remainder of a crossing order that would have
+    /// rested inside it.
+    pub unfilled: u64,
+    
+    /// Resting order cancelled because this order replaced it
+    pub replaced: Option<Order>,
+    
+    /// Total improvement versus the taker's limit, in quote fixed-point
+    ///
+    /// Sum over trades of `|limit - execution price| * quantity`, scaled
+    /// back by 10^8. Always 0 for market orders, which have no limit.
+    pub price_improvement: u64,
+    
+    /// One entry per trade, in trade order, with the maker's lifetime totals
+    pub maker_fills: Vec<MakerFill>,
+    
+    /// Matching work done: maker fills, price levels matched against, and
+    /// retained empty levels dropped on the way
+    ///
+    /// Counted as the sweep runs. A machine-independent cost measure for
+    /// tests and profiling; the same order against the same book always
+    /// takes the same steps.
+    pub steps: usize,
+    
+    /// Price levels traded against, including a partially consumed last one
+    pub levels_consumed: usize,
+    
+    /// Whether the best bid or best ask price differs after this order
+    ///
+    /// Sizes at an unchanged touch do not count. Always false for a
+    /// rejected order.
+    pub bbo_changed: bool,
 }
 
 impl MatchResult {
@@ -52,6 +162,14 @@ impl MatchResult {
             fully_filled: true,
             remaining: 0,
             resting_key: None,
+            rejected: None,
+            unfilled: 0,
+            replaced: None,
+            price_improvement: 0,
+            maker_fills: Vec::new(),
+            steps: 0,
+            levels_consumed: 0,
+            bbo_changed: false,
         }
     }
     
@@ -63,6 +181,14 @@ impl MatchResult {
             fully_filled: false,
             remaining,
             resting_key,
+            rejected: None,
+            unfilled: 0,
+            replaced: None,
+            price_improvement: 0,
+            maker_fills: Vec::new(),
+            steps: 0,
+            levels_consumed: 0,
+            bbo_changed: false,
         }
     }
     
@@ -75,17 +201,213 @@ impl MatchResult {
             fully_filled: false,
             remaining,
             resting_key: Some(resting_key),
+            rejected: None,
+            unfilled: 0,
+            replaced: None,
+            price_improvement: 0,
+            maker_fills: Vec::new(),
+            steps: 0,
+            levels_consumed: 0,
+            bbo_changed: false,
+        }
+    }
+    
+    /// Create a new match result for a market order that exhausted the book
+    fn exhausted(order: Order, trades: Vec<Trade>, remaining: u64) -> Self {
+        Self {
+            order,
+            trades,
+            fully_filled: false,
+            remaining,
+            resting_key: None,
+            rejected: None,
+            unfilled: remaining,
+            replaced: None,
+            price_improvement: 0,
+            maker_fills: Vec::new(),
+            steps: 0,
+            levels_consumed: 0,
+            bbo_changed: false,
+        }
+    }
+    
+    /// Create a new match result for a rejected order (book untouched)
+    fn rejected(order: Order, reason: RejectReason) -> Self {
+        let remaining = order.remaining;
+        Self {
+            order,
+            trades: Vec::new(),
+            fully_filled: false,
+            remaining,
+            resting_key: None,
+            rejected: Some(reason),
+            unfilled: 0,
+            replaced: None,
+            price_improvement: 0,
+            maker_fills: Vec::new(),
+            steps: 0,
+            levels_consumed: 0,
+            bbo_changed: false,
         }
     }
+    
+    /// Check if the order was rejected
+    #[inline]
+    pub fn is_rejected(&self) -> bool {
+        self.rejected.is_some()
+    }
+    
+    /// Get this match's trades with counterparties stripped, for a public tape
+    ///
+    /// The incoming order is the aggressor on every trade.
+    pub fn anonymized_trades(&self) -> Vec<AnonTrade> {
+        let aggressor = self.order.side();
+        self.trades.iter().map(|trade| trade.anonymized(aggressor)).collect()
+    }
+}
+
+/// A maker's fill in one match, with its lifetime totals after the fill.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MakerFill {
+    /// Maker order ID
+    pub order_id: u64,
+    
+    /// Quantity filled by this trade (fixed-point)
+    pub filled: u64,
+    
+    /// Lifetime filled quantity, including this trade (fixed-point)
+    pub cumulative_filled: u64,
+    
+    /// Lifetime notional, including this trade (scaled by 10^16)
+    pub cumulative_notional: u128,
+}
+
+/// Trades, fills and work counts collected while sweeping one side.
+#[derive(Debug, Default)]
+struct Sweep {
+    trades: Vec<Trade>,
+    maker_fills: Vec<MakerFill>,
+    levels_consumed: usize,
+    steps: usize,
+}
+
+/// Every deterministic field of a [`MatchingEngine`].
+///
+/// Trade and order ID counters live in the `CLOB` and travel with its
+/// snapshot; this captures the engine's configuration and sequencing
+/// guards so a restored engine behaves exactly like the original.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EngineState {
+    /// Reject orders whose timestamp goes backwards
+    pub enforce_monotonic_timestamps: bool,
+    
+    /// Timestamp of the last accepted order
+    pub last_timestamp: Option<u64>,
+    
+    /// Which timestamp generated trades record
+    pub trade_timestamp_source: TradeTimestampSource,
+    
+    /// Treat an order whose ID is still resting as a cancel/replace
+    pub replace_on_duplicate: bool,
+    
+    /// Minimum tradable increment (fixed-point), 0 if disabled
+    pub lot_size: u64,
+    
+    /// Re-run every match on a scratch copy and compare (debug aid)
+    pub determinism_audit: bool,
+    
+    /// Largest accepted order quantity (fixed-point), if limited
+    pub max_order_quantity: Option<u64>,
+    
+    /// Smallest accepted order quantity (fixed-point), if limited
+    pub min_order_quantity: Option<u64>,
+    
+    /// Reject prices and quantities above `price::MAX_FIXED`
+    pub enforce_value_range: bool,
+    
+    /// External reference price for pegged orders (fixed-point), if set
+    pub reference_price: Option<u64>,
+    
+    /// How many recent trades to retain for per-order lookup (0 = off)
+    pub trade_history: usize,
+    
+    /// Smallest spread a non-crossing order may leave (fixed-point), 0 if disabled
+    pub min_spread: u64,
+    
+    /// Most distinct users with resting orders, if limited
+    pub max_users: Option<usize>,
+    
+    /// Reject market orders against an empty opposite side
+    pub reject_market_without_liquidity: bool,
+    
+    /// Log every book change as a `BookEvent`
+    pub book_events: bool,
 }
 
 /// Deterministic order matching engine.
 ///
-/// The matching engine is stateless - all state is stored in the CLOB.
+/// All book state is stored in the CLOB. The engine only holds
+/// configuration and the sequencing guards that configuration enables.
 /// This design ensures determinism and simplifies testing.
 #[derive(Debug, Default)]
 pub struct MatchingEngine {
-    // No state - all state is in the CLOB
+    /// Reject orders whose timestamp goes backwards (opt-in)
+    enforce_monotonic_timestamps: bool,
+    
+    /// Timestamp of the last accepted order
+    last_timestamp: Option<u64>,
+    
+    /// Which timestamp generated trades record
+    trade_timestamp_source: TradeTimestampSource,
+    
+    /// Treat an order whose ID is still resting as a cancel/replace
+    replace_on_duplicate: bool,
+    
+    /// Minimum tradable increment (fixed-point), 0 if disabled
+    lot_size: u64,
+    
+    /// Re-run every match on a scratch copy and compare (debug aid)
+    determinism_audit: bool,
+    
+    /// Largest accepted order quantity (fixed-point), if limited
+    max_order_quantity: Option<u64>,
+    
+    /// Smallest accepted order quantity (fixed-point), if limited
+    min_order_quantity: Option<u64>,
+    
+    /// Reject prices and quantities above `price::MAX_FIXED`
+    enforce_value_range: bool,
+    
+    /// External reference price for pegged orders (fixed-point), if set
+    reference_price: Option<u64>,
+    
+    /// How many recent trades to retain for per-order lookup (0 = off)
+    trade_history: usize,
+    
+    /// Smallest spread a non-crossing order may leave (fixed-point), 0 if disabled
+    min_spread: u64,
+    
+    /// Most distinct users with resting orders, if limited
+    max_users: Option<usize>,
+    
+    /// Reject market orders against an empty opposite side
+    reject_market_without_liquidity: bool,
+    
+    /// Log every book change as a `BookEvent`
+    book_events: bool,
+    
+    /// Logged book changes not yet taken, oldest first
+    events: Vec<BookEvent>,
+    
+    /// Recently executed trades, oldest first
+    history: VecDeque<Trade>,
+    
+    /// Number of trades ever recorded into `history`
+    history_total: u64,
+    
+    /// Order ID to the history positions (counted from the first trade
+    /// ever recorded) of the retained trades it took part in, oldest first
+    history_index: HashMap<u64, VecDeque<u64>>,
 }
 
 impl MatchingEngine {
@@ -94,25 +416,46 @@ impl MatchingEngine {
         Self::default()
     }
     
-    /// Process an incoming order against the book.
+    /// Enable or disable monotonic timestamp enforcement
     ///
-    /// # Arguments
+    /// When enabled, an order whose `timestamp` is strictly less than the
+    /// last accepted order's timestamp is rejected with
+    /// [`RejectReason::StaleTimestamp`]. Equal timestamps are accepted.
     ///
-    /// * `clob` - The order book
-    /// * `incoming` - The incoming order
-    /// * `timestamp` - Current timestamp (for trade records)
+    /// # Example
     ///
-    /// # Returns
+    /// ```
+    /// use dark_hypercore::engine::{MatchingEngine, RejectReason};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
     ///
-    /// A `MatchResult` containing trades and order status
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_monotonic_timestamps(true);
     ///
-    /// # Matching Rules
+    /// engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 20), 0);
+    /// let result = engine.match_order(&mut clob, Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 10), 0);
     ///
-    /// 1. Buy orders match against asks (low to high)
-    /// 2. Sell orders match against bids (high to low)
-    /// 3. Price-time priority (FIFO at same price)
-    /// 4. Partial fills allowed
-    /// 5. Unfilled quantity rests on book (limit orders)
+    /// assert_eq!(result.rejected, Some(RejectReason::StaleTimestamp));
+    /// ```
+    pub fn with_monotonic_timestamps(mut self, enabled: bool) -> Self {
+        self.enforce_monotonic_timestamps = enabled;
+        self
+    }
+    
+    /// Treat a resubmitted order ID as a cancel/replace
+    ///
+    /// When an incoming order carries the ID of an order still resting on
+    /// the book, the default is to reject it with
+    /// [`RejectReason::DuplicateOrderId`]. With replacement enabled, a
+    /// replace is a cancel of the resting order followed by a submit of the
+    /// new one through the full match path: it loses the old time priority,
+    /// and trades immediately if its new price is marketable. The cancelled
+    /// order is returned in `MatchResult::replaced`.
+    ///
+    /// The replacement keeps the original's identity: it must come from the
+    /// same `user_id` (another user's resubmitted ID is still rejected as a
+    /// duplicate), and if its `client_tag` is unset (all zeros) it inherits
+    /// the original's tag, so the resulting fills stay attributable.
     ///
     /// # Example
     ///
@@ -122,624 +465,3201 @@ impl MatchingEngine {
     /// use dark_hypercore::types::{Order, Side};
     ///
     /// let mut clob = CLOB::with_capacity(100);
-    /// let mut engine = MatchingEngine::new();
+    /// let mut engine = MatchingEngine::new().with_replace_on_duplicate(true);
     ///
-    /// // Add resting order
-    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    /// engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    /// let result = engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 110_000_000, 100_000_000, 0), 0);
     ///
-    /// // Match incoming order
-    /// let buy = Order::new(2, 101, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
-    /// let result = engine.match_order(&mut clob, buy, 0);
+    /// assert!(result.replaced.is_some());
+    /// assert_eq!(clob.best_bid(), Some(110_000_000));
+    /// ```
+    pub fn with_replace_on_duplicate(mut self, enabled: bool) -> Self {
+        self.replace_on_duplicate = enabled;
+        self
+    }
+    
+    /// Choose which timestamp generated trades record
+    ///
+    /// Defaults to [`TradeTimestampSource::Call`], the timestamp passed to
+    /// `match_order`.
+    ///
+    /// # Example
     ///
-    /// assert!(result.fully_filled);
     /// ```
-    pub fn match_order(&mut self, clob: &mut CLOB, mut incoming: Order, timestamp: u64) -> MatchResult {
-        let mut trades = Vec::new();
-        let mut remaining = incoming.remaining;
-        
-        // Match based on order side
-        match incoming.side() {
-            Side::Buy => {
-                // Buy orders match against asks (lowest price first)
-                remaining = self.match_against_asks(clob, &incoming, remaining, timestamp, &mut trades);
-            }
-            Side::Sell => {
-                // Sell orders match against bids (highest price first)
-                remaining = self.match_against_bids(clob, &incoming, remaining, timestamp, &mut trades);
-            }
+    /// use dark_hypercore::engine::{MatchingEngine, TradeTimestampSource};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_trade_timestamp_source(TradeTimestampSource::Taker);
+    ///
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 100_000_000, 100_000_000, 10));
+    /// let result = engine.match_order(&mut clob, Order::new(2, 101, Side::Buy, 100_000_000, 100_000_000, 20), 30);
+    ///
+    /// assert_eq!(result.trades[0].timestamp, 20);
+    /// ```
+    pub fn with_trade_timestamp_source(mut self, source: TradeTimestampSource) -> Self {
+        self.trade_timestamp_source = source;
+        self
+    }
+    
+    /// Only trade in whole multiples of `lot_size` (fixed-point, 0 disables)
+    ///
+    /// The incoming order's sub-lot remainder is split off before matching
+    /// and never trades or rests: a limit order rests only its whole lots,
+    /// and the remainder of any order is reported in `MatchResult::unfilled`.
+    /// Orders rested through the engine are therefore whole lots, so every
+    /// fill between them is too. Orders added directly with `CLOB::add_order`
+    /// bypass the check and should already be lot multiples.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_lot_size(1_000_000); // 0.01
+    ///
+    /// // 1.005 buys 1.00 and discards 0.005
+    /// let result = engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, 100_000_000, 100_500_000, 0), 0);
+    ///
+    /// assert_eq!(result.unfilled, 500_000);
+    /// assert_eq!(clob.level_summary(Side::Buy, 100_000_000).unwrap().total_quantity, 100_000_000);
+    /// ```
+    pub fn with_lot_size(mut self, lot_size: u64) -> Self {
+        self.lot_size = lot_size;
+        self
+    }
+    
+    /// Get the configured lot size (0 if disabled)
+    #[inline]
+    pub fn lot_size(&self) -> u64 {
+        self.lot_size
+    }
+    
+    /// Check whether resting `order` now would improve its side's best
+    /// price to within `min_spread` of a non-crossing opposite best
+    fn narrows_spread(&self, clob: &CLOB, order: &Order) -> bool {
+        if self.min_spread == 0 {
+            return false;
         }
+        let (own, opposite) = match order.side() {
+            Side::Buy => (clob.best_bid(), clob.best_ask()),
+            Side::Sell => (clob.best_ask(), clob.best_bid()),
+        };
+        let Some(opposite) = opposite else {
+            return false;
+        };
         
-        // Update the incoming order's remaining quantity
-        incoming.remaining = remaining;
-        
-        // Determine result based on remaining quantity
-        if remaining == 0 {
-            // Fully filled
-            MatchResult::fully_filled(incoming, trades)
-        } else {
-            // Partial or no fill - add to book if limit order
-            let resting_key = clob.add_order(incoming.clone());
-            
-            if trades.is_empty() {
-                MatchResult::no_match(incoming, resting_key)
-            } else {
-                MatchResult::partial_fill(incoming, trades, remaining, Some(resting_key))
-            }
-        }
+        // Improving means beating the touch: a same-price order crossing into us
+        let price_order = clob.price_order();
+        let improves = own.is_none_or(|own| own != order.price && price_order.crosses(order.side().opposite(), own, order.price));
+        improves
+            && !price_order.crosses(order.side(), order.price, opposite)
+            && order.price.abs_diff(opposite) < self.min_spread
     }
     
-    /// Match a buy order against asks (lowest price first)
-    fn match_against_asks(
-        &mut self,
-        clob: &mut CLOB,
-        incoming: &Order,
-        mut remaining: u64,
-        timestamp: u64,
-        trades: &mut Vec<Trade>,
-    ) -> u64 {
-        let incoming_price = incoming.price;
-        
-        // Process price levels until no more matches
-        while remaining > 0 {
-            // Get best ask price
-            let best_ask_price = match clob.asks().keys().next().copied() {
-                Some(p) => p,
-                None => break, // No more asks
-            };
-            
-            // Check if price is compatible
-            // Buy matches if book price <= incoming price
-            if best_ask_price > incoming_price {
-                break; // No more compatible prices
-            }
-            
-            // Collect matching info from this price level
-            let matches = self.collect_matches_at_ask_level(clob, remaining, best_ask_price);
-            
-            if matches.is_empty() {
-                break;
-            }
-            
-            // Execute the matches
-            for (key, order_id, maker_user_id, fill_qty, fully_filled) in matches {
-                // Create trade record
-                let trade_id = clob.next_trade_id();
-                let trade = Trade::new(
-                    trade_id,
-                    order_id,               // maker_order_id
-                    incoming.id,            // taker_order_id
-                    maker_user_id,          // maker_user_id
-                    incoming.user_id,       // taker_user_id
-                    best_ask_price,         // execution price
-                    fill_qty,               // quantity
-                    timestamp,
-                );
-                trades.push(trade);
-                
-                // Update remaining
-                remaining -= fill_qty;
-                
-                // Fill the maker order
-                if let Some(node) = clob.orders_mut().get_mut(key) {
-                    node.fill(fill_qty);
-                }
-                
-                // Update price level quantity
-                if let Some(level) = clob.asks_mut().get_mut(&best_ask_price) {
-                    level.reduce_quantity(fill_qty);
-                }
-                
-                // Remove fully filled orders
-                if fully_filled {
-                    self.remove_ask_order(clob, key, order_id, best_ask_price);
-                }
-            }
-            
-            // Remove empty price level
-            if let Some(level) = clob.asks().get(&best_ask_price) {
-                if level.is_empty() {
-                    clob.remove_ask_level(best_ask_price);
-                }
-            }
+    /// Round a quantity down to a whole number of lots
+    #[inline]
+    fn round_to_lot(&self, quantity: u64) -> u64 {
+        if self.lot_size == 0 {
+            quantity
+        } else {
+            quantity - quantity % self.lot_size
         }
-        
-        remaining
     }
     
-    /// Match a sell order against bids (highest price first)
-    fn match_against_bids(
-        &mut self,
-        clob: &mut CLOB,
-        incoming: &Order,
-        mut remaining: u64,
-        timestamp: u64,
-        trades: &mut Vec<Trade>,
-    ) -> u64 {
-        let incoming_price = incoming.price;
+    /// Reject orders with a quantity above `max` (fixed-point)
+    ///
+    /// Oversized orders are rejected with [`RejectReason::SizeTooLarge`]
+    /// before they touch the book. The check uses the order's original
+    /// `quantity`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::{MatchingEngine, RejectReason};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_max_order_quantity(1_000_000_000); // 10.0
+    ///
+    /// let result = engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, 100_000_000, 2_000_000_000, 0), 0);
+    ///
+    /// assert_eq!(result.rejected, Some(RejectReason::SizeTooLarge));
+    /// assert!(clob.is_empty());
+    /// ```
+    pub fn with_max_order_quantity(mut self, max: u64) -> Self {
+        self.max_order_quantity = Some(max);
+        self
+    }
+    
+    /// Reject orders with a quantity below `min` (fixed-point)
+    ///
+    /// Undersized orders are rejected with [`RejectReason::SizeTooSmall`]
+    /// before they touch the book.
+    pub fn with_min_order_quantity(mut self, min: u64) -> Self {
+        self.min_order_quantity = Some(min);
+        self
+    }
+    
+    /// Reject orders whose price or quantity exceeds [`MAX_FIXED`], or
+    /// whose notional exceeds [`MAX_NOTIONAL`]
+    ///
+    /// Values above `price::MAX_VALUE` whole units cannot round-trip
+    /// through the decimal helpers and leave no headroom in notional and
+    /// state-root math. The notional is `quantity` times the highest price
+    /// the order could trade at: the limit price for a buy (the worst ask
+    /// for a market buy), and the higher of the limit price and best bid
+    /// for a sell. Keeping it within [`MAX_NOTIONAL`] keeps every fee on
+    /// the order's trades within `i64`. With the check enabled such orders
+    /// are rejected with [`RejectReason::ValueOutOfRange`] instead of
+    /// resting.
+    ///
+    /// [`MAX_FIXED`]: crate::types::price::MAX_FIXED
+    /// [`MAX_NOTIONAL`]: crate::types::price::MAX_NOTIONAL
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::{MatchingEngine, RejectReason};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::price::MAX_FIXED;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_value_range_check(true);
+    ///
+    /// let result = engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, MAX_FIXED + 1, 100_000_000, 0), 0);
+    /// assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+    /// ```
+    pub fn with_value_range_check(mut self, enabled: bool) -> Self {
+        self.enforce_value_range = enabled;
+        self
+    }
+    
+    /// Reject orders that would rest inside a minimum spread
+    ///
+    /// The check applies to the price an order would rest at, against the
+    /// opposite best left once it has traded, and only when resting would
+    /// improve its own side's best price: joining or backing an existing
+    /// touch never narrows the spread. `min_spread` is in fixed-point price
+    /// units (e.g. two ticks; 0 disables), and an empty opposite side
+    /// imposes no limit. This stops quotes being stuffed up to a locked
+    /// market.
+    ///
+    /// A limit order that does not cross is rejected with
+    /// [`RejectReason::SpreadTooNarrow`]. A crossing order trades as usual,
+    /// but a remainder that would rest inside the spread is discarded into
+    /// `MatchResult::unfilled` instead.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::{MatchingEngine, RejectReason};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 200_000_000, 100_000_000, 0));
+    /// let mut engine = MatchingEngine::new().with_min_spread(10_000_000);
+    ///
+    /// let result = engine.match_order(&mut clob, Order::new(2, 101, Side::Buy, 195_000_000, 100_000_000, 0), 0);
+    /// assert_eq!(result.rejected, Some(RejectReason::SpreadTooNarrow));
+    /// ```
+    pub fn with_min_spread(mut self, min_spread: u64) -> Self {
+        self.min_spread = min_spread;
+        self
+    }
+    
+    /// Cap the number of distinct users with resting orders
+    ///
+    /// Once orders from `max` users rest on the book, a limit order from
+    /// any other user is rejected with [`RejectReason::TooManyUsers`].
+    /// Users already resting keep trading, and a slot frees up when a
+    /// user's last order leaves the book. Market orders never rest and
+    /// are not limited.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::{MatchingEngine, RejectReason};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_max_users(1);
+    ///
+    /// engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    /// let result = engine.match_order(&mut clob, Order::new(2, 200, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    ///
+    /// assert_eq!(result.rejected, Some(RejectReason::TooManyUsers));
+    /// ```
+    pub fn with_max_users(mut self, max: usize) -> Self {
+        self.max_users = Some(max);
+        self
+    }
+    
+    /// Choose how a market order against an empty opposite side ends
+    ///
+    /// By default (`false`) it is accepted and returns a zero-fill result
+    /// with its whole quantity in `unfilled`. When enabled it is rejected
+    /// with [`RejectReason::NoLiquidity`] instead. Either way the book is
+    /// unchanged. Only an empty side counts; a market order that finds
+    /// some liquidity fills what it can as usual.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::{MatchingEngine, RejectReason};
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_market_liquidity_check(true);
+    ///
+    /// let result = engine.match_order(&mut clob, Order::market(1, 100, Side::Buy, 100_000_000, 0), 0);
+    /// assert_eq!(result.rejected, Some(RejectReason::NoLiquidity));
+    /// ```
+    pub fn with_market_liquidity_check(mut self, enabled: bool) -> Self {
+        self.reject_market_without_liquidity = enabled;
+        self
+    }
+    
+    /// Enable or disable the determinism audit
+    ///
+    /// When enabled, every `match_order` first runs on a copy of the book
+    /// and of the engine's state, then for real, and panics unless both
+    /// runs produce the same `MatchResult`, state root and engine state.
+    /// The copy's hash maps are rebuilt with fresh hashers, so results
+    /// that depend on hash-map iteration order show up as a mismatch.
+    /// Each match copies the whole book, so keep it off in production.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_determinism_audit(true);
+    ///
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 100_000_000, 100_000_000, 0));
+    /// let result = engine.match_order(&mut clob, Order::new(2, 101, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    ///
+    /// assert!(result.fully_filled);
+    /// ```
+    pub fn with_determinism_audit(mut self, enabled: bool) -> Self {
+        self.determinism_audit = enabled;
+        self
+    }
+    
+    /// Retain the last `capacity` trades for [`MatchingEngine::trades_for_order`]
+    ///
+    /// Trades from `match_order`, `match_pegged` and `uncross` are recorded
+    /// and indexed by maker and taker order ID; the oldest is evicted once
+    /// `capacity` are held. 0 (the default) turns the history off. The
+    /// configured capacity is part of [`EngineState`], the retained trades
+    /// are not.
+    pub fn with_trade_history(mut self, capacity: usize) -> Self {
+        self.trade_history = capacity;
+        self.history = VecDeque::with_capacity(capacity);
+        self
+    }
+    
+    /// Get the retained trades an order took part in, as maker or taker
+    ///
+    /// Trades are returned oldest first. Only trades still in the history
+    /// (see [`MatchingEngine::with_trade_history`]) are found.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_trade_history(1_000);
+    ///
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 100_000_000, 200_000_000, 0));
+    /// engine.match_order(&mut clob, Order::new(2, 101, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    /// engine.match_order(&mut clob, Order::new(3, 102, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    ///
+    /// assert_eq!(engine.trades_for_order(1).len(), 2);
+    /// assert_eq!(engine.trades_for_order(3)[0].maker_order_id, 1);
+    /// ```
+    pub fn trades_for_order(&self, order_id: u64) -> Vec<&Trade> {
+        let first = self.history_total - self.history.len() as u64;
+        self.history_index
+            .get(&order_id)
+            .map(|positions| positions.iter().map(|&p| &self.history[(p - first) as usize]).collect())
+            .unwrap_or_default()
+    }
+    
+    /// Append trades to the history, evicting the oldest past capacity
+    fn record_trades(&mut self, trades: &[Trade]) {
+        if self.trade_history == 0 {
+            return;
+        }
         
-        // Process price levels until no more matches
-        while remaining > 0 {
-            // Get best bid price
-            let best_bid_price = match clob.bids().keys().next().map(|r| r.0) {
-                Some(p) => p,
-                None => break, // No more bids
-            };
-            
-            // Check if price is compatible
-            // Sell matches if book price >= incoming price
-            if best_bid_price < incoming_price {
-                break; // No more compatible prices
+        for trade in trades {
+            if self.history.len() == self.trade_history {
+                if let Some(oldest) = self.history.pop_front() {
+                    // The oldest trade is the first entry of each of its orders
+                    for order_id in trade_orders(&oldest) {
+                        if let Some(positions) = self.history_index.get_mut(&order_id) {
+                            positions.pop_front();
+                            if positions.is_empty() {
+                                self.history_index.remove(&order_id);
+                            }
+                        }
+                    }
+                }
             }
             
-            // Collect matching info from this price level
-            let matches = self.collect_matches_at_bid_level(clob, remaining, best_bid_price);
-            
-            if matches.is_empty() {
-                break;
+            for order_id in trade_orders(trade) {
+                self.history_index.entry(order_id).or_default().push_back(self.history_total);
             }
-            
-            // Execute the matches
-            for (key, order_id, maker_user_id, fill_qty, fully_filled) in matches {
-                // Create trade record
-                let trade_id = clob.next_trade_id();
-                let trade = Trade::new(
-                    trade_id,
-                    order_id,               // maker_order_id
-                    incoming.id,            // taker_order_id
-                    maker_user_id,          // maker_user_id
-                    incoming.user_id,       // taker_user_id
-                    best_bid_price,         // execution price
-                    fill_qty,               // quantity
-                    timestamp,
-                );
-                trades.push(trade);
-                
-                // Update remaining
-                remaining -= fill_qty;
-                
-                // Fill the maker order
-                if let Some(node) = clob.orders_mut().get_mut(key) {
-                    node.fill(fill_qty);
-                }
-                
-                // Update price level quantity
-                if let Some(level) = clob.bids_mut().get_mut(&Reverse(best_bid_price)) {
-                    level.reduce_quantity(fill_qty);
-                }
-                
-                // Remove fully filled orders
-                if fully_filled {
-                    self.remove_bid_order(clob, key, order_id, best_bid_price);
+            self.history.push_back(trade.clone());
+            self.history_total += 1;
+        }
+    }
+    
+    /// Log every change made to a book as a [`BookEvent`]
+    ///
+    /// Orders coming to rest, maker fills (from `match_order`,
+    /// `match_pegged` and `uncross`), cancel/replace removals and priority
+    /// decay passes are logged in the order they happen, for [`CLOB::apply_event`] on a
+    /// replica. Collect them with [`MatchingEngine::take_book_events`]. The
+    /// flag is part of [`EngineState`], the pending events are not.
+    pub fn with_book_events(mut self, enabled: bool) -> Self {
+        self.book_events = enabled;
+        self
+    }
+    
+    /// Take the book events logged since the last call, oldest first
+    pub fn take_book_events(&mut self) -> Vec<BookEvent> {
+        std::mem::take(&mut self.events)
+    }
+    
+    /// Log a book event if enabled
+    #[inline]
+    fn emit(&mut self, event: BookEvent) {
+        if self.book_events {
+            self.events.push(event);
+        }
+    }
+    
+    /// Set the external reference price used by [`MatchingEngine::match_pegged`]
+    ///
+    /// This is an input, not something the engine fetches: the caller
+    /// supplies the index or oracle price as part of the deterministic
+    /// input stream, and it is captured by `export_state`. `None` falls
+    /// back to the book's own mid.
+    pub fn set_reference_price(&mut self, price: Option<u64>) {
+        self.reference_price = price;
+    }
+    
+    /// Get the current external reference price
+    #[inline]
+    pub fn reference_price(&self) -> Option<u64> {
+        self.reference_price
+    }
+    
+    /// Capture the engine's deterministic state for a snapshot
+    pub fn export_state(&self) -> EngineState {
+        EngineState {
+            enforce_monotonic_timestamps: self.enforce_monotonic_timestamps,
+            last_timestamp: self.last_timestamp,
+            trade_timestamp_source: self.trade_timestamp_source,
+            replace_on_duplicate: self.replace_on_duplicate,
+            lot_size: self.lot_size,
+            determinism_audit: self.determinism_audit,
+            max_order_quantity: self.max_order_quantity,
+            min_order_quantity: self.min_order_quantity,
+            enforce_value_range: self.enforce_value_range,
+            reference_price: self.reference_price,
+            trade_history: self.trade_history,
+            min_spread: self.min_spread,
+            max_users: self.max_users,
+            reject_market_without_liquidity: self.reject_market_without_liquidity,
+            book_events: self.book_events,
+        }
+    }
+    
+    /// Recreate an engine from a snapshot taken with [`MatchingEngine::export_state`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    ///
+    /// let engine = MatchingEngine::new().with_monotonic_timestamps(true);
+    /// let restored = MatchingEngine::import_state(engine.export_state());
+    ///
+    /// assert_eq!(restored.export_state(), engine.export_state());
+    /// ```
+    pub fn import_state(state: EngineState) -> Self {
+        Self {
+            enforce_monotonic_timestamps: state.enforce_monotonic_timestamps,
+            last_timestamp: state.last_timestamp,
+            trade_timestamp_source: state.trade_timestamp_source,
+            replace_on_duplicate: state.replace_on_duplicate,
+            lot_size: state.lot_size,
+            determinism_audit: state.determinism_audit,
+            max_order_quantity: state.max_order_quantity,
+            min_order_quantity: state.min_order_quantity,
+            enforce_value_range: state.enforce_value_range,
+            reference_price: state.reference_price,
+            trade_history: state.trade_history,
+            min_spread: state.min_spread,
+            max_users: state.max_users,
+            reject_market_without_liquidity: state.reject_market_without_liquidity,
+            book_events: state.book_events,
+            events: Vec::new(),
+            history: VecDeque::new(),
+            history_total: 0,
+            history_index: HashMap::new(),
+        }
+    }
+    
+    /// Get the configured trade timestamp source
+    #[inline]
+    pub fn trade_timestamp_source(&self) -> TradeTimestampSource {
+        self.trade_timestamp_source
+    }
+    
+    /// Resolve the timestamp for a trade against the maker at `maker_key`
+    #[inline]
+    fn trade_timestamp(&self, clob: &CLOB, maker_key: usize, incoming: &Order, timestamp: u64) -> u64 {
+        match self.trade_timestamp_source {
+            TradeTimestampSource::Call => timestamp,
+            TradeTimestampSource::Taker => incoming.timestamp,
+            TradeTimestampSource::Maker => clob
+                .orders()
+                .get(maker_key)
+                .map_or(timestamp, |node| node.order.timestamp),
+        }
+    }
+    
+    /// Check an incoming order against the pre-trade gates
+    ///
+    /// Returns the reason the order must be rejected, if any.
+    fn check_order(&self, clob: &CLOB, order: &Order) -> Option<RejectReason> {
+        if self.enforce_monotonic_timestamps {
+            if let Some(last) = self.last_timestamp {
+                if order.timestamp < last {
+                    return Some(RejectReason::StaleTimestamp);
                 }
             }
+        }
+        
+        if self.enforce_value_range {
+            if order.price > MAX_FIXED || order.quantity > MAX_FIXED {
+                return Some(RejectReason::ValueOutOfRange);
+            }
             
-            // Remove empty price level
-            if let Some(level) = clob.bids().get(&Reverse(best_bid_price)) {
-                if level.is_empty() {
-                    clob.remove_bid_level(best_bid_price);
-                }
+            // Bound the notional by the highest price the order could trade at
+            let top_price = match order.side() {
+                Side::Buy if order.is_market() => clob.asks().keys().next_back().copied().unwrap_or(0),
+                Side::Buy => order.price,
+                Side::Sell => order.price.max(clob.best_bid().unwrap_or(0)),
+            };
+            let notional = mul_div(top_price, order.quantity, SCALE).unwrap_or(u128::MAX);
+            if notional > MAX_NOTIONAL as u128 {
+                return Some(RejectReason::ValueOutOfRange);
+            }
+        }
+        
+        if self.max_order_quantity.is_some_and(|max| order.quantity > max) {
+            return Some(RejectReason::SizeTooLarge);
+        }
+        if self.min_order_quantity.is_some_and(|min| order.quantity < min) {
+            return Some(RejectReason::SizeTooSmall);
+        }
+        
+        // A full same-side level at this price means the book's best
+        // opposite price is worse, so the order could only rest there.
+        // Replacing an order on that level frees its slot first.
+        if !order.is_market() && clob.level_is_full(order.side(), order.price) {
+            let frees_slot = self.replace_on_duplicate
+                && order.id != 0
+                && clob.get_key(order.id).and_then(|key| clob.get_order(key)).is_some_and(|resting| {
+                    resting.user_id == order.user_id && resting.side() == order.side() && resting.price == order.price
+                });
+            if !frees_slot {
+                return Some(RejectReason::LevelFull);
+            }
+        }
+        
+        if let Some(max) = self.max_users {
+            if !order.is_market() && !clob.has_resting_orders(order.user_id) && clob.resting_user_count() >= max {
+                return Some(RejectReason::TooManyUsers);
+            }
+        }
+        
+        // A crossing order's remainder is checked in `execute` instead,
+        // against what is left of the opposite side
+        if !order.is_market() && self.narrows_spread(clob, order) {
+            return Some(RejectReason::SpreadTooNarrow);
+        }
+        
+        if self.reject_market_without_liquidity && order.is_market() {
+            let opposite = match order.side() {
+                Side::Buy => clob.best_ask(),
+                Side::Sell => clob.best_bid(),
+            };
+            if opposite.is_none() {
+                return Some(RejectReason::NoLiquidity);
+            }
+        }
+        
+        // Each fill takes a trade ID and a match cannot stop halfway, so an
+        // order that could cross is refused while the IDs left might not
+        // cover a fill against every resting order
+        if clob.trade_ids_left() < clob.order_count() as u64 {
+            let opposite = match order.side() {
+                Side::Buy => clob.best_ask(),
+                Side::Sell => clob.best_bid(),
+            };
+            if opposite.is_some_and(|best| order.is_market() || clob.price_order().crosses(order.side(), order.price, best)) {
+                return Some(RejectReason::TradeIdsExhausted);
+            }
+        }
+        
+        // ID 0 is auto-assigned by the book and can never collide, but a
+        // limit order may need one to rest
+        if order.id == 0 && !order.is_market() && clob.order_ids().is_exhausted() {
+            return Some(RejectReason::OrderIdsExhausted);
+        }
+        if order.id != 0 {
+            let owner = clob.get_key(order.id).and_then(|key| clob.get_order(key)).map(|resting| resting.user_id);
+            if owner.is_some_and(|owner| !self.replace_on_duplicate || owner != order.user_id) {
+                return Some(RejectReason::DuplicateOrderId);
             }
         }
         
-        remaining
+        None
+    }
+    
+    /// Process an incoming order against the book.
+    ///
+    /// # Arguments
+    ///
+    /// * `clob` - The order book
+    /// * `incoming` - The incoming order
+    /// * `timestamp` - Current timestamp (for trade records, see `TradeTimestampSource`)
+    ///
+    /// # Returns
+    ///
+    /// A `MatchResult` containing trades and order status
+    ///
+    /// # Matching Rules
+    ///
+    /// 1. Buy orders match against asks (low to high)
+    /// 2. Sell orders match against bids (high to low)
+    /// 3. Price-time priority (FIFO at same price, or LIFO on a book
+    ///    configured with [`LevelOrdering::Lifo`](crate::orderbook::LevelOrdering::Lifo))
+    /// 4. Partial fills allowed
+    /// 5. Unfilled quantity rests on book (limit orders)
+    /// 6. Market orders ignore price and never rest; whatever is left when
+    ///    the opposite side runs out is reported in `unfilled`
+    /// 7. With a lot size configured, the incoming order's sub-lot
+    ///    remainder is discarded into `unfilled` before matching
+    ///
+    /// Priority decay is not applied here, since it walks every level; on
+    /// a book with [`CLOB::with_priority_decay`], call
+    /// [`MatchingEngine::decay_priorities`] at block boundaries
+    /// ([`MatchingEngine::match_batch`] does so once per batch).
+    ///
+    /// Orders that fail a configured pre-trade gate are returned with
+    /// `rejected` set and leave the book unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new();
+    ///
+    /// // Add resting order
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// // Match incoming order
+    /// let buy = Order::new(2, 101, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+    /// let result = engine.match_order(&mut clob, buy, 0);
+    ///
+    /// assert!(result.fully_filled);
+    /// ```
+    pub fn match_order(&mut self, clob: &mut CLOB, mut incoming: Order, timestamp: u64) -> MatchResult {
+        if self.determinism_audit {
+            return self.audited(clob, incoming, timestamp, Self::match_order);
+        }
+        
+        // Pre-trade gates: rejected orders never touch the book
+        if let Some(reason) = self.check_order(clob, &incoming) {
+            return MatchResult::rejected(incoming, reason);
+        }
+        self.last_timestamp = Some(incoming.timestamp);
+        let bbo_before = (clob.best_bid(), clob.best_ask());
+        
+        // Cancel/replace: the gates passed, so drop the resting order now
+        let replaced = if self.replace_on_duplicate && incoming.id != 0 {
+            clob.cancel_order(incoming.id)
+        } else {
+            None
+        };
+        if let Some(order) = &replaced {
+            self.emit(BookEvent::Removed { order_id: order.id, seq: clob.current_seq() });
+            if incoming.client_tag == [0; 16] {
+                incoming.client_tag = order.client_tag;
+            }
+        }
+        
+        let mut result = self.execute(clob, incoming, timestamp);
+        result.replaced = replaced;
+        if !result.order.is_market() {
+            result.price_improvement = price_improvement(result.order.price, &result.trades);
+        }
+        result.bbo_changed = (clob.best_bid(), clob.best_ask()) != bbo_before;
+        self.record_trades(&result.trades);
+        result
+    }
+    
+    /// Process a mid-pegged order against the book.
+    ///
+    /// The order's price is replaced by the reference price set with
+    /// [`MatchingEngine::set_reference_price`], or by the book's own mid
+    /// (rounded half up) when none is set, and the order is then matched
+    /// as a limit order by [`MatchingEngine::match_order`]. Pegging happens
+    /// once, on entry: any remainder rests at the pegged price and does not
+    /// follow later reference price changes.
+    ///
+    /// Rejected with [`RejectReason::NoReferencePrice`] when there is no
+    /// reference price and the book is missing a side.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new();
+    /// engine.set_reference_price(Some(5_000_000_000_000));
+    ///
+    /// // The order's own price is ignored
+    /// let result = engine.match_pegged(&mut clob, Order::new(1, 100, Side::Buy, 1, 100_000_000, 0), 0);
+    ///
+    /// assert_eq!(result.order.price, 5_000_000_000_000);
+    /// assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+    /// ```
+    pub fn match_pegged(&mut self, clob: &mut CLOB, mut incoming: Order, timestamp: u64) -> MatchResult {
+        let book_mid = match (clob.best_bid(), clob.best_ask()) {
+            (Some(bid), Some(ask)) => Some(midpoint(bid, ask)),
+            _ => None,
+        };
+        match self.reference_price.or(book_mid) {
+            Some(price) => {
+                incoming.price = price;
+                incoming.order_type_raw = OrderType::Limit.to_u8();
+                self.match_order(clob, incoming, timestamp)
+            }
+            None => MatchResult::rejected(incoming, RejectReason::NoReferencePrice),
+        }
+    }
+    
+    /// Amend a resting order through the full match path
+    ///
+    /// A cancel/replace: the resting order is replaced by a new limit
+    /// order with the same ID, user, side and client tag at `new_price`
+    /// for `new_quantity`, timestamped `timestamp`, and matched as by
+    /// [`MatchingEngine::match_order`] with replace on. A replacement that
+    /// crosses the book trades instead of leaving it crossed; the rest
+    /// queues at the tail of its level with fresh fill totals. If a
+    /// pre-trade gate rejects the replacement, the original keeps resting.
+    /// For a size reduction that keeps queue position, use
+    /// [`CLOB::reduce_order`] instead.
+    ///
+    /// # Returns
+    ///
+    /// The replacement's result, or None if no such order is resting or
+    /// `new_quantity` is 0, which just cancels it
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new();
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_010_000_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 101, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// // Repriced through the ask, the bid trades
+    /// let result = engine.amend(&mut clob, 2, 5_010_000_000_000, 100_000_000, 1).unwrap();
+    /// assert!(result.fully_filled);
+    /// assert!(clob.is_empty());
+    /// ```
+    pub fn amend(
+        &mut self,
+        clob: &mut CLOB,
+        order_id: u64,
+        new_price: u64,
+        new_quantity: u64,
+        timestamp: u64,
+    ) -> Option<MatchResult> {
+        let resting = clob.get_order(clob.get_key(order_id)?)?;
+        if new_quantity == 0 {
+            clob.cancel_order(order_id);
+            self.emit(BookEvent::Removed { order_id, seq: clob.current_seq() });
+            return None;
+        }
+        
+        let replacement = Order::new(order_id, resting.user_id, resting.side(), new_price, new_quantity, timestamp)
+            .with_client_tag(resting.client_tag);
+        let replace_on_duplicate = std::mem::replace(&mut self.replace_on_duplicate, true);
+        let result = self.match_order(clob, replacement, timestamp);
+        self.replace_on_duplicate = replace_on_duplicate;
+        Some(result)
+    }
+    
+    /// Move aged orders behind younger ones at the same price
+    ///
+    /// The block-boundary step for a book with
+    /// [`CLOB::with_priority_decay`]: applies [`CLOB::apply_priority_decay`]
+    /// at `now` and logs a [`BookEvent::Decayed`] if any order moved. It
+    /// walks every level, so run it once per block rather than per order.
+    ///
+    /// # Returns
+    ///
+    /// The number of aged orders that moved behind a younger one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_priority_decay(10);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 101, Side::Sell, 5_000_000_000_000, 100_000_000, 5));
+    ///
+    /// let mut engine = MatchingEngine::new();
+    /// assert_eq!(engine.decay_priorities(&mut clob, 10), 1);
+    /// assert_eq!(clob.queue_position(2), Some(0));
+    /// ```
+    pub fn decay_priorities(&mut self, clob: &mut CLOB, now: u64) -> usize {
+        let moved = clob.apply_priority_decay(now);
+        if moved > 0 {
+            self.emit(BookEvent::Decayed { now, max_age: clob.priority_decay(), seq: clob.current_seq() });
+        }
+        moved
+    }
+    
+    /// Trade away any crossed liquidity resting on the book.
+    ///
+    /// A book loaded without matching (bulk `add_order`, a restore, a
+    /// scenario file) can hold bids at or above asks. This repeatedly
+    /// trades the head order of the best bid level against the head order
+    /// of the best ask level until the book no longer crosses, so crossed
+    /// state is cleared best prices first and in queue order within each
+    /// level (FIFO unless the book is LIFO).
+    ///
+    /// Of each pair, the order with the earlier timestamp (lower ID on a
+    /// tie) is the maker and sets the execution price; the other is the
+    /// taker. Neither order loses its place in its queue, and a partially
+    /// filled order stays at the head of its level for the next pair.
+    /// Priority decay is not applied; call
+    /// [`MatchingEngine::decay_priorities`] first if aged orders should
+    /// yield.
+    ///
+    /// # Arguments
+    ///
+    /// * `clob` - The order book
+    /// * `timestamp` - Current timestamp (for trade records, see `TradeTimestampSource`)
+    ///
+    /// # Returns
+    ///
+    /// The trades, in execution order (empty if the book was not crossed).
+    /// Stops early, leaving the book crossed, if the book runs out of
+    /// trade IDs.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 1));
+    /// clob.add_order(Order::new(2, 101, Side::Buy, 5_010_000_000_000, 100_000_000, 2));
+    ///
+    /// let trades = MatchingEngine::new().uncross(&mut clob, 0);
+    ///
+    /// assert_eq!(trades.len(), 1);
+    /// assert_eq!(trades[0].price, 5_000_000_000_000);
+    /// assert!(clob.is_empty());
+    /// ```
+    pub fn uncross(&mut self, clob: &mut CLOB, timestamp: u64) -> Vec<Trade> {
+        let mut trades = Vec::new();
+        
+        loop {
+            clob.prune_empty_best_levels(Side::Buy);
+            clob.prune_empty_best_levels(Side::Sell);
+            
+            let (bid_price, ask_price) = match (clob.best_bid(), clob.best_ask()) {
+                (Some(bid), Some(ask)) if clob.price_order().crosses(Side::Buy, bid, ask) => (bid, ask),
+                _ => break,
+            };
+            let head_order = |level: Option<&PriceLevel>| {
+                level
+                    .and_then(|l| l.head)
+                    .and_then(|key| clob.orders().get(key).map(|node| (key, node.order.clone())))
+            };
+            let (Some((bid_key, bid)), Some((ask_key, ask))) =
+                (head_order(clob.bids().get(&Reverse(bid_price))), head_order(clob.asks().get(&ask_price)))
+            else {
+                break;
+            };
+            
+            // The earlier order was resting first, so it is the maker
+            let bid_is_maker = (bid.timestamp, bid.id) <= (ask.timestamp, ask.id);
+            let (maker_key, maker, taker, price) = if bid_is_maker {
+                (bid_key, &bid, &ask, bid_price)
+            } else {
+                (ask_key, &ask, &bid, ask_price)
+            };
+            let quantity = bid.remaining.min(ask.remaining);
+            let Some(trade_id) = clob.next_trade_id() else {
+                break;
+            };
+            
+            let trade = Trade::new(
+                trade_id,
+                maker.id,
+                taker.id,
+                maker.user_id,
+                taker.user_id,
+                price,
+                quantity,
+                self.trade_timestamp(clob, maker_key, taker, timestamp),
+            )
+            .with_client_tags(maker.client_tag, taker.client_tag);
+            trades.push(trade);
+            
+            self.fill_resting(clob, Side::Buy, bid_key, bid_price, quantity, price);
+            self.emit(BookEvent::Filled { order_id: bid.id, trade_id, quantity, price, seq: clob.current_seq() });
+            self.fill_resting(clob, Side::Sell, ask_key, ask_price, quantity, price);
+            self.emit(BookEvent::Filled { order_id: ask.id, trade_id, quantity, price, seq: clob.current_seq() });
+        }
+        
+        self.record_trades(&trades);
+        trades
+    }
+    
+    /// Fill `quantity` of a resting order at `price`, removing it (and an
+    /// emptied level) when nothing is left
+    fn fill_resting(
+        &self,
+        clob: &mut CLOB,
+        side: Side,
+        key: usize,
+        level_price: u64,
+        quantity: u64,
+        price: u64,
+    ) {
+        clob.advance_seq();
+        let (order_id, fully_filled) = match clob.orders_mut().get_mut(key) {
+            Some(node) => {
+                node.fill(quantity);
+                node.order.record_fill(quantity, price);
+                (node.order.id, node.remaining() == 0)
+            }
+            None => return,
+        };
+        
+        match side {
+            Side::Buy => {
+                if let Some(level) = clob.bids_mut().get_mut(&Reverse(level_price)) {
+                    level.reduce_quantity(quantity);
+                }
+                if fully_filled {
+                    self.remove_bid_order(clob, key, order_id, level_price);
+                }
+                if clob.bids().get(&Reverse(level_price)).is_some_and(|level| level.is_empty()) {
+                    clob.remove_bid_level(level_price);
+                }
+            }
+            Side::Sell => {
+                if let Some(level) = clob.asks_mut().get_mut(&level_price) {
+                    level.reduce_quantity(quantity);
+                }
+                if fully_filled {
+                    self.remove_ask_order(clob, key, order_id, level_price);
+                }
+                if clob.asks().get(&level_price).is_some_and(|level| level.is_empty()) {
+                    clob.remove_ask_level(level_price);
+                }
+            }
+        }
+    }
+    
+    /// Run `step` on a scratch copy of the book and engine, then for real,
+    /// and panic if the two runs disagree
+    fn audited(
+        &mut self,
+        clob: &mut CLOB,
+        incoming: Order,
+        timestamp: u64,
+        step: fn(&mut Self, &mut CLOB, Order, u64) -> MatchResult,
+    ) -> MatchResult {
+        // Both runs go through `step` with the audit off, so it never recurses
+        self.determinism_audit = false;
+        let mut scratch_engine = Self::import_state(self.export_state());
+        let mut scratch_book = clob.clone_rehashed();
+        
+        let expected = step(&mut scratch_engine, &mut scratch_book, incoming.clone(), timestamp);
+        let actual = step(self, clob, incoming, timestamp);
+        
+        assert!(
+            actual == expected,
+            "determinism audit failed for order {}: results differ\n  scratch: {:?}\n  actual:  {:?}",
+            actual.order.id, expected, actual
+        );
+        assert!(
+            clob.compute_state_root() == scratch_book.compute_state_root(),
+            "determinism audit failed for order {}: state roots differ",
+            actual.order.id
+        );
+        assert!(
+            self.export_state() == scratch_engine.export_state(),
+            "determinism audit failed for order {}: engine states differ",
+            actual.order.id
+        );
+        
+        self.determinism_audit = true;
+        actual
+    }
+    
+    /// Match an order that passed the pre-trade gates
+    fn execute(&mut self, clob: &mut CLOB, mut incoming: Order, timestamp: u64) -> MatchResult {
+        let mut sweep = Sweep::default();
+        
+        // Sub-lot remainder can neither trade nor rest
+        let mut remaining = self.round_to_lot(incoming.remaining);
+        let sub_lot = incoming.remaining - remaining;
+        
+        // Match based on order side
+        match incoming.side() {
+            Side::Buy => {
+                // Buy orders match against asks (lowest price first)
+                remaining = self.match_against_asks(clob, &incoming, remaining, timestamp, &mut sweep);
+            }
+            Side::Sell => {
+                // Sell orders match against bids (highest price first)
+                remaining = self.match_against_bids(clob, &incoming, remaining, timestamp, &mut sweep);
+            }
+        }
+        let Sweep { trades, maker_fills, levels_consumed, steps } = sweep;
+        
+        // Update the incoming order's remaining quantity and lifetime totals
+        incoming.remaining = remaining;
+        for trade in &trades {
+            incoming.record_fill(trade.quantity, trade.price);
+        }
+        
+        // A taker filled on arrival never rests, so remember it here; one
+        // that was all sub-lot never traded and is not filled
+        if remaining == 0 && !trades.is_empty() && incoming.id != 0 {
+            clob.record_filled(incoming.id);
+        }
+        
+        // Determine result based on remaining quantity
+        let mut result = if remaining == 0 && sub_lot > 0 {
+            // Every whole lot traded, only the discarded remainder is left
+            MatchResult::exhausted(incoming, trades, 0)
+        } else if remaining == 0 {
+            // Fully filled
+            MatchResult::fully_filled(incoming, trades)
+        } else if incoming.is_market() {
+            // Book exhausted - market orders never rest
+            MatchResult::exhausted(incoming, trades, remaining)
+        } else if self.narrows_spread(clob, &incoming) {
+            // The sweep left a remainder that would rest inside the minimum spread
+            MatchResult::exhausted(incoming, trades, remaining)
+        } else {
+            // Partial or no fill - add to book if limit order
+            let assigned = incoming.id == 0;
+            let resting_key = clob.add_order(incoming);
+            
+            // Read it back so the event and result carry an auto-assigned ID
+            let incoming = clob.get_order(resting_key).expect("order was just added").clone();
+            self.emit(BookEvent::Added { order: incoming.clone(), assigned, seq: clob.current_seq() });
+            
+            if trades.is_empty() {
+                MatchResult::no_match(incoming, resting_key)
+            } else {
+                MatchResult::partial_fill(incoming, trades, remaining, Some(resting_key))
+            }
+        };
+        result.maker_fills = maker_fills;
+        result.levels_consumed = levels_consumed;
+        result.steps = steps;
+        result.unfilled += sub_lot;
+        result
+    }
+    
+    /// Match a buy order against asks (lowest price first)
+    fn match_against_asks(
+        &mut self,
+        clob: &mut CLOB,
+        incoming: &Order,
+        mut remaining: u64,
+        timestamp: u64,
+        sweep: &mut Sweep,
+    ) -> u64 {
+        let incoming_price = incoming.price;
+        
+        // Process price levels until no more matches
+        while remaining > 0 {
+            // A match reaching retained empty levels drops them, including
+            // ones uncovered mid-sweep
+            sweep.steps += clob.prune_empty_best_levels(Side::Sell);
+            
+            // Get best ask price
+            let best_ask_price = match clob.best_ask() {
+                Some(p) => p,
+                None => break, // No more asks
+            };
+            
+            // Check if price is compatible (market orders take any price)
+            // Buy matches if book price <= incoming price (reversed for inverse books)
+            if !incoming.is_market() && !clob.price_order().crosses(Side::Buy, incoming_price, best_ask_price) {
+                break; // No more compatible prices
+            }
+            
+            // Collect matching info from this price level
+            let matches = self.collect_matches_at_ask_level(clob, remaining, best_ask_price);
+            
+            if matches.is_empty() {
+                break;
+            }
+            sweep.levels_consumed += 1;
+            sweep.steps += 1 + matches.len();
+            
+            // Execute the matches
+            for (key, order_id, maker_user_id, fill_qty, fully_filled) in matches {
+                // Create trade record
+                let trade_id = clob.next_trade_id().expect("trade IDs checked by the pre-trade gates");
+                let trade_timestamp = self.trade_timestamp(clob, key, incoming, timestamp);
+                let maker_tag = clob.orders().get(key).map_or([0; 16], |node| node.order.client_tag);
+                let trade = Trade::new(
+                    trade_id,
+                    order_id,               // maker_order_id
+                    incoming.id,            // taker_order_id
+                    maker_user_id,          // maker_user_id
+                    incoming.user_id,       // taker_user_id
+                    best_ask_price,         // execution price
+                    fill_qty,               // quantity
+                    trade_timestamp,
+                )
+                .with_client_tags(maker_tag, incoming.client_tag);
+                sweep.trades.push(trade);
+                
+                // Update remaining
+                remaining -= fill_qty;
+                
+                // Fill the maker order
+                clob.advance_seq();
+                if let Some(node) = clob.orders_mut().get_mut(key) {
+                    node.fill(fill_qty);
+                    node.order.record_fill(fill_qty, best_ask_price);
+                    sweep.maker_fills.push(MakerFill {
+                        order_id,
+                        filled: fill_qty,
+                        cumulative_filled: node.order.cumulative_filled,
+                        cumulative_notional: node.order.cumulative_notional,
+                    });
+                }
+                self.emit(BookEvent::Filled {
+                    order_id,
+                    trade_id,
+                    quantity: fill_qty,
+                    price: best_ask_price,
+                    seq: clob.current_seq(),
+                });
+                
+                // Update price level quantity
+                if let Some(level) = clob.asks_mut().get_mut(&best_ask_price) {
+                    level.reduce_quantity(fill_qty);
+                }
+                
+                // Remove fully filled orders
+                if fully_filled {
+                    self.remove_ask_order(clob, key, order_id, best_ask_price);
+                }
+            }
+            
+            // Remove empty price level
+            if let Some(level) = clob.asks().get(&best_ask_price) {
+                if level.is_empty() {
+                    clob.remove_ask_level(best_ask_price);
+                }
+            }
+        }
+        
+        remaining
+    }
+    
+    /// Match a sell order against bids (highest price first)
+    fn match_against_bids(
+        &mut self,
+        clob: &mut CLOB,
+        incoming: &Order,
+        mut remaining: u64,
+        timestamp: u64,
+        sweep: &mut Sweep,
+    ) -> u64 {
+        let incoming_price = incoming.price;
+        
+        // Process price levels until no more matches
+        while remaining > 0 {
+            // A match reaching retained empty levels drops them, including
+            // ones uncovered mid-sweep
+            sweep.steps += clob.prune_empty_best_levels(Side::Buy);
+            
+            // Get best bid price
+            let best_bid_price = match clob.best_bid() {
+                Some(p) => p,
+                None => break, // No more bids
+            };
+            
+            // Check if price is compatible (market orders take any price)
+            // Sell matches if book price >= incoming price (reversed for inverse books)
+            if !incoming.is_market() && !clob.price_order().crosses(Side::Sell, incoming_price, best_bid_price) {
+                break; // No more compatible prices
+            }
+            
+            // Collect matching info from this price level
+            let matches = self.collect_matches_at_bid_level(clob, remaining, best_bid_price);
+            
+            if matches.is_empty() {
+                break;
+            }
+            sweep.levels_consumed += 1;
+            sweep.steps += 1 + matches.len();
+            
+            // Execute the matches
+            for (key, order_id, maker_user_id, fill_qty, fully_filled) in matches {
+                // Create trade record
+                let trade_id = clob.next_trade_id().expect("trade IDs checked by the pre-trade gates");
+                let trade_timestamp = self.trade_timestamp(clob, key, incoming, timestamp);
+                let maker_tag = clob.orders().get(key).map_or([0; 16], |node| node.order.client_tag);
+                let trade = Trade::new(
+                    trade_id,
+                    order_id,               // maker_order_id
+                    incoming.id,            // taker_order_id
+                    maker_user_id,          // maker_user_id
+                    incoming.user_id,       // taker_user_id
+                    best_bid_price,         // execution price
+                    fill_qty,               // quantity
+                    trade_timestamp,
+                )
+                .with_client_tags(maker_tag, incoming.client_tag);
+                sweep.trades.push(trade);
+                
+                // Update remaining
+                remaining -= fill_qty;
+                
+                // Fill the maker order
+                clob.advance_seq();
+                if let Some(node) = clob.orders_mut().get_mut(key) {
+                    node.fill(fill_qty);
+                    node.order.record_fill(fill_qty, best_bid_price);
+                    sweep.maker_fills.push(MakerFill {
+                        order_id,
+                        filled: fill_qty,
+                        cumulative_filled: node.order.cumulative_filled,
+                        cumulative_notional: node.order.cumulative_notional,
+                    });
+                }
+                self.emit(BookEvent::Filled {
+                    order_id,
+                    trade_id,
+                    quantity: fill_qty,
+                    price: best_bid_price,
+                    seq: clob.current_seq(),
+                });
+                
+                // Update price level quantity
+                if let Some(level) = clob.bids_mut().get_mut(&Reverse(best_bid_price)) {
+                    level.reduce_quantity(fill_qty);
+                }
+                
+                // Remove fully filled orders
+                if fully_filled {
+                    self.remove_bid_order(clob, key, order_id, best_bid_price);
+                }
+            }
+            
+            // Remove empty price level
+            if let Some(level) = clob.bids().get(&Reverse(best_bid_price)) {
+                if level.is_empty() {
+                    clob.remove_bid_level(best_bid_price);
+                }
+            }
+        }
+        
+        remaining
+    }
+    
+    /// Collect orders to match at an ask price level (read-only scan)
+    ///
+    /// Returns: Vec<(key, order_id, user_id, fill_qty, fully_filled)>
+    fn collect_matches_at_ask_level(
+        &self,
+        clob: &CLOB,
+        mut remaining: u64,
+        price: u64,
+    ) -> Vec<(usize, u64, u64, u64, bool)> {
+        let mut matches = Vec::new();
+        
+        let level = match clob.asks().get(&price) {
+            Some(l) => l,
+            None => return matches,
+        };
+        
+        let mut current_key = level.head;
+        
+        while remaining > 0 && current_key.is_some() {
+            let key = current_key.unwrap();
+            
+            let node = match clob.orders().get(key) {
+                Some(n) => n,
+                None => break,
+            };
+            
+            let order_id = node.order.id;
+            let user_id = node.order.user_id;
+            let maker_remaining = node.remaining();
+            
+            // Calculate fill quantity
+            let fill_qty = remaining.min(maker_remaining);
+            let fully_filled = fill_qty >= maker_remaining;
+            
+            matches.push((key, order_id, user_id, fill_qty, fully_filled));
+            
+            remaining -= fill_qty;
+            current_key = node.next;
+        }
+        
+        matches
+    }
+    
+    /// Collect orders to match at a bid price level (read-only scan)
+    ///
+    /// Returns: Vec<(key, order_id, user_id, fill_qty, fully_filled)>
+    fn collect_matches_at_bid_level(
+        &self,
+        clob: &CLOB,
+        mut remaining: u64,
+        price: u64,
+    ) -> Vec<(usize, u64, u64, u64, bool)> {
+        let mut matches = Vec::new();
+        
+        let level = match clob.bids().get(&Reverse(price)) {
+            Some(l) => l,
+            None => return matches,
+        };
+        
+        let mut current_key = level.head;
+        
+        while remaining > 0 && current_key.is_some() {
+            let key = current_key.unwrap();
+            
+            let node = match clob.orders().get(key) {
+                Some(n) => n,
+                None => break,
+            };
+            
+            let order_id = node.order.id;
+            let user_id = node.order.user_id;
+            let maker_remaining = node.remaining();
+            
+            // Calculate fill quantity
+            let fill_qty = remaining.min(maker_remaining);
+            let fully_filled = fill_qty >= maker_remaining;
+            
+            matches.push((key, order_id, user_id, fill_qty, fully_filled));
+            
+            remaining -= fill_qty;
+            current_key = node.next;
+        }
+        
+        matches
+    }
+    
+    /// Remove a fully filled ask order
+    fn remove_ask_order(&self, clob: &mut CLOB, key: usize, order_id: u64, price: u64) {
+        // Get the node's linked list pointers first
+        let (prev_key, next_key) = match clob.orders().get(key) {
+            Some(node) => (node.prev, node.next),
+            None => return, // Order already removed, nothing to do
+        };
+        
+        // Update the previous node's next pointer
+        if let Some(prev) = prev_key {
+            if let Some(prev_node) = clob.orders_mut().get_mut(prev) {
+                prev_node.next = next_key;
+            }
+        }
+        
+        // Update the next node's prev pointer
+        if let Some(next) = next_key {
+            if let Some(next_node) = clob.orders_mut().get_mut(next) {
+                next_node.prev = prev_key;
+            }
+        }
+        
+        // Update the price level's head/tail
+        if let Some(level) = clob.asks_mut().get_mut(&price) {
+            if level.head == Some(key) {
+                level.head = next_key;
+            }
+            if level.tail == Some(key) {
+                level.tail = prev_key;
+            }
+            decrement_count(&mut level.order_count, 1, "level order");
+        }
+        
+        // Remove from slab
+        clob.remove_from_slab(key);
+        
+        // Remove from index
+        clob.remove_from_index(order_id);
+        clob.record_filled(order_id);
+        
+        // Decrement count
+        clob.decrement_ask_count();
+    }
+    
+    /// Remove a fully filled bid order
+    fn remove_bid_order(&self, clob: &mut CLOB, key: usize, order_id: u64, price: u64) {
+        // Get the node's linked list pointers first
+        let (prev_key, next_key) = match clob.orders().get(key) {
+            Some(node) => (node.prev, node.next),
+            None => return, // Order already removed, nothing to do
+        };
+        
+        // Update the previous node's next pointer
+        if let Some(prev) = prev_key {
+            if let Some(prev_node) = clob.orders_mut().get_mut(prev) {
+                prev_node.next = next_key;
+            }
+        }
+        
+        // Update the next node's prev pointer
+        if let Some(next) = next_key {
+            if let Some(next_node) = clob.orders_mut().get_mut(next) {
+                next_node.prev = prev_key;
+            }
+        }
+        
+        // Update the price level's head/tail
+        if let Some(level) = clob.bids_mut().get_mut(&Reverse(price)) {
+            if level.head == Some(key) {
+                level.head = next_key;
+            }
+            if level.tail == Some(key) {
+                level.tail = prev_key;
+            }
+            decrement_count(&mut level.order_count, 1, "level order");
+        }
+        
+        // Remove from slab
+        clob.remove_from_slab(key);
+        
+        // Remove from index
+        clob.remove_from_index(order_id);
+        clob.record_filled(order_id);
+        
+        // Decrement count
+        clob.decrement_bid_count();
+    }
+}
+
+// ============================================================================
+// Helper Functions
+// ============================================================================
+
+/// Check if prices are compatible for matching.
+///
+/// # Arguments
+///
+/// * `incoming_side` - Side of the incoming order
+/// * `incoming_price` - Price of the incoming order
+/// * `book_price` - Price of the resting order
+///
+/// # Returns
+///
+/// `true` if the orders can match
+///
+/// Uses standard price ordering; see [`PriceOrder::crosses`] for inverse books.
+#[inline]
+pub fn is_price_compatible(incoming_side: Side, incoming_price: u64, book_price: u64) -> bool {
+    // Buy order matches if book price <= incoming price
+    // Sell order matches if book price >= incoming price
+    PriceOrder::Standard.crosses(incoming_side, incoming_price, book_price)
+}
+
+/// Total price improvement of `trades` against a taker's limit price.
+///
+/// Trades only execute at prices at least as good as the limit, so the
+/// absolute difference is always in the taker's favor. Products are summed
+/// at full precision and scaled back by 10^8 once.
+fn price_improvement(limit: u64, trades: &[Trade]) -> u64 {
+    let raw: u128 = trades
+        .iter()
+        .map(|t| t.price.abs_diff(limit) as u128 * t.quantity as u128)
+        .sum();
+    u64::try_from(raw / SCALE as u128).unwrap_or(u64::MAX)
+}
+
+/// The distinct order IDs a trade involves, maker first
+fn trade_orders(trade: &Trade) -> impl Iterator<Item = u64> {
+    let taker = (trade.taker_order_id != trade.maker_order_id).then_some(trade.taker_order_id);
+    std::iter::once(trade.maker_order_id).chain(taker)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn create_buy_order(id: u64, price: u64, quantity: u64) -> Order {
+        Order::new(id, 100, Side::Buy, price, quantity, 0)
+    }
+    
+    fn create_sell_order(id: u64, price: u64, quantity: u64) -> Order {
+        Order::new(id, 100, Side::Sell, price, quantity, 0)
+    }
+    
+    #[test]
+    fn test_simple_match() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Add resting sell order
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        
+        // Incoming buy order should match
+        let buy = create_buy_order(2, 5_000_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(result.fully_filled);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].price, 5_000_000_000_000);
+        assert_eq!(result.trades[0].quantity, 100_000_000);
+        assert_eq!(result.trades[0].maker_order_id, 1);
+        assert_eq!(result.trades[0].taker_order_id, 2);
+        
+        // Book should be empty
+        assert!(clob.is_empty());
+    }
+    
+    #[test]
+    fn test_exact_single_maker_cross() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // 1.0 sell at 50000 from user 7, met by a 1.0 buy at 50000 from user 8
+        clob.add_order(Order::new(1, 7, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        let result = engine.match_order(&mut clob, Order::new(2, 8, Side::Buy, 5_000_000_000_000, 100_000_000, 1), 1000);
+        
+        assert_eq!(result.trades.len(), 1);
+        let trade = &result.trades[0];
+        assert_eq!((trade.maker_order_id, trade.taker_order_id), (1, 2));
+        assert_eq!((trade.maker_user_id, trade.taker_user_id), (7, 8));
+        assert_eq!((trade.price, trade.quantity), (5_000_000_000_000, 100_000_000));
+        
+        // Taker filled and not rested, maker gone from the book
+        assert!(result.fully_filled);
+        assert_eq!(result.remaining, 0);
+        assert_eq!(result.resting_key, None);
+        assert_eq!(result.maker_fills.len(), 1);
+        assert!(!clob.contains_order(1));
+        assert!(!clob.contains_order(2));
+        assert!(clob.is_empty());
+        assert_eq!((clob.bid_levels(), clob.ask_levels()), (0, 0));
+        clob.verify_invariants().unwrap();
+    }
+    
+    #[test]
+    fn test_partial_fill() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Add resting sell order for 1 unit
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        
+        // Incoming buy order for 2 units
+        let buy = create_buy_order(2, 5_000_000_000_000, 200_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(!result.fully_filled);
+        assert_eq!(result.remaining, 100_000_000);
+        assert_eq!(result.trades.len(), 1);
+        assert!(result.resting_key.is_some());
+        
+        // Remaining should rest on book
+        assert_eq!(clob.order_count(), 1);
+        assert_eq!(clob.bid_count(), 1);
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+    }
+    
+    #[test]
+    fn test_partial_fill_resting_key_points_at_remainder() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        let result = engine.match_order(&mut clob, create_buy_order(2, 5_000_000_000_000, 250_000_000), 1000);
+        
+        // The key is the same one an index lookup would give
+        let key = result.resting_key.unwrap();
+        assert_eq!(clob.get_key(2), Some(key));
+        
+        let resting = clob.get_order(key).unwrap();
+        assert_eq!(resting.id, 2);
+        assert_eq!(resting.remaining, 150_000_000);
+        assert_eq!(resting.filled_quantity(), 100_000_000);
+        
+        // ...and is good for a later O(1) removal
+        assert_eq!(clob.remove_order(key).map(|o| o.id), Some(2));
+        assert!(clob.is_empty());
+    }
+    
+    #[test]
+    fn test_partial_fill_then_rest_accounting() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(1, 10_000_000_000, 30_000_000));
+        clob.add_order(create_sell_order(2, 10_000_000_000, 40_000_000));
+        clob.add_order(create_sell_order(3, 10_100_000_000, 50_000_000));
+        clob.add_order(create_buy_order(9, 9_900_000_000, 20_000_000));
+        
+        // Takes the whole 100 ask level, then rests 0.3 as a new 100 bid
+        let result = engine.match_order(&mut clob, create_buy_order(10, 10_000_000_000, 100_000_000), 0);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.remaining, 30_000_000);
+        
+        // The consumed ask level is gone, the next one untouched
+        assert_eq!(clob.level_summary(Side::Sell, 10_000_000_000), None);
+        assert_eq!(clob.best_ask(), Some(10_100_000_000));
+        assert_eq!(clob.level_summary(Side::Sell, 10_100_000_000).unwrap().total_quantity, 50_000_000);
+        assert!(!clob.contains_order(1) && !clob.contains_order(2));
+        
+        // The remainder opened a new best bid level holding only itself
+        let level = clob.level_summary(Side::Buy, 10_000_000_000).unwrap();
+        assert_eq!((level.total_quantity, level.order_count), (30_000_000, 1));
+        assert_eq!(clob.best_bid(), Some(10_000_000_000));
+        assert_eq!(clob.get_key(10), result.resting_key);
+        assert_eq!(clob.get_order(result.resting_key.unwrap()).unwrap().remaining, 30_000_000);
+        
+        assert_eq!((clob.bid_count(), clob.ask_count()), (2, 1));
+        assert_eq!((clob.bid_levels(), clob.ask_levels()), (2, 1));
+        assert_eq!(clob.order_count(), 3);
+        clob.verify_invariants().unwrap();
+        
+        // A taker that stops inside a level leaves it reduced, not removed
+        let result = engine.match_order(&mut clob, create_sell_order(11, 9_900_000_000, 40_000_000), 0);
+        assert_eq!(result.trades.len(), 2);
+        let level = clob.level_summary(Side::Buy, 9_900_000_000).unwrap();
+        assert_eq!((level.total_quantity, level.order_count), (10_000_000, 1));
+        assert_eq!((clob.bid_count(), clob.ask_count()), (1, 1));
+        clob.verify_invariants().unwrap();
+    }
+    
+    #[test]
+    fn test_rested_remaining_reflects_fills() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 30_000_000));
+        clob.add_order(create_sell_order(2, 5_010_000_000_000, 20_000_000));
+        
+        // Unmatched: rests whole
+        engine.match_order(&mut clob, create_buy_order(3, 4_990_000_000_000, 100_000_000), 0);
+        let rested = clob.get_order(clob.get_key(3).unwrap()).unwrap();
+        assert_eq!(rested.remaining, rested.quantity);
+        
+        // Partially filled across two levels: rests only what is left
+        let result = engine.match_order(&mut clob, create_buy_order(4, 5_010_000_000_000, 100_000_000), 0);
+        let rested = clob.get_order(result.resting_key.unwrap()).unwrap();
+        assert_eq!(rested.remaining, 50_000_000);
+        assert_eq!(rested.cumulative_filled, 50_000_000);
+        assert_eq!(clob.level_summary(Side::Buy, 5_010_000_000_000).unwrap().total_quantity, 50_000_000);
+        clob.verify_invariants().unwrap();
+        
+        // Resting a filled taker at its original size is caught
+        let key = result.resting_key.unwrap();
+        clob.orders_mut().get_mut(key).unwrap().order.remaining = 100_000_000;
+        clob.bids_mut().get_mut(&Reverse(5_010_000_000_000)).unwrap().total_quantity = 100_000_000;
+        assert!(clob.verify_invariants().is_err());
+    }
+    
+    #[test]
+    fn test_exact_size_sweep_leaves_empty_book() {
+        for retain in [false, true] {
+            let mut clob = CLOB::with_capacity(100).with_level_retention(retain);
+            let mut engine = MatchingEngine::new();
+            
+            // Three 1.0 makers across three levels, 3.0 available in total
+            clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(2, 5_010_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(3, 5_020_000_000_000, 100_000_000));
+            
+            let result = engine.match_order(&mut clob, create_buy_order(4, 5_020_000_000_000, 300_000_000), 1000);
+            
+            assert!(result.fully_filled);
+            assert_eq!(result.remaining, 0);
+            assert_eq!(result.resting_key, None);
+            assert_eq!(result.trades.len(), 3);
+            assert!(result.trades.iter().all(|t| t.quantity == 100_000_000));
+            
+            // No dust makers, no rested taker, no lingering levels
+            assert_eq!(clob.order_count(), 0);
+            assert!(!clob.contains_order(4));
+            assert_eq!(clob.ask_levels(), 0);
+            assert_eq!(clob.bid_levels(), 0);
+            assert_eq!(clob.best_ask(), None);
+            assert!(clob.verify_invariants().is_ok());
+        }
+    }
+    
+    #[test]
+    fn test_order_size_limits() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new()
+            .with_min_order_quantity(10_000_000)
+            .with_max_order_quantity(1_000_000_000);
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 2_000_000_000));
+        
+        let result = engine.match_order(&mut clob, create_buy_order(2, 5_000_000_000_000, 1_000_000_001), 0);
+        assert_eq!(result.rejected, Some(RejectReason::SizeTooLarge));
+        assert!(result.trades.is_empty());
+        
+        let result = engine.match_order(&mut clob, create_buy_order(3, 5_000_000_000_000, 9_999_999), 0);
+        assert_eq!(result.rejected, Some(RejectReason::SizeTooSmall));
+        assert!(result.trades.is_empty());
+        
+        // Rejections left the maker untouched
+        assert_eq!(clob.level_summary(Side::Sell, 5_000_000_000_000).unwrap().total_quantity, 2_000_000_000);
+        
+        // Both bounds are inclusive
+        for (id, quantity) in [(4, 10_000_000), (5, 1_000_000_000)] {
+            let result = engine.match_order(&mut clob, create_buy_order(id, 5_000_000_000_000, quantity), 0);
+            assert!(!result.is_rejected());
+            assert!(result.fully_filled);
+        }
+    }
+    
+    #[test]
+    fn test_matching_on_cloned_book_leaves_original_untouched() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_010_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(3, 4_990_000_000_000, 100_000_000));
+        
+        let root = clob.compute_state_root();
+        let mut fork = clob.clone();
+        assert_eq!(fork.compute_state_root(), root);
+        assert_eq!(fork.get_key(2), clob.get_key(2));
+        
+        // What-if: sweep both asks and rest the remainder on the fork only
+        let result = engine.match_order(&mut fork, create_buy_order(4, 5_010_000_000_000, 300_000_000), 1000);
+        assert_eq!(result.trades.len(), 2);
+        assert!(fork.verify_invariants().is_ok());
+        assert_ne!(fork.compute_state_root(), root);
+        
+        // The original is exactly as it was, including its ID counters
+        assert_eq!(clob.compute_state_root(), root);
+        assert_eq!(clob.order_count(), 3);
+        assert_eq!(clob.best_ask(), Some(5_000_000_000_000));
+        assert_eq!(clob.peek_next_trade_id(), 1);
+        assert_eq!(fork.peek_next_trade_id(), 3);
+        
+        // The same order on the original reproduces the fork
+        engine.match_order(&mut clob, create_buy_order(4, 5_010_000_000_000, 300_000_000), 1000);
+        assert_eq!(clob.compute_state_root(), fork.compute_state_root());
+    }
+    
+    #[test]
+    fn test_level_cap_rejects_order_past_cap() {
+        let mut clob = CLOB::with_capacity(100).with_max_level_orders(3);
+        let mut engine = MatchingEngine::new();
+        
+        for id in 1..=3 {
+            let result = engine.match_order(&mut clob, create_buy_order(id, 5_000_000_000_000, 100_000_000), 0);
+            assert!(!result.is_rejected());
+        }
+        
+        // The fourth order at the same price is rejected, the book untouched
+        let result = engine.match_order(&mut clob, create_buy_order(4, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::LevelFull));
+        assert_eq!(clob.largest_level_size(), 3);
+        assert!(!clob.contains_order(4));
+        
+        // Another price still accepts orders, and the full level still trades
+        let result = engine.match_order(&mut clob, create_buy_order(5, 4_990_000_000_000, 100_000_000), 0);
+        assert!(result.resting_key.is_some());
+        let result = engine.match_order(&mut clob, create_sell_order(6, 5_000_000_000_000, 100_000_000), 0);
+        assert!(result.fully_filled);
+        
+        // The fill freed a slot
+        let result = engine.match_order(&mut clob, create_buy_order(7, 5_000_000_000_000, 100_000_000), 0);
+        assert!(!result.is_rejected());
+        
+        // Replacing an order on its own full level takes the slot it frees
+        let mut clob = CLOB::with_capacity(100).with_max_level_orders(1);
+        let mut engine = MatchingEngine::new().with_replace_on_duplicate(true);
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        let result = engine.match_order(&mut clob, create_buy_order(1, 5_000_000_000_000, 200_000_000), 0);
+        assert!(!result.is_rejected());
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 200_000_000);
+        
+        // Another user's order, or one resting elsewhere, still finds the level full
+        let result = engine.match_order(&mut clob, Order::new(1, 101, Side::Buy, 5_000_000_000_000, 100_000_000, 0), 0);
+        assert_eq!(result.rejected, Some(RejectReason::LevelFull));
+        clob.add_order(create_buy_order(2, 4_990_000_000_000, 100_000_000));
+        let result = engine.match_order(&mut clob, create_buy_order(2, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::LevelFull));
+    }
+    
+    #[test]
+    fn test_no_match() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Add resting sell order at 51000
+        clob.add_order(create_sell_order(1, 5_100_000_000_000, 100_000_000));
+        
+        // Incoming buy order at 50000 (won't match)
+        let buy = create_buy_order(2, 5_000_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(!result.fully_filled);
+        assert_eq!(result.remaining, 100_000_000);
+        assert!(result.trades.is_empty());
+        assert!(result.resting_key.is_some());
+        
+        // Both orders should be on book
+        assert_eq!(clob.order_count(), 2);
+    }
+    
+    #[test]
+    fn test_price_priority() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Add asks at different prices (out of order)
+        clob.add_order(create_sell_order(1, 5_200_000_000_000, 100_000_000)); // 52000
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000)); // 50000
+        clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000)); // 51000
+        
+        // Buy should match against lowest ask first
+        let buy = create_buy_order(4, 5_200_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(result.fully_filled);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].price, 5_000_000_000_000); // Matched at 50000
+        assert_eq!(result.trades[0].maker_order_id, 2);
+    }
+    
+    #[test]
+    fn test_trades_in_execution_order() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Levels and queue entries deliberately added out of price order
+        clob.add_order(create_sell_order(1, 5_020_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_010_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(4, 5_000_000_000_000, 50_000_000));
+        clob.add_order(create_sell_order(5, 5_010_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(6, 5_020_000_000_000, 100_000_000));
+        
+        let result = engine.match_order(&mut clob, create_buy_order(7, 5_020_000_000_000, 500_000_000), 0);
+        
+        let executed: Vec<_> = result.trades.iter().map(|t| (t.id, t.maker_order_id, t.price, t.quantity)).collect();
+        assert_eq!(executed, vec![
+            (1, 2, 5_000_000_000_000, 100_000_000),
+            (2, 4, 5_000_000_000_000, 50_000_000),
+            (3, 3, 5_010_000_000_000, 100_000_000),
+            (4, 5, 5_010_000_000_000, 100_000_000),
+            (5, 1, 5_020_000_000_000, 100_000_000),
+            (6, 6, 5_020_000_000_000, 50_000_000),
+        ]);
+        
+        // The same holds on the bid side: highest first
+        clob.add_order(create_buy_order(8, 4_990_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(9, 4_995_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(10, 4_990_000_000_000, 100_000_000));
+        let result = engine.match_order(&mut clob, create_sell_order(11, 4_990_000_000_000, 300_000_000), 0);
+        let makers: Vec<_> = result.trades.iter().map(|t| (t.id, t.maker_order_id)).collect();
+        assert_eq!(makers, vec![(7, 9), (8, 8), (9, 10)]);
+    }
+    
+    #[test]
+    fn test_bbo_changed() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // First quotes on each side set the touch
+        assert!(engine.match_order(&mut clob, create_buy_order(1, 4_990_000_000_000, 100_000_000), 0).bbo_changed);
+        assert!(engine.match_order(&mut clob, create_sell_order(2, 5_010_000_000_000, 100_000_000), 0).bbo_changed);
+        
+        // Deep in the book, or joining the touch, leaves it in place
+        assert!(!engine.match_order(&mut clob, create_buy_order(3, 4_900_000_000_000, 100_000_000), 0).bbo_changed);
+        assert!(!engine.match_order(&mut clob, create_buy_order(4, 4_990_000_000_000, 100_000_000), 0).bbo_changed);
+        
+        // Improving the touch moves it
+        assert!(engine.match_order(&mut clob, create_buy_order(5, 5_000_000_000_000, 100_000_000), 0).bbo_changed);
+        
+        // A partial fill of the best ask leaves its price; clearing it moves it
+        assert!(!engine.match_order(&mut clob, create_buy_order(6, 5_010_000_000_000, 50_000_000), 0).bbo_changed);
+        assert!(engine.match_order(&mut clob, create_buy_order(7, 5_010_000_000_000, 50_000_000), 0).bbo_changed);
+        
+        // Rejections never report a change
+        let mut strict = MatchingEngine::new().with_max_order_quantity(1);
+        assert!(!strict.match_order(&mut clob, create_sell_order(8, 4_000_000_000_000, 100_000_000), 0).bbo_changed);
+    }
+    
+    #[test]
+    fn test_value_range_check() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_value_range_check(true);
+        
+        let result = engine.match_order(&mut clob, create_buy_order(1, MAX_FIXED + 1, 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+        let result = engine.match_order(&mut clob, create_buy_order(2, 100_000_000, MAX_FIXED + 1), 0);
+        assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+        assert!(clob.is_empty());
+        
+        // Exactly MAX_VALUE is accepted while the notional stays in range
+        let result = engine.match_order(&mut clob, create_sell_order(3, MAX_FIXED, 1), 0);
+        assert!(!result.is_rejected());
+        assert_eq!(clob.best_ask(), Some(MAX_FIXED));
+        let result = engine.match_order(&mut clob, create_buy_order(4, 1, MAX_FIXED), 0);
+        assert!(!result.is_rejected());
+        
+        // Notional is bounded at MAX_NOTIONAL, whose 100% fee fits in i64
+        let at_limit = MAX_NOTIONAL / 100_000_000 * 100_000_000;
+        let result = engine.match_order(&mut clob, create_buy_order(5, 100_000_000, at_limit), 0);
+        assert!(!result.is_rejected());
+        let result = engine.match_order(&mut clob, create_buy_order(6, 100_000_000, at_limit + 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+        let fees = crate::engine::FeeSchedule::new(10_000, 10_000).raw_fees(&Trade::new(1, 1, 2, 100, 200, 100_000_000, at_limit, 0));
+        assert_eq!(fees.taker_fee as u64, at_limit);
+        
+        // A sell is bounded by the best bid it could hit, a market buy by the worst ask
+        let result = engine.match_order(&mut clob, create_sell_order(7, 1, at_limit + 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+        let result = engine.match_order(&mut clob, Order::market(8, 100, Side::Buy, 100_000_000, 0), 0);
+        assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+        
+        // Off by default
+        let result = MatchingEngine::new().match_order(&mut clob, create_buy_order(9, 100_000_000, MAX_FIXED + 1), 0);
+        assert!(!result.is_rejected());
+    }
+    
+    #[test]
+    fn test_max_value_boundary() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(create_sell_order(1, MAX_FIXED, MAX_FIXED));
+        let resting_root = clob.compute_state_root();
+        let restored = CLOB::from_ssz_bytes(&clob.to_ssz_bytes()).unwrap();
+        assert_eq!(restored.compute_state_root(), resting_root);
+        
+        // A full fill at MAX_VALUE for MAX_VALUE keeps every notional exact
+        let result = engine.match_order(&mut clob, create_buy_order(2, MAX_FIXED, MAX_FIXED), 0);
+        assert!(result.fully_filled);
+        let notional = MAX_FIXED as u128 * MAX_FIXED as u128;
+        assert_eq!(result.trades[0].notional_raw(), notional);
+        assert_eq!(result.maker_fills[0].cumulative_notional, notional);
+        assert_eq!(result.order.cumulative_notional, notional);
+        assert_eq!(result.order.average_fill_price(), Some(MAX_FIXED));
+        assert_eq!(result.price_improvement, 0);
+        assert!(clob.is_empty());
+        assert_ne!(clob.compute_state_root(), resting_root);
+        
+        // A taker limit far above a tiny price improves by nearly MAX_VALUE * MAX_VALUE
+        clob.add_order(create_sell_order(3, 1, MAX_FIXED));
+        let result = engine.match_order(&mut clob, create_buy_order(4, MAX_FIXED, MAX_FIXED), 0);
+        assert_eq!(result.price_improvement, u64::MAX);
+        assert!(clob.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_priority_decay_at_threshold() {
+        // Maker 1 rests at t=0, maker 2 at the same price at t=5; aged at 10
+        let book = || {
+            let mut clob = CLOB::with_capacity(100).with_priority_decay(10);
+            clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+            clob.add_order(Order::new(2, 101, Side::Sell, 5_000_000_000_000, 100_000_000, 5));
+            clob
+        };
+        let taker = |now| Order::new(3, 200, Side::Buy, 5_000_000_000_000, 100_000_000, now);
+        
+        // One tick before the threshold, time priority holds
+        let mut clob = book();
+        let batch = MatchingEngine::new().match_batch(&mut clob, [taker(9)], 9);
+        assert_eq!(batch.trades[0].maker_order_id, 1);
+        
+        // Exactly at it, the newer order goes first
+        let mut clob = book();
+        let mut engine = MatchingEngine::new().with_book_events(true);
+        let batch = engine.match_batch(&mut clob, [taker(10)], 10);
+        assert_eq!(batch.trades[0].maker_order_id, 2);
+        assert_eq!(engine.take_book_events()[0], BookEvent::Decayed { now: 10, max_age: 10, seq: 3 });
+        
+        // A single match_order leaves the queues alone until the block step
+        let mut clob = book();
+        let result = engine.match_order(&mut clob, taker(10), 10);
+        assert_eq!(result.trades[0].maker_order_id, 1);
+        
+        // Plain FIFO by default
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 101, Side::Sell, 5_000_000_000_000, 100_000_000, 5));
+        let batch = MatchingEngine::new().match_batch(&mut clob, [taker(1_000)], 1_000);
+        assert_eq!(batch.trades[0].maker_order_id, 1);
+    }
+    
+    #[test]
+    fn test_pegged_order_uses_reference_price() {
+        let book = || {
+            let mut clob = CLOB::with_capacity(100);
+            clob.add_order(create_buy_order(1, 4_990_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(2, 5_010_000_000_000, 100_000_000));
+            clob
+        };
+        let pegged = Order::new(3, 200, Side::Buy, 0, 100_000_000, 0);
+        
+        // Internal mid is 50000; the reference says 50010, which crosses the ask
+        let mut clob = book();
+        let mut engine = MatchingEngine::new();
+        engine.set_reference_price(Some(5_010_000_000_000));
+        let result = engine.match_pegged(&mut clob, pegged.clone(), 0);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].price, 5_010_000_000_000);
+        assert_eq!(engine.export_state().reference_price, Some(5_010_000_000_000));
+        
+        // Without a reference the order pegs to the book mid and rests
+        let mut clob = book();
+        let mut engine = MatchingEngine::new();
+        let result = engine.match_pegged(&mut clob, pegged.clone(), 0);
+        assert!(result.trades.is_empty());
+        assert_eq!(result.order.price, 5_000_000_000_000);
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+        
+        // No reference and a one-sided book: nothing to peg to
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(create_sell_order(2, 5_010_000_000_000, 100_000_000));
+        let result = engine.match_pegged(&mut clob, pegged, 0);
+        assert_eq!(result.rejected, Some(RejectReason::NoReferencePrice));
+        assert_eq!(clob.order_count(), 1);
+    }
+    
+    #[test]
+    fn test_uncross_crossed_book() {
+        // Bulk-loaded without matching: two bids above two asks
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 1));
+        clob.add_order(Order::new(2, 101, Side::Sell, 5_010_000_000_000, 100_000_000, 2));
+        clob.add_order(Order::new(3, 102, Side::Buy, 5_020_000_000_000, 150_000_000, 3));
+        clob.add_order(Order::new(4, 103, Side::Buy, 5_005_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(5, 104, Side::Buy, 4_990_000_000_000, 100_000_000, 5));
+        
+        let mut engine = MatchingEngine::new();
+        let trades = engine.uncross(&mut clob, 1000);
+        
+        // Best prices first; the earlier order of each pair sets the price
+        let summary: Vec<_> = trades
+            .iter()
+            .map(|t| (t.maker_order_id, t.taker_order_id, t.price, t.quantity))
+            .collect();
+        assert_eq!(summary, vec![
+            (1, 3, 5_000_000_000_000, 100_000_000),
+            (2, 3, 5_010_000_000_000, 50_000_000),
+        ]);
+        
+        // Order 4 (50050) no longer crosses the remaining ask at 50100
+        assert_eq!(clob.best_bid(), Some(5_005_000_000_000));
+        assert_eq!(clob.best_ask(), Some(5_010_000_000_000));
+        assert_eq!(clob.order_count(), 3);
+        clob.verify_invariants().unwrap();
+        
+        // Already uncrossed: nothing more to do
+        assert!(engine.uncross(&mut clob, 1000).is_empty());
+        
+        // Across levels the earlier order is the maker, whichever side it is on
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(1, 100, Side::Buy, 5_020_000_000_000, 100_000_000, 1));
+        clob.add_order(Order::new(2, 101, Side::Sell, 5_000_000_000_000, 100_000_000, 2));
+        let trades = engine.uncross(&mut clob, 1000);
+        assert_eq!((trades[0].maker_order_id, trades[0].price), (1, 5_020_000_000_000));
+        assert!(clob.is_empty());
+    }
+    
+    #[test]
+    fn test_trades_for_order() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_trade_history(4);
+        
+        // Maker 1 is hit by three takers, maker 2 only by the last
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 300_000_000));
+        clob.add_order(create_sell_order(2, 5_010_000_000_000, 100_000_000));
+        for id in [10, 11] {
+            engine.match_order(&mut clob, create_buy_order(id, 5_000_000_000_000, 100_000_000), 0);
+        }
+        engine.match_order(&mut clob, create_buy_order(12, 5_010_000_000_000, 200_000_000), 0);
+        
+        let ids = |engine: &MatchingEngine, order_id| -> Vec<u64> {
+            engine.trades_for_order(order_id).iter().map(|t| t.id).collect()
+        };
+        assert_eq!(ids(&engine, 1), vec![1, 2, 3]);
+        assert_eq!(ids(&engine, 2), vec![4]);
+        assert_eq!(ids(&engine, 12), vec![3, 4]);
+        assert_eq!(ids(&engine, 10), vec![1]);
+        assert!(engine.trades_for_order(99).is_empty());
+        
+        // A fifth trade evicts the oldest from every order it touched
+        clob.add_order(create_sell_order(3, 5_000_000_000_000, 100_000_000));
+        engine.match_order(&mut clob, create_buy_order(13, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(ids(&engine, 1), vec![2, 3]);
+        assert!(engine.trades_for_order(10).is_empty());
+        assert_eq!(ids(&engine, 13), vec![5]);
+        
+        // Off by default
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(4, 5_010_000_000_000, 10_000_000));
+        let result = engine.match_order(&mut clob, create_buy_order(14, 5_010_000_000_000, 10_000_000), 0);
+        assert_eq!(result.trades.len(), 1);
+        assert!(engine.trades_for_order(14).is_empty());
+    }
+    
+    #[test]
+    fn test_min_spread() {
+        const TICK: u64 = 1_000_000_000;
+        
+        let book = || {
+            let mut clob = CLOB::with_capacity(100);
+            clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(2, 5_000_000_000_000 + 5 * TICK, 100_000_000));
+            clob
+        };
+        let mut engine = MatchingEngine::new().with_min_spread(2 * TICK);
+        
+        // Improving the bid to one tick under the ask is rejected
+        let mut clob = book();
+        let result = engine.match_order(&mut clob, create_buy_order(3, 5_000_000_000_000 + 4 * TICK, 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::SpreadTooNarrow));
+        assert_eq!(clob.order_count(), 2);
+        
+        // Two ticks under is accepted, and so is a sell two ticks over the new bid
+        let result = engine.match_order(&mut clob, create_buy_order(4, 5_000_000_000_000 + 3 * TICK, 100_000_000), 0);
+        assert_eq!(result.rejected, None);
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000 + 3 * TICK));
+        let result = engine.match_order(&mut clob, create_sell_order(5, 5_000_000_000_000 + 4 * TICK, 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::SpreadTooNarrow));
+        
+        // Crossing orders still trade
+        let result = engine.match_order(&mut clob, create_sell_order(6, 5_000_000_000_000 + 3 * TICK, 100_000_000), 0);
+        assert_eq!(result.trades.len(), 1);
+        
+        // Joining a touch already narrower than the minimum is allowed
+        let mut clob = book();
+        clob.add_order(create_sell_order(7, 5_000_000_000_000 + TICK, 100_000_000));
+        let result = engine.match_order(&mut clob, create_buy_order(8, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(result.rejected, None);
+        assert_eq!(clob.best_bid_level().unwrap().order_count, 2);
+    }
+    
+    #[test]
+    fn test_min_spread_checks_crossing_remainder() {
+        const TICK: u64 = 1_000_000_000;
+        let mut engine = MatchingEngine::new().with_min_spread(2 * TICK);
+        let book = || {
+            let mut clob = CLOB::with_capacity(100);
+            clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(2, 5_000_000_000_000 + 5 * TICK, 100_000_000));
+            clob
+        };
+        
+        // Trades the first ask; resting at 5004 would sit one tick under 5005
+        let mut clob = book();
+        let result = engine.match_order(&mut clob, create_buy_order(3, 5_000_000_000_000 + 4 * TICK, 200_000_000), 0);
+        assert_eq!(result.rejected, None);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.unfilled, 100_000_000);
+        assert!(result.resting_key.is_none());
+        assert_eq!(clob.best_bid(), None);
+        assert_eq!(clob.best_ask(), Some(5_000_000_000_000 + 5 * TICK));
+        
+        // Two ticks away, the remainder rests as usual
+        let mut clob = book();
+        let result = engine.match_order(&mut clob, create_buy_order(4, 5_000_000_000_000 + 3 * TICK, 200_000_000), 0);
+        assert_eq!(result.unfilled, 0);
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000 + 3 * TICK));
+    }
+    
+    #[test]
+    fn test_max_users() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_max_users(2);
+        let order = |id, user_id, side, price| Order::new(id, user_id, side, price, 100_000_000, 0);
+        
+        engine.match_order(&mut clob, order(1, 100, Side::Buy, 4_990_000_000_000), 0);
+        engine.match_order(&mut clob, order(2, 200, Side::Sell, 5_010_000_000_000), 0);
+        assert_eq!(clob.resting_user_count(), 2);
+        
+        // A third user is turned away, the first two keep adding orders
+        let result = engine.match_order(&mut clob, order(3, 300, Side::Buy, 4_980_000_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::TooManyUsers));
+        for (id, user_id) in [(4, 100), (5, 200)] {
+            let result = engine.match_order(&mut clob, order(id, user_id, Side::Buy, 4_970_000_000_000), 0);
+            assert_eq!(result.rejected, None);
+        }
+        assert_eq!(clob.order_count(), 4);
+        
+        // Once user 200 has nothing resting, the slot is free
+        clob.cancel_order(2);
+        clob.cancel_order(5);
+        let result = engine.match_order(&mut clob, order(6, 300, Side::Buy, 4_980_000_000_000), 0);
+        assert_eq!(result.rejected, None);
+    }
+    
+    #[test]
+    fn test_market_order_against_empty_side() {
+        let market = || Order::market(1, 100, Side::Buy, 100_000_000, 0);
+        
+        // Default: accepted with nothing filled
+        let mut clob = CLOB::with_capacity(100);
+        let root = clob.compute_state_root();
+        let result = MatchingEngine::new().match_order(&mut clob, market(), 0);
+        assert!(!result.is_rejected());
+        assert!(result.trades.is_empty());
+        assert_eq!(result.unfilled, 100_000_000);
+        assert_eq!(result.resting_key, None);
+        assert!(clob.is_empty());
+        assert_eq!(clob.compute_state_root(), root);
+        
+        // Configured: rejected, book untouched
+        let mut engine = MatchingEngine::new().with_market_liquidity_check(true);
+        let result = engine.match_order(&mut clob, market(), 0);
+        assert_eq!(result.rejected, Some(RejectReason::NoLiquidity));
+        assert!(result.trades.is_empty());
+        assert!(clob.is_empty());
+        assert_eq!(clob.compute_state_root(), root);
+        
+        // Limit orders and a non-empty opposite side are unaffected
+        assert!(!engine.match_order(&mut clob, create_buy_order(2, 4_900_000_000_000, 100_000_000), 0).is_rejected());
+        clob.add_order(create_sell_order(3, 5_000_000_000_000, 50_000_000));
+        let result = engine.match_order(&mut clob, Order::market(4, 100, Side::Buy, 100_000_000, 0), 0);
+        assert!(!result.is_rejected());
+        assert_eq!(result.unfilled, 50_000_000);
+    }
+    
+    #[test]
+    fn test_exhausted_id_range_rejects_gracefully() {
+        let mut clob = CLOB::with_capacity(100).with_id_range(10, 11);
+        let engine = MatchingEngine::new();
+        
+        let mut engine = engine.with_book_events(true);
+        let result = engine.match_order(&mut clob, create_buy_order(0, 4_900_000_000_000, 100_000_000), 0);
+        assert_eq!(clob.get_order(result.resting_key.unwrap()).unwrap().id, 10);
+        
+        // The result and the event carry the allocated ID, not 0
+        assert_eq!(result.order.id, 10);
+        match &engine.take_book_events()[..] {
+            [BookEvent::Added { order, assigned, .. }] => assert_eq!((order.id, *assigned), (10, true)),
+            events => panic!("unexpected events: {events:?}"),
+        }
+        
+        // The range is used up: auto-assigned orders are rejected, book untouched
+        let root = clob.compute_state_root();
+        let result = engine.match_order(&mut clob, create_buy_order(0, 4_900_000_000_000, 100_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::OrderIdsExhausted));
+        assert_eq!(clob.compute_state_root(), root);
+        
+        // Explicit IDs and market orders still go through
+        assert!(!engine.match_order(&mut clob, create_sell_order(20, 5_000_000_000_000, 100_000_000), 0).is_rejected());
+        let result = engine.match_order(&mut clob, Order::market(0, 101, Side::Sell, 50_000_000, 0), 0);
+        assert!(!result.is_rejected());
+        assert_eq!(result.trades.len(), 1);
+    }
+    
+    #[test]
+    fn test_exhausted_trade_ids_reject_the_match() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        clob.restore_id_counters(clob.peek_next_order_id(), u64::MAX - 2);
+        
+        // Two IDs cover both makers, so the match runs as usual
+        let result = engine.match_order(&mut clob, create_buy_order(3, 5_000_000_000_000, 50_000_000), 0);
+        assert_eq!(result.trades[0].id, u64::MAX - 2);
+        assert_eq!(clob.trade_ids_left(), 1);
+        
+        // One ID left for two makers: crossing orders are refused, book untouched
+        let root = clob.compute_state_root();
+        let result = engine.match_order(&mut clob, create_buy_order(4, 5_000_000_000_000, 50_000_000), 0);
+        assert_eq!(result.rejected, Some(RejectReason::TradeIdsExhausted));
+        let result = engine.match_order(&mut clob, Order::market(5, 100, Side::Buy, 50_000_000, 0), 0);
+        assert_eq!(result.rejected, Some(RejectReason::TradeIdsExhausted));
+        assert_eq!(clob.compute_state_root(), root);
+        
+        // Orders that cannot trade still rest, and no ID is ever reused
+        assert!(!engine.match_order(&mut clob, create_buy_order(6, 4_900_000_000_000, 50_000_000), 0).is_rejected());
+        assert_eq!(clob.next_trade_id(), Some(u64::MAX - 1));
+        assert_eq!(clob.next_trade_id(), None);
+        assert_eq!(clob.trade_ids_left(), 0);
+        
+        // Uncross stops rather than number a trade twice
+        clob.add_order(create_buy_order(7, 5_100_000_000_000, 50_000_000));
+        assert!(engine.uncross(&mut clob, 0).is_empty());
+        assert_eq!(clob.best_bid(), Some(5_100_000_000_000));
+    }
+    
+    #[test]
+    fn test_match_advances_seq_per_delta() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        let before = clob.current_seq();
+        
+        // One maker filled out, one partially filled: two deltas
+        engine.match_order(&mut clob, create_buy_order(3, 5_000_000_000_000, 150_000_000), 0);
+        assert_eq!(clob.current_seq(), before + 2);
+        
+        // Fill the rest and rest the remainder: two more
+        engine.match_order(&mut clob, create_buy_order(4, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(clob.current_seq(), before + 4);
+        
+        // A rejected order changes nothing
+        engine.match_order(&mut clob, create_buy_order(4, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(clob.current_seq(), before + 4);
+    }
+    
+    #[test]
+    fn test_priority_is_insertion_order_not_timestamp_or_id() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        let price = 5_000_000_000_000;
+        
+        // Equal timestamps, higher ID first; then an earlier timestamp last
+        for (id, ts) in [(9, 1000), (3, 1000), (5, 1000), (7, 500)] {
+            let result = engine.match_order(&mut clob, Order::new(id, 100, Side::Sell, price, 100_000_000, ts), 0);
+            assert!(result.resting_key.is_some());
+        }
+        
+        // A reused ID is rejected rather than tie-broken
+        let duplicate = engine.match_order(&mut clob, Order::new(3, 101, Side::Sell, price, 100_000_000, 1000), 0);
+        assert_eq!(duplicate.rejected, Some(RejectReason::DuplicateOrderId));
+        
+        let result = engine.match_order(&mut clob, create_buy_order(10, price, 400_000_000), 2000);
+        let makers: Vec<u64> = result.trades.iter().map(|t| t.maker_order_id).collect();
+        assert_eq!(makers, vec![9, 3, 5, 7]);
+    }
+    
+    #[test]
+    fn test_time_priority() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Add multiple orders at same price (FIFO)
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_000_000_000_000, 100_000_000));
+        
+        // Buy should match against first order added
+        let buy = create_buy_order(4, 5_000_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(result.fully_filled);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].maker_order_id, 1); // First order matched
+        
+        // Two orders should remain
+        assert_eq!(clob.order_count(), 2);
+    }
+    
+    #[test]
+    fn test_replenished_slice_queues_behind_later_arrivals() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // First visible slice, then a normal order arrives behind it
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        
+        // The slice is consumed and replenished with a fresh order
+        let result = engine.match_order(&mut clob, create_buy_order(3, 5_000_000_000_000, 100_000_000), 0);
+        assert_eq!(result.trades[0].maker_order_id, 1);
+        clob.add_order(create_sell_order(4, 5_000_000_000_000, 100_000_000));
+        
+        // The normal order fills before the replenished slice
+        assert_eq!(clob.queue_position(2), Some(0));
+        assert_eq!(clob.queue_position(4), Some(1));
+        let result = engine.match_order(&mut clob, create_buy_order(5, 5_000_000_000_000, 200_000_000), 0);
+        let makers: Vec<u64> = result.trades.iter().map(|t| t.maker_order_id).collect();
+        assert_eq!(makers, vec![2, 4]);
+    }
+    
+    #[test]
+    fn test_multi_level_match() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        // Add asks at different prices
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000)); // 50000
+        clob.add_order(create_sell_order(2, 5_100_000_000_000, 100_000_000)); // 51000
+        
+        // Buy enough to cross both levels
+        let buy = create_buy_order(3, 5_100_000_000_000, 200_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(result.fully_filled);
+        assert_eq!(result.trades.len(), 2);
+        
+        // First trade at 50000, second at 51000
+        assert_eq!(result.trades[0].price, 5_000_000_000_000);
+        assert_eq!(result.trades[1].price, 5_100_000_000_000);
+        
+        // Book should be empty
+        assert!(clob.is_empty());
+    }
+    
+    #[test]
+    fn test_match_steps() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000));
+        
+        // Resting without a fill does no matching work
+        let result = engine.match_order(&mut clob, create_buy_order(4, 4_900_000_000_000, 100_000_000), 0);
+        assert_eq!(result.steps, 0);
+        
+        // Three fills across two levels
+        let result = engine.match_order(&mut clob, create_buy_order(5, 5_100_000_000_000, 300_000_000), 0);
+        assert_eq!(result.trades.len(), 3);
+        assert_eq!(result.steps, 5);
+        
+        // Retained empty levels the sweep drops are work too
+        let mut clob = CLOB::with_capacity(100).with_level_retention(true);
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_010_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_020_000_000_000, 100_000_000));
+        clob.cancel_order(1);
+        clob.cancel_order(2);
+        let result = engine.match_order(&mut clob, create_buy_order(6, 5_020_000_000_000, 100_000_000), 0);
+        assert_eq!(result.levels_consumed, 1);
+        assert_eq!(result.steps, 4);
+    }
+    
+    #[test]
+    fn test_levels_consumed() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_010_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(4, 5_020_000_000_000, 200_000_000));
+        
+        // Resting without a fill touches no level
+        let result = engine.match_order(&mut clob, create_buy_order(10, 4_900_000_000_000, 100_000_000), 0);
+        assert_eq!(result.levels_consumed, 0);
+        
+        // One order at one level
+        let result = engine.match_order(&mut clob, create_buy_order(11, 5_000_000_000_000, 50_000_000), 0);
+        assert_eq!(result.levels_consumed, 1);
+        
+        // Rest of the first level, all of the second, half of the third
+        let result = engine.match_order(&mut clob, create_buy_order(12, 5_020_000_000_000, 350_000_000), 0);
+        assert_eq!(result.trades.len(), 4);
+        assert_eq!(result.levels_consumed, 3);
+        assert_eq!(clob.level_summary(Side::Sell, 5_020_000_000_000).unwrap().total_quantity, 100_000_000);
+    }
+    
+    #[test]
+    fn test_client_tags_echoed_on_trades() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        let maker_tag = *b"maker-quote-0001";
+        let taker_tag = *b"taker-order-0002";
+        engine.match_order(&mut clob, create_sell_order(1, 5_000_000_000_000, 100_000_000).with_client_tag(maker_tag), 0);
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
+        
+        let buy = create_buy_order(3, 5_000_000_000_000, 300_000_000).with_client_tag(taker_tag);
+        let result = engine.match_order(&mut clob, buy, 0);
+        
+        let tags: Vec<_> = result.trades.iter().map(|t| (t.maker_client_tag, t.taker_client_tag)).collect();
+        assert_eq!(tags, vec![(maker_tag, taker_tag), ([0; 16], taker_tag)]);
+        
+        // The resting remainder keeps its tag for later fills
+        assert_eq!(result.order.client_tag, taker_tag);
+        let resting = clob.get_order(result.resting_key.unwrap()).unwrap();
+        assert_eq!(resting.client_tag, taker_tag);
+    }
+    
+    #[test]
+    fn test_determinism_audit_passes_normal_matching() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_determinism_audit(true).with_monotonic_timestamps(true);
+        
+        for i in 0..20u64 {
+            let side = if i % 3 == 0 { Side::Buy } else { Side::Sell };
+            let price = 5_000_000_000_000 + (i % 4) * 10_000_000_000;
+            engine.match_order(&mut clob, Order::new(i + 1, i % 5, side, price, 100_000_000 + i, i), i);
+        }
+        
+        // Audit still on, and the book matches an unaudited run
+        assert!(engine.export_state().determinism_audit);
+        let mut plain = CLOB::with_capacity(100);
+        let mut plain_engine = MatchingEngine::new().with_monotonic_timestamps(true);
+        for i in 0..20u64 {
+            let side = if i % 3 == 0 { Side::Buy } else { Side::Sell };
+            let price = 5_000_000_000_000 + (i % 4) * 10_000_000_000;
+            plain_engine.match_order(&mut plain, Order::new(i + 1, i % 5, side, price, 100_000_000 + i, i), i);
+        }
+        assert_eq!(plain.compute_state_root(), clob.compute_state_root());
+    }
+    
+    #[test]
+    #[should_panic(expected = "determinism audit failed for order 2")]
+    fn test_determinism_audit_catches_nondeterministic_step() {
+        use std::sync::atomic::{AtomicU64, Ordering};
+        
+        // Stamps each trade with a call counter, so no two runs agree
+        fn flaky(engine: &mut MatchingEngine, clob: &mut CLOB, order: Order, timestamp: u64) -> MatchResult {
+            static CALLS: AtomicU64 = AtomicU64::new(0);
+            engine.match_order(clob, order, timestamp + CALLS.fetch_add(1, Ordering::Relaxed))
+        }
+        
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        
+        engine.audited(&mut clob, create_buy_order(2, 5_000_000_000_000, 100_000_000), 0, flaky);
     }
     
-    /// Collect orders to match at an ask price level (read-only scan)
-    ///
-    /// Returns: Vec<(key, order_id, user_id, fill_qty, fully_filled)>
-    fn collect_matches_at_ask_level(
-        &self,
-        clob: &CLOB,
-        mut remaining: u64,
-        price: u64,
-    ) -> Vec<(usize, u64, u64, u64, bool)> {
-        let mut matches = Vec::new();
+    #[test]
+    fn test_sell_matches_bids() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
         
-        let level = match clob.asks().get(&price) {
-            Some(l) => l,
-            None => return matches,
-        };
+        // Add bids at different prices
+        clob.add_order(create_buy_order(1, 4_900_000_000_000, 100_000_000)); // 49000
+        clob.add_order(create_buy_order(2, 5_100_000_000_000, 100_000_000)); // 51000
+        clob.add_order(create_buy_order(3, 5_000_000_000_000, 100_000_000)); // 50000
         
-        let mut current_key = level.head;
+        // Sell should match against highest bid first
+        let sell = create_sell_order(4, 4_900_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, sell, 1000);
         
-        while remaining > 0 && current_key.is_some() {
-            let key = current_key.unwrap();
-            
-            let node = match clob.orders().get(key) {
-                Some(n) => n,
-                None => break,
-            };
+        assert!(result.fully_filled);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].price, 5_100_000_000_000); // Matched at 51000
+        assert_eq!(result.trades[0].maker_order_id, 2);
+    }
+    
+    #[test]
+    fn test_determinism() {
+        // Run same sequence twice and verify identical results
+        fn run_sequence() -> Vec<Trade> {
+            let mut clob = CLOB::with_capacity(100);
+            let mut engine = MatchingEngine::new();
+            let mut all_trades = Vec::new();
             
-            let order_id = node.order.id;
-            let user_id = node.order.user_id;
-            let maker_remaining = node.remaining();
+            // Add orders
+            clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(2, 5_000_000_000_000, 200_000_000));
+            clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000));
             
-            // Calculate fill quantity
-            let fill_qty = remaining.min(maker_remaining);
-            let fully_filled = fill_qty >= maker_remaining;
+            // Match orders
+            let buy1 = create_buy_order(4, 5_000_000_000_000, 150_000_000);
+            let result1 = engine.match_order(&mut clob, buy1, 1000);
+            all_trades.extend(result1.trades);
             
-            matches.push((key, order_id, user_id, fill_qty, fully_filled));
+            let buy2 = create_buy_order(5, 5_100_000_000_000, 200_000_000);
+            let result2 = engine.match_order(&mut clob, buy2, 2000);
+            all_trades.extend(result2.trades);
             
-            remaining -= fill_qty;
-            current_key = node.next;
+            all_trades
         }
         
-        matches
+        let trades1 = run_sequence();
+        let trades2 = run_sequence();
+        
+        // Results must be identical
+        assert_eq!(trades1.len(), trades2.len());
+        for (t1, t2) in trades1.iter().zip(trades2.iter()) {
+            assert_eq!(t1.maker_order_id, t2.maker_order_id);
+            assert_eq!(t1.taker_order_id, t2.taker_order_id);
+            assert_eq!(t1.price, t2.price);
+            assert_eq!(t1.quantity, t2.quantity);
+        }
     }
     
-    /// Collect orders to match at a bid price level (read-only scan)
-    ///
-    /// Returns: Vec<(key, order_id, user_id, fill_qty, fully_filled)>
-    fn collect_matches_at_bid_level(
-        &self,
-        clob: &CLOB,
-        mut remaining: u64,
-        price: u64,
-    ) -> Vec<(usize, u64, u64, u64, bool)> {
-        let mut matches = Vec::new();
+    #[test]
+    fn test_is_price_compatible() {
+        // Buy order: matches if book_price <= incoming_price
+        assert!(is_price_compatible(Side::Buy, 5_000_000_000_000, 4_900_000_000_000));
+        assert!(is_price_compatible(Side::Buy, 5_000_000_000_000, 5_000_000_000_000));
+        assert!(!is_price_compatible(Side::Buy, 5_000_000_000_000, 5_100_000_000_000));
         
-        let level = match clob.bids().get(&Reverse(price)) {
-            Some(l) => l,
-            None => return matches,
-        };
+        // Sell order: matches if book_price >= incoming_price
+        assert!(is_price_compatible(Side::Sell, 5_000_000_000_000, 5_100_000_000_000));
+        assert!(is_price_compatible(Side::Sell, 5_000_000_000_000, 5_000_000_000_000));
+        assert!(!is_price_compatible(Side::Sell, 5_000_000_000_000, 4_900_000_000_000));
+    }
+    
+    #[test]
+    fn test_inverse_price_order_matching() {
+        let mut clob = CLOB::with_capacity(100).with_price_order(PriceOrder::Inverse);
+        let mut engine = MatchingEngine::new();
         
-        let mut current_key = level.head;
+        // Under inverse ordering the highest ask is best
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000)); // 50000
+        clob.add_order(create_sell_order(2, 5_200_000_000_000, 100_000_000)); // 52000
+        clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000)); // 51000
+        assert_eq!(clob.best_ask(), Some(5_200_000_000_000));
         
-        while remaining > 0 && current_key.is_some() {
-            let key = current_key.unwrap();
-            
-            let node = match clob.orders().get(key) {
-                Some(n) => n,
-                None => break,
-            };
-            
-            let order_id = node.order.id;
-            let user_id = node.order.user_id;
-            let maker_remaining = node.remaining();
-            
-            // Calculate fill quantity
-            let fill_qty = remaining.min(maker_remaining);
-            let fully_filled = fill_qty >= maker_remaining;
+        // A buy at 51000 crosses asks priced at or above it, best first
+        let buy = create_buy_order(4, 5_100_000_000_000, 200_000_000);
+        let result = engine.match_order(&mut clob, buy, 1000);
+        
+        assert!(result.fully_filled);
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].maker_order_id, 2);
+        assert_eq!(result.trades[0].price, 5_200_000_000_000);
+        assert_eq!(result.trades[1].maker_order_id, 3);
+        assert_eq!(result.trades[1].price, 5_100_000_000_000);
+        
+        // The 50000 ask is worse than the buy and stays on the book
+        assert_eq!(clob.best_ask(), Some(5_000_000_000_000));
+        
+        // A sell at 47000 does not cross a bid at 48000 in an inverse market
+        clob.add_order(create_buy_order(5, 4_800_000_000_000, 100_000_000));
+        let sell = create_sell_order(6, 4_700_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, sell, 1000);
+        assert!(result.trades.is_empty());
+        clob.cancel_order(6);
+        
+        // But a sell at 49000 does
+        let sell = create_sell_order(7, 4_900_000_000_000, 100_000_000);
+        let result = engine.match_order(&mut clob, sell, 1000);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].maker_order_id, 5);
+    }
+    
+    #[test]
+    fn test_stale_timestamp_rejected() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_monotonic_timestamps(true);
+        
+        let first = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 200);
+        assert!(!engine.match_order(&mut clob, first, 0).is_rejected());
+        
+        // Equal timestamp is fine
+        let same = Order::new(2, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 200);
+        assert!(!engine.match_order(&mut clob, same, 0).is_rejected());
+        
+        // Earlier timestamp is rejected and does not touch the book
+        let stale = Order::new(3, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 100);
+        let result = engine.match_order(&mut clob, stale, 0);
+        assert_eq!(result.rejected, Some(RejectReason::StaleTimestamp));
+        assert!(result.trades.is_empty());
+        assert!(result.resting_key.is_none());
+        assert_eq!(clob.order_count(), 2);
+        
+        // The rejected order does not move the watermark
+        let later = Order::new(4, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 150);
+        assert!(engine.match_order(&mut clob, later, 0).is_rejected());
+    }
+    
+    #[test]
+    fn test_stale_timestamp_allowed_by_default() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 200), 0);
+        let result = engine.match_order(&mut clob, Order::new(2, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 100), 0);
+        
+        assert!(result.rejected.is_none());
+        assert_eq!(clob.order_count(), 2);
+    }
+    
+    #[test]
+    fn test_trade_timestamp_source() {
+        let cases = [
+            (TradeTimestampSource::Call, 300),
+            (TradeTimestampSource::Taker, 200),
+            (TradeTimestampSource::Maker, 100),
+        ];
+        
+        for (source, expected) in cases {
+            let mut clob = CLOB::with_capacity(100);
+            let mut engine = MatchingEngine::new().with_trade_timestamp_source(source);
             
-            matches.push((key, order_id, user_id, fill_qty, fully_filled));
+            clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 100));
+            let taker = Order::new(2, 200, Side::Buy, 5_000_000_000_000, 100_000_000, 200);
+            let result = engine.match_order(&mut clob, taker, 300);
             
-            remaining -= fill_qty;
-            current_key = node.next;
+            assert_eq!(result.trades.len(), 1);
+            assert_eq!(result.trades[0].timestamp, expected, "source {:?}", source);
         }
         
-        matches
+        assert_eq!(MatchingEngine::new().trade_timestamp_source(), TradeTimestampSource::Call);
     }
     
-    /// Remove a fully filled ask order
-    fn remove_ask_order(&self, clob: &mut CLOB, key: usize, order_id: u64, price: u64) {
-        // Get the node's linked list pointers first
-        let (prev_key, next_key) = match clob.orders().get(key) {
-            Some(node) => (node.prev, node.next),
-            None => return, // Order already removed, nothing to do
-        };
+    #[test]
+    fn test_self_match_single_trade() {
+        use crate::types::{ExecutionReceiptBuilder, UserFillSummary};
         
-        // Update the previous node's next pointer
-        if let Some(prev) = prev_key {
-            if let Some(prev_node) = clob.orders_mut().get_mut(prev) {
-                prev_node.next = next_key;
-            }
-        }
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
         
-        // Update the next node's prev pointer
-        if let Some(next) = next_key {
-            if let Some(next_node) = clob.orders_mut().get_mut(next) {
-                next_node.prev = prev_key;
-            }
-        }
+        // Same user on both sides, no self-trade prevention
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        let result = engine.match_order(&mut clob, Order::new(2, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0), 0);
         
-        // Update the price level's head/tail
-        if let Some(level) = clob.asks_mut().get_mut(&price) {
-            if level.head == Some(key) {
-                level.head = next_key;
-            }
-            if level.tail == Some(key) {
-                level.tail = prev_key;
-            }
-            level.order_count = level.order_count.saturating_sub(1);
+        assert_eq!(result.trades.len(), 1);
+        let trade = &result.trades[0];
+        assert_eq!(trade.maker_user_id, 100);
+        assert_eq!(trade.taker_user_id, 100);
+        assert!(trade.is_self_trade());
+        assert!(clob.is_empty());
+        
+        // The receipt counts one trade, and the user's sides net to zero
+        let mut builder = ExecutionReceiptBuilder::new(1);
+        builder.record_order(&result.order, &result.trades);
+        assert_eq!(builder.trades_executed(), 1);
+        assert_eq!(
+            builder.user_summaries(),
+            vec![UserFillSummary {
+                user_id: 100,
+                bought_quantity: 100_000_000,
+                sold_quantity: 100_000_000,
+                net_notional: 0,
+            }]
+        );
+    }
+    
+    #[test]
+    fn test_maker_cumulative_fills_accumulate() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 300_000_000, 0));
+        
+        let first = engine.match_order(&mut clob, Order::new(2, 200, Side::Buy, 5_000_000_000_000, 100_000_000, 1), 1);
+        let second = engine.match_order(&mut clob, Order::new(3, 201, Side::Buy, 5_000_000_000_000, 50_000_000, 2), 2);
+        
+        assert_eq!(first.maker_fills, vec![MakerFill {
+            order_id: 1,
+            filled: 100_000_000,
+            cumulative_filled: 100_000_000,
+            cumulative_notional: 5_000_000_000_000 * 100_000_000,
+        }]);
+        assert_eq!(second.maker_fills[0].filled, 50_000_000);
+        assert_eq!(second.maker_fills[0].cumulative_filled, 150_000_000);
+        assert_eq!(second.maker_fills[0].cumulative_notional, 5_000_000_000_000 * 150_000_000);
+        
+        // Takers carry their own totals
+        assert_eq!(second.order.cumulative_filled, 50_000_000);
+        
+        // Lifetime totals live on the resting order and survive a snapshot
+        let restored = CLOB::from_ssz_bytes(&clob.to_ssz_bytes()).unwrap();
+        let maker = restored.get_order(restored.get_key(1).unwrap()).unwrap();
+        assert_eq!(maker.cumulative_filled, 150_000_000);
+        assert_eq!(maker.average_fill_price(), Some(5_000_000_000_000));
+        
+        let bytes = ssz_rs::serialize(maker).unwrap();
+        let decoded: Order = ssz_rs::deserialize(&bytes).unwrap();
+        assert_eq!(decoded.cumulative_notional, maker.cumulative_notional);
+    }
+    
+    #[test]
+    fn test_anonymized_trades_carry_aggressor_side() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 101, Side::Buy, 4_900_000_000_000, 100_000_000, 0));
+        let result = engine.match_order(&mut clob, Order::new(3, 200, Side::Sell, 4_900_000_000_000, 200_000_000, 5), 5);
+        
+        let tape = result.anonymized_trades();
+        assert_eq!(tape.len(), 2);
+        for (anon, trade) in tape.iter().zip(&result.trades) {
+            assert_eq!(anon.aggressor_side(), Some(Side::Sell));
+            assert_eq!((anon.price, anon.quantity, anon.timestamp), (trade.price, trade.quantity, trade.timestamp));
         }
+    }
+    
+    #[test]
+    fn test_filled_makers_leave_index() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
         
-        // Remove from slab
-        clob.remove_from_slab(key);
+        // Two asks and two bids at one price each; each taker sweeps one side
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(3, 100, Side::Buy, 4_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(4, 100, Side::Buy, 4_000_000_000_000, 100_000_000, 0));
         
-        // Remove from index
-        clob.remove_from_index(order_id);
+        engine.match_order(&mut clob, Order::new(10, 200, Side::Buy, 5_000_000_000_000, 150_000_000, 1), 1);
+        engine.match_order(&mut clob, Order::new(11, 200, Side::Sell, 4_000_000_000_000, 150_000_000, 2), 2);
         
-        // Decrement count
-        clob.decrement_ask_count();
+        // Fully filled makers are gone from the index, not just the slab
+        for maker_id in [1, 3] {
+            assert!(!clob.contains_order(maker_id));
+            assert_eq!(clob.get_key(maker_id), None);
+            assert_eq!(clob.cancel_order(maker_id), None);
+        }
+        
+        // Partially filled makers still resolve
+        for maker_id in [2, 4] {
+            assert!(clob.contains_order(maker_id));
+        }
+        assert!(clob.verify_invariants().is_ok());
     }
     
-    /// Remove a fully filled bid order
-    fn remove_bid_order(&self, clob: &mut CLOB, key: usize, order_id: u64, price: u64) {
-        // Get the node's linked list pointers first
-        let (prev_key, next_key) = match clob.orders().get(key) {
-            Some(node) => (node.prev, node.next),
-            None => return, // Order already removed, nothing to do
-        };
+    #[test]
+    fn test_cancel_after_full_fill_reports_already_filled() {
+        use crate::orderbook::CancelOutcome;
+        
+        let mut clob = CLOB::with_capacity(100).with_fill_tracking(16);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Buy, 4_000_000_000_000, 100_000_000, 0));
+        engine.match_order(&mut clob, Order::new(3, 200, Side::Buy, 5_000_000_000_000, 100_000_000, 1), 1);
+        engine.match_order(&mut clob, Order::new(4, 200, Side::Sell, 4_000_000_000_000, 100_000_000, 2), 2);
+        
+        assert_eq!(clob.cancel_order_detailed(1), CancelOutcome::AlreadyFilled);
+        assert_eq!(clob.cancel_order_detailed(2), CancelOutcome::AlreadyFilled);
+        assert_eq!(clob.cancel_order_detailed(99), CancelOutcome::NotFound);
+        
+        // Takers filled in full on arrival count too
+        assert_eq!(clob.cancel_order_detailed(3), CancelOutcome::AlreadyFilled);
+        assert_eq!(clob.cancel_order_detailed(4), CancelOutcome::AlreadyFilled);
+        
+        // A taker that only partly fills rests, so it is cancelled normally
+        clob.add_order(Order::new(5, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 3));
+        engine.match_order(&mut clob, Order::new(6, 200, Side::Buy, 5_000_000_000_000, 150_000_000, 3), 3);
+        assert!(matches!(clob.cancel_order_detailed(6), CancelOutcome::Canceled(_)));
         
-        // Update the previous node's next pointer
-        if let Some(prev) = prev_key {
-            if let Some(prev_node) = clob.orders_mut().get_mut(prev) {
-                prev_node.next = next_key;
-            }
-        }
+        // Less than one lot is discarded without trading, so it was never filled
+        let mut engine = MatchingEngine::new().with_lot_size(1_000_000);
+        let result = engine.match_order(&mut clob, Order::new(7, 200, Side::Buy, 5_000_000_000_000, 500_000, 4), 4);
+        assert!(result.trades.is_empty());
+        assert_eq!(clob.cancel_order_detailed(7), CancelOutcome::NotFound);
+    }
+    
+    #[test]
+    fn test_lifo_level_matches_newest_first() {
+        use crate::orderbook::LevelOrdering;
         
-        // Update the next node's prev pointer
-        if let Some(next) = next_key {
-            if let Some(next_node) = clob.orders_mut().get_mut(next) {
-                next_node.prev = prev_key;
-            }
-        }
+        let mut fifo = CLOB::with_capacity(100);
+        let mut lifo = CLOB::with_capacity(100).with_level_ordering(LevelOrdering::Lifo);
+        let mut engine = MatchingEngine::new();
         
-        // Update the price level's head/tail
-        if let Some(level) = clob.bids_mut().get_mut(&Reverse(price)) {
-            if level.head == Some(key) {
-                level.head = next_key;
-            }
-            if level.tail == Some(key) {
-                level.tail = prev_key;
+        for clob in [&mut fifo, &mut lifo] {
+            for id in 1..=3 {
+                clob.add_order(Order::new(id, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
             }
-            level.order_count = level.order_count.saturating_sub(1);
         }
         
-        // Remove from slab
-        clob.remove_from_slab(key);
+        let taker = Order::new(10, 200, Side::Buy, 5_000_000_000_000, 150_000_000, 1);
+        let fifo_result = engine.match_order(&mut fifo, taker.clone(), 1);
+        let lifo_result = engine.match_order(&mut lifo, taker, 1);
         
-        // Remove from index
-        clob.remove_from_index(order_id);
+        let makers = |r: &MatchResult| r.trades.iter().map(|t| t.maker_order_id).collect::<Vec<_>>();
+        assert_eq!(makers(&fifo_result), vec![1, 2]);
+        assert_eq!(makers(&lifo_result), vec![3, 2]);
         
-        // Decrement count
-        clob.decrement_bid_count();
-    }
-}
-
-// ============================================================================
-// Helper Functions
-// ============================================================================
-
-/// Check if prices are compatible for matching.
-///
-/// # Arguments
-///
-/// * `incoming_side` - Side of the incoming order
-/// * `incoming_price` - Price of the incoming order
-/// * `book_price` - Price of the resting order
-///
-/// # Returns
-///
-/// `true` if the orders can match
-#[inline]
-pub fn is_price_compatible(incoming_side: Side, incoming_price: u64, book_price: u64) -> bool {
-    match incoming_side {
-        // Buy order matches if book price <= incoming price
-        Side::Buy => book_price <= incoming_price,
-        // Sell order matches if book price >= incoming price
-        Side::Sell => book_price >= incoming_price,
+        // The partially filled order keeps its place at the head
+        assert_eq!(lifo.queue_position(2), Some(0));
+        assert_eq!(lifo.queue_position(1), Some(1));
+        assert!(lifo.verify_invariants().is_ok());
     }
-}
-
-// ============================================================================
-// Unit Tests
-// ============================================================================
-
-#[cfg(test)]
-mod tests {
-    use super::*;
     
-    fn create_buy_order(id: u64, price: u64, quantity: u64) -> Order {
-        Order::new(id, 100, Side::Buy, price, quantity, 0)
+    #[test]
+    fn test_match_drops_retained_levels() {
+        let mut clob = CLOB::with_capacity(100).with_level_retention(true);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Sell, 5_100_000_000_000, 100_000_000, 0));
+        clob.cancel_order(1);
+        assert_eq!(clob.asks().len(), 2);
+        
+        // The buy skips the retained level and fills at the next price
+        let result = engine.match_order(&mut clob, Order::new(3, 101, Side::Buy, 5_100_000_000_000, 100_000_000, 0), 0);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].price, 5_100_000_000_000);
+        assert!(clob.asks().is_empty());
+        assert!(clob.verify_invariants().is_ok());
     }
     
-    fn create_sell_order(id: u64, price: u64, quantity: u64) -> Order {
-        Order::new(id, 100, Side::Sell, price, quantity, 0)
+    #[test]
+    fn test_sweep_drops_interior_retained_level() {
+        let mut clob = CLOB::with_capacity(100).with_level_retention(true);
+        let mut engine = MatchingEngine::new();
+        
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_100_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_200_000_000_000, 100_000_000));
+        clob.cancel_order(2);
+        
+        // The retained level sits between two real ones and is never quoted
+        assert_eq!(clob.asks().len(), 3);
+        assert_eq!(clob.level_summaries(Side::Sell, 10).len(), 2);
+        
+        // The sweep fills both real levels and cleans up the empty one
+        let result = engine.match_order(&mut clob, create_buy_order(4, 5_200_000_000_000, 200_000_000), 0);
+        let prices: Vec<u64> = result.trades.iter().map(|t| t.price).collect();
+        assert_eq!(prices, vec![5_000_000_000_000, 5_200_000_000_000]);
+        assert!(result.fully_filled);
+        assert!(clob.asks().is_empty());
+        assert!(clob.verify_invariants().is_ok());
     }
     
     #[test]
-    fn test_simple_match() {
+    fn test_market_buy_exhausts_asks() {
         let mut clob = CLOB::with_capacity(100);
         let mut engine = MatchingEngine::new();
         
-        // Add resting sell order
-        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Sell, 5_100_000_000_000, 200_000_000, 0));
         
-        // Incoming buy order should match
-        let buy = create_buy_order(2, 5_000_000_000_000, 100_000_000);
-        let result = engine.match_order(&mut clob, buy, 1000);
+        let result = engine.match_order(&mut clob, Order::market(3, 200, Side::Buy, 500_000_000, 0), 0);
         
-        assert!(result.fully_filled);
-        assert_eq!(result.trades.len(), 1);
+        // Lowest ask first, then the next level up
+        assert_eq!(result.trades.len(), 2);
         assert_eq!(result.trades[0].price, 5_000_000_000_000);
-        assert_eq!(result.trades[0].quantity, 100_000_000);
-        assert_eq!(result.trades[0].maker_order_id, 1);
-        assert_eq!(result.trades[0].taker_order_id, 2);
+        assert_eq!(result.trades[1].price, 5_100_000_000_000);
         
-        // Book should be empty
+        let filled: u64 = result.trades.iter().map(|t| t.quantity).sum();
+        assert_eq!(filled, 300_000_000);
+        assert_eq!(result.unfilled, 200_000_000);
+        assert_eq!(result.remaining, 200_000_000);
+        assert!(!result.fully_filled);
+        assert!(result.resting_key.is_none());
         assert!(clob.is_empty());
     }
     
     #[test]
-    fn test_partial_fill() {
+    fn test_lot_size_discards_sub_lot_remainder() {
         let mut clob = CLOB::with_capacity(100);
-        let mut engine = MatchingEngine::new();
+        let mut engine = MatchingEngine::new().with_lot_size(1_000_000); // 0.01
         
-        // Add resting sell order for 1 unit
-        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 60_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Sell, 5_100_000_000_000, 30_000_000, 0));
         
-        // Incoming buy order for 2 units
-        let buy = create_buy_order(2, 5_000_000_000_000, 200_000_000);
-        let result = engine.match_order(&mut clob, buy, 1000);
+        // 1.005 trades 0.90 and rests 0.10; the 0.005 is never a dust order
+        let result = engine.match_order(&mut clob, Order::new(3, 200, Side::Buy, 5_100_000_000_000, 100_500_000, 0), 0);
         
-        assert!(!result.fully_filled);
-        assert_eq!(result.remaining, 100_000_000);
-        assert_eq!(result.trades.len(), 1);
+        let fills: Vec<u64> = result.trades.iter().map(|t| t.quantity).collect();
+        assert_eq!(fills, vec![60_000_000, 30_000_000]);
+        assert_eq!(result.remaining, 10_000_000);
+        assert_eq!(result.unfilled, 500_000);
         assert!(result.resting_key.is_some());
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 10_000_000);
         
-        // Remaining should rest on book
-        assert_eq!(clob.order_count(), 1);
-        assert_eq!(clob.bid_count(), 1);
-        assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+        // A market order fills its whole lots and discards the rest
+        let result = engine.match_order(&mut clob, Order::market(4, 300, Side::Sell, 10_000_001, 0), 0);
+        assert_eq!(result.trades[0].quantity, 10_000_000);
+        assert_eq!((result.remaining, result.unfilled), (0, 1));
+        assert!(!result.fully_filled);
+        assert!(clob.is_empty());
+        
+        // Less than one lot never reaches the book
+        let result = engine.match_order(&mut clob, Order::new(5, 200, Side::Buy, 5_000_000_000_000, 999_999, 0), 0);
+        assert_eq!((result.remaining, result.unfilled), (0, 999_999));
+        assert!(result.resting_key.is_none());
+        assert!(clob.is_empty());
     }
     
     #[test]
-    fn test_no_match() {
+    fn test_market_sell_exhausts_bids() {
         let mut clob = CLOB::with_capacity(100);
         let mut engine = MatchingEngine::new();
         
-        // Add resting sell order at 51000
-        clob.add_order(create_sell_order(1, 5_100_000_000_000, 100_000_000));
+        clob.add_order(Order::new(1, 100, Side::Buy, 4_900_000_000_000, 200_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
         
-        // Incoming buy order at 50000 (won't match)
-        let buy = create_buy_order(2, 5_000_000_000_000, 100_000_000);
-        let result = engine.match_order(&mut clob, buy, 1000);
+        let result = engine.match_order(&mut clob, Order::market(3, 200, Side::Sell, 500_000_000, 0), 0);
         
-        assert!(!result.fully_filled);
-        assert_eq!(result.remaining, 100_000_000);
-        assert!(result.trades.is_empty());
-        assert!(result.resting_key.is_some());
+        // Highest bid first, then the next level down
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.trades[0].price, 5_000_000_000_000);
+        assert_eq!(result.trades[1].price, 4_900_000_000_000);
         
-        // Both orders should be on book
-        assert_eq!(clob.order_count(), 2);
+        let filled: u64 = result.trades.iter().map(|t| t.quantity).sum();
+        assert_eq!(filled, 300_000_000);
+        assert_eq!(result.unfilled, 200_000_000);
+        assert_eq!(result.remaining, 200_000_000);
+        assert!(!result.fully_filled);
+        assert!(result.resting_key.is_none());
+        assert!(clob.is_empty());
     }
     
     #[test]
-    fn test_price_priority() {
+    fn test_market_order_fills_without_exhausting() {
         let mut clob = CLOB::with_capacity(100);
         let mut engine = MatchingEngine::new();
         
-        // Add asks at different prices (out of order)
-        clob.add_order(create_sell_order(1, 5_200_000_000_000, 100_000_000)); // 52000
-        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000)); // 50000
-        clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000)); // 51000
-        
-        // Buy should match against lowest ask first
-        let buy = create_buy_order(4, 5_200_000_000_000, 100_000_000);
-        let result = engine.match_order(&mut clob, buy, 1000);
+        clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 300_000_000, 0));
         
+        let result = engine.match_order(&mut clob, Order::market(2, 200, Side::Sell, 100_000_000, 0), 0);
         assert!(result.fully_filled);
-        assert_eq!(result.trades.len(), 1);
-        assert_eq!(result.trades[0].price, 5_000_000_000_000); // Matched at 50000
-        assert_eq!(result.trades[0].maker_order_id, 2);
+        assert_eq!(result.unfilled, 0);
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 200_000_000);
+        
+        // Nothing to sweep: the whole size comes back unfilled
+        let result = engine.match_order(&mut clob, Order::market(3, 200, Side::Buy, 100_000_000, 0), 0);
+        assert!(result.trades.is_empty());
+        assert_eq!(result.unfilled, 100_000_000);
+        assert_eq!(clob.ask_count(), 0);
     }
     
     #[test]
-    fn test_time_priority() {
+    fn test_duplicate_order_id_rejected() {
         let mut clob = CLOB::with_capacity(100);
         let mut engine = MatchingEngine::new();
         
-        // Add multiple orders at same price (FIFO)
-        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
-        clob.add_order(create_sell_order(2, 5_000_000_000_000, 100_000_000));
-        clob.add_order(create_sell_order(3, 5_000_000_000_000, 100_000_000));
+        engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0), 0);
+        let result = engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 5_100_000_000_000, 200_000_000, 0), 0);
         
-        // Buy should match against first order added
-        let buy = create_buy_order(4, 5_000_000_000_000, 100_000_000);
-        let result = engine.match_order(&mut clob, buy, 1000);
+        assert_eq!(result.rejected, Some(RejectReason::DuplicateOrderId));
+        assert!(result.replaced.is_none());
+        assert_eq!(clob.order_count(), 1);
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
         
-        assert!(result.fully_filled);
+        // Once the original is gone the ID may be reused
+        clob.cancel_order(7);
+        let result = engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 5_100_000_000_000, 200_000_000, 0), 0);
+        assert!(!result.is_rejected());
+    }
+    
+    #[test]
+    fn test_duplicate_order_id_replaced() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_replace_on_duplicate(true);
+        
+        clob.add_order(Order::new(1, 200, Side::Sell, 5_100_000_000_000, 100_000_000, 0));
+        engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0), 0);
+        
+        // The replacement crosses the ask, so the old bid must not rest alongside it
+        let result = engine.match_order(&mut clob, Order::new(7, 100, Side::Buy, 5_100_000_000_000, 300_000_000, 0), 0);
+        
+        let replaced = result.replaced.expect("old order should be replaced");
+        assert_eq!(replaced.price, 5_000_000_000_000);
         assert_eq!(result.trades.len(), 1);
-        assert_eq!(result.trades[0].maker_order_id, 1); // First order matched
+        assert_eq!(result.remaining, 200_000_000);
         
-        // Two orders should remain
-        assert_eq!(clob.order_count(), 2);
+        assert_eq!(clob.order_count(), 1);
+        assert_eq!(clob.best_bid(), Some(5_100_000_000_000));
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 200_000_000);
+        assert!(clob.verify_invariants().is_ok());
     }
     
     #[test]
-    fn test_multi_level_match() {
+    fn test_replace_requires_same_user() {
         let mut clob = CLOB::with_capacity(100);
-        let mut engine = MatchingEngine::new();
+        let mut engine = MatchingEngine::new().with_replace_on_duplicate(true);
+        engine.match_order(&mut clob, create_buy_order(7, 5_000_000_000_000, 100_000_000), 0);
+        let root = clob.compute_state_root();
         
-        // Add asks at different prices
-        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000)); // 50000
-        clob.add_order(create_sell_order(2, 5_100_000_000_000, 100_000_000)); // 51000
+        // User 101 reusing user 100's order ID neither cancels nor replaces it
+        let result = engine.match_order(&mut clob, Order::new(7, 101, Side::Buy, 5_100_000_000_000, 300_000_000, 0), 0);
+        assert_eq!(result.rejected, Some(RejectReason::DuplicateOrderId));
+        assert!(result.replaced.is_none());
+        assert_eq!(clob.compute_state_root(), root);
+        assert_eq!(clob.get_order(clob.get_key(7).unwrap()).unwrap().user_id, 100);
         
-        // Buy enough to cross both levels
-        let buy = create_buy_order(3, 5_100_000_000_000, 200_000_000);
-        let result = engine.match_order(&mut clob, buy, 1000);
+        // The owner still can
+        let result = engine.match_order(&mut clob, create_buy_order(7, 5_100_000_000_000, 300_000_000), 0);
+        assert!(result.replaced.is_some());
+    }
+    
+    #[test]
+    fn test_amend_goes_through_match_path() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new().with_book_events(true).with_value_range_check(true);
+        let tag = *b"desk-3/quote-002";
         
-        assert!(result.fully_filled);
-        assert_eq!(result.trades.len(), 2);
+        clob.add_order(Order::new(1, 200, Side::Sell, 5_100_000_000_000, 100_000_000, 0));
+        clob.add_order(create_buy_order(7, 5_000_000_000_000, 300_000_000).with_client_tag(tag));
+        clob.add_order(create_buy_order(8, 4_900_000_000_000, 100_000_000));
+        let mut replica = clob.clone_rehashed();
         
-        // First trade at 50000, second at 51000
-        assert_eq!(result.trades[0].price, 5_000_000_000_000);
-        assert_eq!(result.trades[1].price, 5_100_000_000_000);
+        // Repriced through the ask: trades, and the rest rests at the new price
+        let result = engine.amend(&mut clob, 7, 5_100_000_000_000, 300_000_000, 1).unwrap();
+        assert_eq!(result.replaced.as_ref().unwrap().id, 7);
+        assert_eq!(result.trades.len(), 1);
+        assert_eq!(result.trades[0].taker_client_tag, tag);
+        assert_eq!(result.trades[0].taker_user_id, 100);
+        assert_eq!(clob.best_bid(), Some(5_100_000_000_000));
+        assert!(clob.best_ask().is_none());
         
-        // Book should be empty
-        assert!(clob.is_empty());
+        // A rejected replacement leaves the original resting
+        let root = clob.compute_state_root();
+        let result = engine.amend(&mut clob, 7, MAX_FIXED + 1, 100_000_000, 2).unwrap();
+        assert_eq!(result.rejected, Some(RejectReason::ValueOutOfRange));
+        assert_eq!(clob.compute_state_root(), root);
+        
+        // Zero cancels; unknown IDs do nothing; replace stays off
+        assert!(engine.amend(&mut clob, 8, 4_900_000_000_000, 0, 3).is_none());
+        assert!(!clob.contains_order(8));
+        assert!(engine.amend(&mut clob, 99, 4_900_000_000_000, 1, 3).is_none());
+        assert!(!engine.export_state().replace_on_duplicate);
+        
+        // The event log replays every step
+        for event in engine.take_book_events() {
+            assert!(replica.apply_event(&event));
+        }
+        assert_eq!(replica.compute_state_root(), clob.compute_state_root());
     }
     
     #[test]
-    fn test_sell_matches_bids() {
+    fn test_marketable_replace_keeps_identity() {
         let mut clob = CLOB::with_capacity(100);
-        let mut engine = MatchingEngine::new();
-        
-        // Add bids at different prices
-        clob.add_order(create_buy_order(1, 4_900_000_000_000, 100_000_000)); // 49000
-        clob.add_order(create_buy_order(2, 5_100_000_000_000, 100_000_000)); // 51000
-        clob.add_order(create_buy_order(3, 5_000_000_000_000, 100_000_000)); // 50000
+        let mut engine = MatchingEngine::new().with_replace_on_duplicate(true);
+        let tag = *b"desk-3/quote-001";
         
-        // Sell should match against highest bid first
-        let sell = create_sell_order(4, 4_900_000_000_000, 100_000_000);
-        let result = engine.match_order(&mut clob, sell, 1000);
+        clob.add_order(Order::new(1, 200, Side::Sell, 5_100_000_000_000, 300_000_000, 0));
+        engine.match_order(&mut clob, create_buy_order(7, 5_000_000_000_000, 200_000_000).with_client_tag(tag), 0);
         
-        assert!(result.fully_filled);
+        // Repriced through the ask without a tag: trades instead of resting, tag carried over
+        let result = engine.match_order(&mut clob, create_buy_order(7, 5_100_000_000_000, 200_000_000), 0);
+        assert_eq!(result.replaced.unwrap().client_tag, tag);
         assert_eq!(result.trades.len(), 1);
-        assert_eq!(result.trades[0].price, 5_100_000_000_000); // Matched at 51000
-        assert_eq!(result.trades[0].maker_order_id, 2);
+        assert!(result.fully_filled);
+        assert_eq!(result.trades[0].taker_client_tag, tag);
+        assert_eq!(result.trades[0].taker_user_id, 100);
+        assert!(clob.best_bid().is_none());
+        
+        // An explicit tag on the replacement wins
+        engine.match_order(&mut clob, create_buy_order(8, 5_000_000_000_000, 100_000_000).with_client_tag(tag), 0);
+        let result = engine.match_order(&mut clob, create_buy_order(8, 5_100_000_000_000, 100_000_000).with_client_tag([9; 16]), 0);
+        assert_eq!(result.trades[0].taker_client_tag, [9; 16]);
     }
     
     #[test]
-    fn test_determinism() {
-        // Run same sequence twice and verify identical results
-        fn run_sequence() -> Vec<Trade> {
-            let mut clob = CLOB::with_capacity(100);
-            let mut engine = MatchingEngine::new();
-            let mut all_trades = Vec::new();
-            
-            // Add orders
-            clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
-            clob.add_order(create_sell_order(2, 5_000_000_000_000, 200_000_000));
-            clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000));
-            
-            // Match orders
-            let buy1 = create_buy_order(4, 5_000_000_000_000, 150_000_000);
-            let result1 = engine.match_order(&mut clob, buy1, 1000);
-            all_trades.extend(result1.trades);
-            
-            let buy2 = create_buy_order(5, 5_100_000_000_000, 200_000_000);
-            let result2 = engine.match_order(&mut clob, buy2, 2000);
-            all_trades.extend(result2.trades);
-            
-            all_trades
-        }
+    fn test_price_improvement() {
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = MatchingEngine::new();
         
-        let trades1 = run_sequence();
-        let trades2 = run_sequence();
+        clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Sell, 5_000_500_000_000, 100_000_000, 0));
         
-        // Results must be identical
-        assert_eq!(trades1.len(), trades2.len());
-        for (t1, t2) in trades1.iter().zip(trades2.iter()) {
-            assert_eq!(t1.maker_order_id, t2.maker_order_id);
-            assert_eq!(t1.taker_order_id, t2.taker_order_id);
-            assert_eq!(t1.price, t2.price);
-            assert_eq!(t1.quantity, t2.quantity);
-        }
+        // Buy 2.0 limit 50010: 1.0 improves by 10, 1.0 improves by 5
+        let buy = Order::new(3, 200, Side::Buy, 5_001_000_000_000, 200_000_000, 0);
+        let result = engine.match_order(&mut clob, buy, 0);
+        
+        assert_eq!(result.trades.len(), 2);
+        assert_eq!(result.price_improvement, 1_500_000_000);
+        
+        // Filling exactly at the limit improves nothing
+        clob.add_order(Order::new(4, 100, Side::Buy, 4_900_000_000_000, 50_000_000, 0));
+        let sell = Order::new(5, 200, Side::Sell, 4_900_000_000_000, 50_000_000, 0);
+        assert_eq!(engine.match_order(&mut clob, sell, 0).price_improvement, 0);
+        
+        // Market orders have no limit to improve on
+        clob.add_order(Order::new(6, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+        let market = Order::market(7, 200, Side::Buy, 100_000_000, 0);
+        assert_eq!(engine.match_order(&mut clob, market, 0).price_improvement, 0);
     }
     
     #[test]
-    fn test_is_price_compatible() {
-        // Buy order: matches if book_price <= incoming_price
-        assert!(is_price_compatible(Side::Buy, 5_000_000_000_000, 4_900_000_000_000));
-        assert!(is_price_compatible(Side::Buy, 5_000_000_000_000, 5_000_000_000_000));
-        assert!(!is_price_compatible(Side::Buy, 5_000_000_000_000, 5_100_000_000_000));
+    fn test_engine_state_roundtrip_continues_identically() {
+        let orders: Vec<Order> = (0..40u64)
+            .map(|i| {
+                let side = if i.is_multiple_of(3) { Side::Sell } else { Side::Buy };
+                let price = 5_000_000_000_000 + (i % 5) * 100_000_000;
+                // One order arrives with a stale timestamp after the split
+                let timestamp = if i == 25 { 1 } else { i * 10 };
+                Order::new(i + 1, 100 + i % 4, side, price, 50_000_000 + (i % 3) * 25_000_000, timestamp)
+            })
+            .collect();
         
-        // Sell order: matches if book_price >= incoming_price
-        assert!(is_price_compatible(Side::Sell, 5_000_000_000_000, 5_100_000_000_000));
-        assert!(is_price_compatible(Side::Sell, 5_000_000_000_000, 5_000_000_000_000));
-        assert!(!is_price_compatible(Side::Sell, 5_000_000_000_000, 4_900_000_000_000));
+        let new_engine = || {
+            MatchingEngine::new()
+                .with_monotonic_timestamps(true)
+                .with_trade_timestamp_source(TradeTimestampSource::Taker)
+        };
+        
+        // Uninterrupted run
+        let mut clob = CLOB::with_capacity(100);
+        let mut engine = new_engine();
+        let mut expected = Vec::new();
+        for order in &orders {
+            let result = engine.match_order(&mut clob, order.clone(), 0);
+            expected.push((result.is_rejected(), result.trades));
+        }
+        
+        // Snapshot after 20 orders, restore, then continue
+        let mut clob_a = CLOB::with_capacity(100);
+        let mut engine_a = new_engine();
+        let mut actual = Vec::new();
+        for order in &orders[..20] {
+            let result = engine_a.match_order(&mut clob_a, order.clone(), 0);
+            actual.push((result.is_rejected(), result.trades));
+        }
+        
+        let mut clob_b = CLOB::from_ssz_bytes(&clob_a.to_ssz_bytes()).unwrap();
+        let mut engine_b = MatchingEngine::import_state(engine_a.export_state());
+        
+        for order in &orders[20..] {
+            let result = engine_b.match_order(&mut clob_b, order.clone(), 0);
+            actual.push((result.is_rejected(), result.trades));
+        }
+        
+        assert!(expected[25].0, "stale order must be rejected in both runs");
+        assert_eq!(actual, expected);
+        assert_eq!(clob_b.compute_state_root(), clob.compute_state_root());
+        assert_eq!(engine_b.export_state(), engine.export_state());
     }
     
     #[test]
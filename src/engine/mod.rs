@@ -7,7 +7,8 @@
 //! 1. **Determinism**: Same input always produces same output
 //! 2. **Fixed-Point Math**: No floating-point operations
 //! 3. **Synchronous Execution**: No async/await in hot path
-//! 4. **Price-Time Priority**: Best price first, then FIFO
+//! 4. **Price-Time Priority**: Best price first, then the level's queue
+//!    order (FIFO unless the book is configured for LIFO)
 //!
 //! ## Matching Rules
 //!
@@ -16,6 +17,42 @@
 //! - **Partial fills** are supported
 //! - **Unfilled quantity** rests on the book
 //!
+//! ## Fees
+//!
+//! [`FeeSchedule`] computes signed maker/taker fees per trade and caps maker
+//! rebates so the venue never pays out more than it collects.
+//!
+//! ## Pro-Rata Allocation
+//!
+//! [`allocate_pro_rata`] splits a quantity across makers by resting size
+//! with a deterministic rule for the rounding residual.
+//!
+//! ## Call Auctions
+//!
+//! [`MatchingEngine::run_auction`] clears a collected book at one uniform,
+//! volume-maximizing price with pro-rata allocation at the margin.
+//!
+//! ## Batch Results
+//!
+//! [`MatchingEngine::match_batch`] runs a block's orders and returns one
+//! [`BatchResult`] with every result, trade, the state root and the BBO
+//! before and after, plus a canonical byte encoding for publishing.
+//!
+//! ## Sealed Batches
+//!
+//! [`CommitmentBook`] takes order commitments first and reveals later,
+//! matching revealed orders in commit order so they cannot be front-run.
+//!
+//! ## Replay
+//!
+//! [`replay_and_verify`] re-executes an order sequence on a fresh book and
+//! checks the result against a claimed state root.
+//!
+//! ## Sharding
+//!
+//! [`ShardedEngine`] runs independent symbols on separate worker threads
+//! while keeping each symbol's execution single-threaded and deterministic.
+//!
 //! ## Example
 //!
 //! ```
@@ -38,7 +75,21 @@
 //! assert_eq!(result.trades.len(), 1);
 //! ```
 
+pub mod auction;
+pub mod batch;
+pub mod commit_reveal;
+pub mod fees;
 pub mod matcher;
+pub mod pro_rata;
+pub mod replay;
+pub mod sharded;
 
-pub use matcher::{MatchingEngine, MatchResult};
+pub use auction::AuctionResult;
+pub use batch::BatchResult;
+pub use commit_reveal::{Commitment, CommitmentBook, SealedBatchResult, SealedOrderError};
+pub use fees::{FeeSchedule, RebateFloor, TradeFees};
+pub use matcher::{EngineState, MakerFill, MatchingEngine, MatchResult, RejectReason, TradeTimestampSource};
+pub use pro_rata::allocate_pro_rata;
+pub use replay::{replay_and_verify, ReplayMismatch};
+pub use sharded::{ShardedEngine, SymbolId};
 
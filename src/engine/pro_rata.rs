@@ -0,0 +1,119 @@
+//! Pro-rata allocation of an incoming quantity across resting makers.
+//!
+//! ## Rounding Rule
+//!
+//! Each maker first receives `floor(quantity * resting / total_resting)`
+//! fixed-point units. Flooring can leave a residual of fewer units than
+//! there are makers. Those leftover units go one each to makers in
+//! descending resting size, with ties broken by ascending order ID.
+//!
+//! The result is a pure function of its inputs (independent of input
+//! order) and always sums exactly to the allocated quantity.
+
+/// Allocate `quantity` across makers in proportion to their resting size
+///
+/// # Arguments
+///
+/// * `quantity` - Quantity to allocate (fixed-point); capped at the total resting
+/// * `makers` - `(order_id, resting)` pairs
+///
+/// # Returns
+///
+/// The fill for each maker, in the same order as `makers`
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::engine::allocate_pro_rata;
+///
+/// // 5 units over 3/3/1: floors are 2/2/0, the residual unit goes to
+/// // the larger maker with the lower order ID
+/// let fills = allocate_pro_rata(5, &[(10, 3), (11, 3), (12, 1)]);
+/// assert_eq!(fills, vec![3, 2, 0]);
+/// ```
+pub fn allocate_pro_rata(quantity: u64, makers: &[(u64, u64)]) -> Vec<u64> {
+    let total: u128 = makers.iter().map(|&(_, resting)| resting as u128).sum();
+    if total == 0 {
+        return vec![0; makers.len()];
+    }
+    
+    let quantity = (quantity as u128).min(total);
+    
+    // Proportional floor; each share is strictly below the maker's size
+    // unless the whole book is taken
+    let mut fills: Vec<u64> = makers
+        .iter()
+        .map(|&(_, resting)| (quantity * resting as u128 / total) as u64)
+        .collect();
+    
+    let allocated: u128 = fills.iter().map(|&f| f as u128).sum();
+    let mut residual = (quantity - allocated) as u64;
+    if residual == 0 {
+        return fills;
+    }
+    
+    // Largest resting size first, then lowest order ID
+    let mut priority: Vec<usize> = (0..makers.len()).collect();
+    priority.sort_by(|&a, &b| {
+        makers[b].1.cmp(&makers[a].1).then(makers[a].0.cmp(&makers[b].0))
+    });
+    
+    for index in priority {
+        if residual == 0 {
+            break;
+        }
+        if fills[index] < makers[index].1 {
+            fills[index] += 1;
+            residual -= 1;
+        }
+    }
+    
+    fills
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_allocation_seven_over_three_three_one() {
+        let makers = [(1, 3), (2, 3), (3, 1)];
+        
+        assert_eq!(allocate_pro_rata(7, &makers), vec![3, 3, 1]);
+        
+        // 6 of 7: floors 2/2/0, residual 2 -> both size-3 makers
+        assert_eq!(allocate_pro_rata(6, &makers), vec![3, 3, 0]);
+        
+        // 5 of 7: floors 2/2/0, residual 1 -> size 3 with the lower ID
+        assert_eq!(allocate_pro_rata(5, &makers), vec![3, 2, 0]);
+    }
+    
+    #[test]
+    fn test_allocation_independent_of_input_order() {
+        let forward = allocate_pro_rata(5, &[(1, 3), (2, 3), (3, 1)]);
+        let reversed = allocate_pro_rata(5, &[(3, 1), (2, 3), (1, 3)]);
+        
+        assert_eq!(forward, vec![3, 2, 0]);
+        assert_eq!(reversed, vec![0, 2, 3]);
+    }
+    
+    #[test]
+    fn test_allocation_conserves_quantity() {
+        let makers = [(5, 100_000_007), (9, 33_333_333), (2, 1), (7, 250_000_000)];
+        let total: u64 = makers.iter().map(|m| m.1).sum();
+        
+        for quantity in [0, 1, 3, 99_999_999, 123_456_789, total - 1, total, total + 50] {
+            let fills = allocate_pro_rata(quantity, &makers);
+            assert_eq!(fills.iter().sum::<u64>(), quantity.min(total));
+            for (fill, &(_, resting)) in fills.iter().zip(&makers) {
+                assert!(*fill <= resting);
+            }
+        }
+        
+        assert_eq!(allocate_pro_rata(10, &[]), Vec::<u64>::new());
+    }
+}
@@ -0,0 +1,146 @@
+//! Replay verification for light clients.
+//!
+//! A verifier that only knows a block's ordered inputs and its claimed state
+//! root re-executes the inputs against a fresh book and compares roots.
+//! Because matching is fully deterministic, any difference in inputs or
+//! execution shows up as a root mismatch.
+
+use crate::engine::MatchingEngine;
+use crate::orderbook::CLOB;
+use crate::types::{ExecutionReceipt, Order};
+
+/// Replay produced a different state root than the one claimed.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "state root mismatch after {orders_processed} orders ({trades_executed} trades): \
+     expected {}, actual {}",
+    hex::encode(.expected_root),
+    hex::encode(.actual_root)
+)]
+pub struct ReplayMismatch {
+    /// Root the caller claimed
+    pub expected_root: [u8; 32],
+    
+    /// Root produced by the replay
+    pub actual_root: [u8; 32],
+    
+    /// Number of orders replayed
+    pub orders_processed: u64,
+    
+    /// Number of trades the replay generated
+    pub trades_executed: u64,
+}
+
+/// Replay an order sequence on a fresh book and check the resulting root
+///
+/// Each order is matched with its own `timestamp` as the call timestamp.
+///
+/// # Arguments
+///
+/// * `orders` - The full ordered input sequence
+/// * `expected_root` - The state root claimed for the sequence
+///
+/// # Returns
+///
+/// A receipt for the replayed batch if the roots match, otherwise a
+/// [`ReplayMismatch`] describing both roots.
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::engine::replay_and_verify;
+/// use dark_hypercore::orderbook::CLOB;
+/// use dark_hypercore::types::{Order, Side};
+///
+/// let orders = vec![Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0)];
+///
+/// let mut clob = CLOB::new();
+/// clob.add_order(orders[0].clone());
+///
+/// let receipt = replay_and_verify(&orders, clob.compute_state_root()).unwrap();
+/// assert_eq!(receipt.orders_processed, 1);
+/// ```
+pub fn replay_and_verify(orders: &[Order], expected_root: [u8; 32]) -> Result<ExecutionReceipt, ReplayMismatch> {
+    let mut clob = CLOB::with_capacity(orders.len());
+    let mut engine = MatchingEngine::new();
+    let mut trades_executed = 0u64;
+    
+    for order in orders {
+        let result = engine.match_order(&mut clob, order.clone(), order.timestamp);
+        trades_executed += result.trades.len() as u64;
+    }
+    
+    let actual_root = clob.compute_state_root();
+    let orders_processed = orders.len() as u64;
+    
+    if actual_root != expected_root {
+        return Err(ReplayMismatch {
+            expected_root,
+            actual_root,
+            orders_processed,
+            trades_executed,
+        });
+    }
+    
+    let timestamp = orders.last().map_or(0, |order| order.timestamp);
+    Ok(ExecutionReceipt::new(0, orders_processed, trades_executed, actual_root, timestamp))
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    
+    fn sample_orders() -> Vec<Order> {
+        vec![
+            Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 1),
+            Order::new(2, 101, Side::Sell, 5_010_000_000_000, 100_000_000, 2),
+            Order::new(3, 102, Side::Buy, 5_000_000_000_000, 50_000_000, 3),
+            Order::new(4, 103, Side::Buy, 4_990_000_000_000, 100_000_000, 4),
+        ]
+    }
+    
+    fn root_of(orders: &[Order]) -> [u8; 32] {
+        let mut clob = CLOB::new();
+        let mut engine = MatchingEngine::new();
+        for order in orders {
+            engine.match_order(&mut clob, order.clone(), order.timestamp);
+        }
+        clob.compute_state_root()
+    }
+    
+    #[test]
+    fn test_replay_matching_root() {
+        let orders = sample_orders();
+        let root = root_of(&orders);
+        
+        let receipt = replay_and_verify(&orders, root).expect("roots should match");
+        assert_eq!(receipt.state_root, root);
+        assert_eq!(receipt.orders_processed, 4);
+        assert_eq!(receipt.trades_executed, 1);
+        assert_eq!(receipt.timestamp, 4);
+    }
+    
+    #[test]
+    fn test_replay_tampered_order() {
+        let orders = sample_orders();
+        let root = root_of(&orders);
+        
+        let mut tampered = orders.clone();
+        tampered[2].quantity = 100_000_000;
+        tampered[2].remaining = 100_000_000;
+        
+        let err = replay_and_verify(&tampered, root).unwrap_err();
+        assert_eq!(err.expected_root, root);
+        assert_ne!(err.actual_root, root);
+        assert_eq!(err.orders_processed, 4);
+        
+        let message = err.to_string();
+        assert!(message.contains(&hex::encode(root)));
+        assert!(message.contains(&hex::encode(err.actual_root)));
+    }
+}
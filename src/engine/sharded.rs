@@ -0,0 +1,303 @@
+//! Symbol-sharded execution across worker threads.
+//!
+//! ## Design
+//!
+//! A `ShardedEngine` owns N worker threads. Every symbol is assigned to
+//! exactly one shard (`symbol % N`), and each shard owns the `CLOB` and
+//! `MatchingEngine` for all of its symbols. Different symbols therefore
+//! match in parallel, while every order for a given symbol is processed
+//! by a single thread in submission order.
+//!
+//! ## Determinism
+//!
+//! - Symbol-to-shard routing is a pure function of the symbol ID
+//! - Each shard drains its queue in FIFO order
+//! - Per-symbol books never interact, so inter-symbol timing cannot
+//!   affect any book's state
+//! - The combined root hashes per-symbol roots in ascending symbol order
+//!
+//! As long as each symbol's orders are submitted in a fixed order, the
+//! combined state root is reproducible regardless of how submissions for
+//! different symbols interleave.
+//!
+//! ## Example
+//!
+//! ```
+//! use dark_hypercore::engine::ShardedEngine;
+//! use dark_hypercore::types::{Order, Side};
+//!
+//! let engine = ShardedEngine::new(2, 1000);
+//!
+//! engine.submit(1, Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0), 0);
+//! engine.submit(2, Order::new(1, 100, Side::Buy, 300_000_000_000, 100_000_000, 0), 0);
+//!
+//! let roots = engine.state_roots();
+//! assert_eq!(roots.len(), 2);
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread::{self, JoinHandle};
+
+use sha2::{Sha256, Digest};
+
+use crate::engine::MatchingEngine;
+use crate::orderbook::CLOB;
+use crate::types::Order;
+
+/// Symbol identifier used for shard routing
+pub type SymbolId = u64;
+
+/// Per-symbol state roots reported by one shard
+type ShardRoots = Vec<(SymbolId, [u8; 32])>;
+
+/// Work item sent to a shard thread
+enum ShardCommand {
+    /// Match an order against the symbol's book
+    Submit {
+        symbol: SymbolId,
+        order: Order,
+        timestamp: u64,
+    },
+    /// Report the state root of every symbol owned by the shard
+    Roots(Sender<ShardRoots>),
+    /// Stop the worker loop
+    Shutdown,
+}
+
+/// Per-symbol state owned by a shard thread
+struct SymbolBook {
+    clob: CLOB,
+    engine: MatchingEngine,
+}
+
+/// Multi-threaded engine that partitions symbols across worker books.
+///
+/// Submission is non-blocking; queries such as [`ShardedEngine::state_roots`]
+/// wait for every shard to drain the orders queued before the query.
+pub struct ShardedEngine {
+    /// Command channel for each shard (index = shard number)
+    senders: Vec<Sender<ShardCommand>>,
+    
+    /// Worker thread handles, joined on drop
+    workers: Vec<JoinHandle<()>>,
+}
+
+impl ShardedEngine {
+    /// Create a sharded engine with `num_shards` worker threads
+    ///
+    /// # Arguments
+    ///
+    /// * `num_shards` - Number of worker threads (at least 1)
+    /// * `symbol_capacity` - Order capacity pre-allocated per symbol book
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_shards` is zero
+    pub fn new(num_shards: usize, symbol_capacity: usize) -> Self {
+        assert!(num_shards > 0, "ShardedEngine needs at least one shard");
+        
+        let mut senders = Vec::with_capacity(num_shards);
+        let mut workers = Vec::with_capacity(num_shards);
+        
+        for _ in 0..num_shards {
+            let (tx, rx) = mpsc::channel();
+            senders.push(tx);
+            workers.push(thread::spawn(move || run_shard(rx, symbol_capacity)));
+        }
+        
+        Self { senders, workers }
+    }
+    
+    /// Get the number of shards
+    #[inline]
+    pub fn num_shards(&self) -> usize {
+        self.senders.len()
+    }
+    
+    /// Get the shard that owns a symbol
+    #[inline]
+    pub fn shard_for(&self, symbol: SymbolId) -> usize {
+        (symbol % self.senders.len() as u64) as usize
+    }
+    
+    /// Route an order to the shard that owns its symbol
+    ///
+    /// Orders for the same symbol are matched in the order they are
+    /// submitted. This call does not wait for matching to complete.
+    ///
+    /// # Arguments
+    ///
+    /// * `symbol` - Symbol the order trades
+    /// * `order` - The incoming order
+    /// * `timestamp` - Current timestamp (for trade records)
+    pub fn submit(&self, symbol: SymbolId, order: Order, timestamp: u64) {
+        let shard = self.shard_for(symbol);
+        self.senders[shard]
+            .send(ShardCommand::Submit { symbol, order, timestamp })
+            .expect("Shard thread terminated");
+    }
+    
+    /// Get the state root of every symbol, sorted by symbol ID
+    ///
+    /// Blocks until each shard has processed all previously submitted orders.
+    pub fn state_roots(&self) -> BTreeMap<SymbolId, [u8; 32]> {
+        // Ask every shard first so they drain in parallel
+        let replies: Vec<Receiver<ShardRoots>> = self
+            .senders
+            .iter()
+            .map(|sender| {
+                let (tx, rx) = mpsc::channel();
+                sender.send(ShardCommand::Roots(tx)).expect("Shard thread terminated");
+                rx
+            })
+            .collect();
+        
+        let mut roots = BTreeMap::new();
+        for reply in replies {
+            let shard_roots = reply.recv().expect("Shard thread terminated");
+            roots.extend(shard_roots);
+        }
+        roots
+    }
+    
+    /// Compute a combined state root over all symbols
+    ///
+    /// SHA-256 of `(symbol, root)` pairs in ascending symbol order.
+    pub fn combined_state_root(&self) -> [u8; 32] {
+        combine_roots(&self.state_roots())
+    }
+}
+
+impl Drop for ShardedEngine {
+    fn drop(&mut self) {
+        for sender in &self.senders {
+            // A shard that already exited has nothing left to stop
+            let _ = sender.send(ShardCommand::Shutdown);
+        }
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+impl std::fmt::Debug for ShardedEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ShardedEngine")
+            .field("num_shards", &self.senders.len())
+            .finish()
+    }
+}
+
+/// Combine per-symbol roots into a single root
+///
+/// Exposed so sequential (single-threaded) processing can produce the
+/// same combined root for comparison.
+pub fn combine_roots(roots: &BTreeMap<SymbolId, [u8; 32]>) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for (symbol, root) in roots {
+        hasher.update(symbol.to_le_bytes());
+        hasher.update(root);
+    }
+    
+    let result = hasher.finalize();
+    let mut combined = [0u8; 32];
+    combined.copy_from_slice(&result);
+    combined
+}
+
+/// Worker loop: owns the books for every symbol routed to this shard
+fn run_shard(rx: Receiver<ShardCommand>, symbol_capacity: usize) {
+    let mut books: HashMap<SymbolId, SymbolBook> = HashMap::new();
+    
+    while let Ok(command) = rx.recv() {
+        match command {
+            ShardCommand::Submit { symbol, order, timestamp } => {
+                let book = books.entry(symbol).or_insert_with(|| SymbolBook {
+                    clob: CLOB::with_capacity(symbol_capacity),
+                    engine: MatchingEngine::new(),
+                });
+                book.engine.match_order(&mut book.clob, order, timestamp);
+            }
+            ShardCommand::Roots(reply) => {
+                let roots = books
+                    .iter()
+                    .map(|(symbol, book)| (*symbol, book.clob.compute_state_root()))
+                    .collect();
+                // The requester may have gone away; nothing to do then
+                let _ = reply.send(roots);
+            }
+            ShardCommand::Shutdown => break,
+        }
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    
+    /// Deterministic order stream for one symbol
+    fn symbol_orders(symbol: SymbolId, count: u64) -> Vec<Order> {
+        (0..count)
+            .map(|i| {
+                let side = if (i + symbol).is_multiple_of(2) { Side::Buy } else { Side::Sell };
+                let price = 5_000_000_000_000 + ((i * 7 + symbol) % 11) * 100_000_000;
+                let quantity = 10_000_000 + ((i * 13) % 5) * 10_000_000;
+                Order::new(i + 1, 100 + (i % 3), side, price, quantity, i)
+            })
+            .collect()
+    }
+    
+    fn sequential_roots(symbols: &[SymbolId], count: u64) -> BTreeMap<SymbolId, [u8; 32]> {
+        symbols
+            .iter()
+            .map(|&symbol| {
+                let mut clob = CLOB::with_capacity(count as usize);
+                let mut engine = MatchingEngine::new();
+                for order in symbol_orders(symbol, count) {
+                    engine.match_order(&mut clob, order, 0);
+                }
+                (symbol, clob.compute_state_root())
+            })
+            .collect()
+    }
+    
+    #[test]
+    fn test_shard_routing() {
+        let engine = ShardedEngine::new(4, 10);
+        
+        assert_eq!(engine.num_shards(), 4);
+        assert_eq!(engine.shard_for(0), 0);
+        assert_eq!(engine.shard_for(5), 1);
+        assert_eq!(engine.shard_for(7), engine.shard_for(11));
+    }
+    
+    #[test]
+    fn test_concurrent_matches_sequential() {
+        const COUNT: u64 = 500;
+        let symbols: Vec<SymbolId> = (1..=6).collect();
+        
+        let engine = ShardedEngine::new(3, COUNT as usize);
+        
+        // One submitter thread per symbol, all racing each other
+        thread::scope(|scope| {
+            for &symbol in &symbols {
+                let engine = &engine;
+                scope.spawn(move || {
+                    for order in symbol_orders(symbol, COUNT) {
+                        engine.submit(symbol, order, 0);
+                    }
+                });
+            }
+        });
+        
+        let expected = sequential_roots(&symbols, COUNT);
+        assert_eq!(engine.state_roots(), expected);
+        assert_eq!(engine.combined_state_root(), combine_roots(&expected));
+    }
+}
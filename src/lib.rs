@@ -8,6 +8,7 @@
 //! - **Types**: Core data structures (Order, Trade, ExecutionReceipt)
 //! - **OrderBook**: CLOB with slab-based memory allocation
 //! - **Engine**: Deterministic matching engine
+//! - **Wire**: Compact binary order-entry messages and L2 deltas for gateways
 //!
 //! ## Design Principles
 //!
@@ -21,6 +22,16 @@
 //! - Throughput: >100,000 orders/second
 //! - Latency: <10μs per match operation
 //! - Memory: <200 bytes per order
+//!
+//! ## `no_std`
+//!
+//! With default features disabled the crate builds as `no_std` + `alloc`
+//! and exposes only [`types`]. Every other module requires the `std`
+//! feature (on by default).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 // ============================================================================
 // Module declarations
@@ -30,16 +41,28 @@
 pub mod types;
 
 /// Order book: CLOB with slab-based storage
+#[cfg(feature = "std")]
 pub mod orderbook;
 
 /// Matching engine: Deterministic order matching
+#[cfg(feature = "std")]
 pub mod engine;
 
+/// Test utilities: book diffs, equivalence assertions, stream interleaving
+#[cfg(feature = "std")]
+pub mod testing;
+
+/// Wire protocol: compact binary order-entry messages and L2 deltas
+#[cfg(feature = "std")]
+pub mod wire;
+
 // ============================================================================
 // Re-exports for convenience
 // ============================================================================
 
 pub use types::{Order, OrderType, Side, Trade, ExecutionReceipt};
-pub use orderbook::{CLOB, OrderNode, PriceLevel};
-pub use engine::{MatchingEngine, MatchResult};
+#[cfg(feature = "std")]
+pub use orderbook::{CLOB, LevelSummary, OrderNode, PriceLevel, PriceOrder, TopOfBook};
+#[cfg(feature = "std")]
+pub use engine::{MatchingEngine, MatchResult, RejectReason};
 
@@ -3,7 +3,16 @@
 //! This binary will eventually run the matching engine.
 //! For now, it serves as a simple verification that the project builds.
 
-use dark_hypercore::types::{Order, Side};
+use dark_hypercore::orderbook::CLOB;
+
+/// Demo book: two bids and two asks around 50000
+const DEMO_SCENARIO: &str = "
+    # side  price     quantity  user
+    buy     49999.00  1         100
+    buy     49998.50  2.5       101
+    sell    50001.00  1         200
+    sell    50002.00  0.75      201
+";
 
 fn main() {
     println!("===========================================");
@@ -11,24 +20,22 @@ fn main() {
     println!("===========================================");
     println!();
     
-    // Demonstrate basic type creation
-    println!("Creating sample order...");
-    let order = Order::new(
-        1,                      // id
-        100,                    // user_id
-        Side::Buy,              // side
-        5_000_000_000_000,      // price: 50000.00000000 (scaled by 10^8)
-        100_000_000,            // quantity: 1.00000000 (scaled by 10^8)
-        1703577600000,          // timestamp (ms)
-    );
+    // Seed a known book from the demo scenario
+    println!("Loading demo scenario...");
+    let clob = CLOB::from_scenario(DEMO_SCENARIO).expect("demo scenario is valid");
+    let top = clob.top_of_book();
+    println!("  Orders: {}", clob.order_count());
+    println!("  Best bid: {:?} x {:?} (raw)", top.bid_price, top.bid_size);
+    println!("  Best ask: {:?} x {:?} (raw)", top.ask_price, top.ask_size);
+    println!();
+    
+    let key = clob.get_key(1).expect("demo scenario has order 1");
+    let order = clob.get_order(key).expect("order 1 rests").clone();
     
     println!("Order created:");
-    println!("  ID: {}", order.id);
-    println!("  Side: {:?}", order.side());
+    println!("  {}", order);
     println!("  Price: {} (raw)", order.price);
-    println!("  Price: {:.8} (human)", order.price as f64 / 100_000_000.0);
     println!("  Quantity: {} (raw)", order.quantity);
-    println!("  Quantity: {:.8} (human)", order.quantity as f64 / 100_000_000.0);
     println!();
     
     // Test SSZ serialization
@@ -13,6 +13,9 @@
 //! - **Bids** (buy orders): Sorted high-to-low (best bid = highest price)
 //! - **Asks** (sell orders): Sorted low-to-high (best ask = lowest price)
 //!
+//! Inverse markets ([`PriceOrder::Inverse`]) read the same maps from the
+//! opposite end, so the lowest bid and highest ask are best.
+//!
 //! ## Memory Model
 //!
 //! Per slab docs (https://docs.rs/slab/0.4.11):
@@ -41,16 +44,158 @@
 //! ```
 
 use std::cmp::Reverse;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use slab::Slab;
 
-use crate::orderbook::{OrderNode, PriceLevel};
+use crate::orderbook::level::decrement_count;
+use crate::orderbook::{IdAllocator, LevelSummary, OrderContext, OrderNode, PriceLevel, TopOfBook};
+use crate::types::price::SCALE;
 use crate::types::{Order, Side};
 
+// ============================================================================
+// PriceOrder enum
+// ============================================================================
+
+/// Price priority convention for a book.
+///
+/// Inverse markets quote so that a higher numeric price is worse for the
+/// buyer. Flipping the convention flips which end of each price map is
+/// "best"; the maps themselves keep their numeric ordering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PriceOrder {
+    /// Highest bid and lowest ask are best
+    #[default]
+    Standard,
+    /// Lowest bid and highest ask are best
+    Inverse,
+}
+
+impl PriceOrder {
+    /// Check if an incoming order crosses a resting price
+    ///
+    /// # Arguments
+    ///
+    /// * `incoming_side` - Side of the incoming order
+    /// * `incoming_price` - Price of the incoming order
+    /// * `book_price` - Price of the resting order
+    ///
+    /// # Returns
+    ///
+    /// `true` if the orders can match
+    #[inline]
+    pub fn crosses(self, incoming_side: Side, incoming_price: u64, book_price: u64) -> bool {
+        match (self, incoming_side) {
+            (PriceOrder::Standard, Side::Buy) | (PriceOrder::Inverse, Side::Sell) => {
+                book_price <= incoming_price
+            }
+            (PriceOrder::Standard, Side::Sell) | (PriceOrder::Inverse, Side::Buy) => {
+                book_price >= incoming_price
+            }
+        }
+    }
+}
+
+// ============================================================================
+// LevelOrdering enum
+// ============================================================================
+
+/// Queue discipline within a single price level.
+///
+/// Production books are always [`LevelOrdering::Fifo`] (price-time
+/// priority). [`LevelOrdering::Lifo`] exists for research and for tests
+/// that need to show the engine's reliance on FIFO is intentional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum LevelOrdering {
+    /// New orders join the tail; the oldest order matches first
+    #[default]
+    Fifo,
+    /// New orders join the head; the newest order matches first
+    Lifo,
+}
+
+// ============================================================================
+// CancelOutcome enum
+// ============================================================================
+
+/// Result of [`CLOB::cancel_order_detailed`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CancelOutcome {
+    /// The order was resting and has been removed
+    Canceled(Order),
+    /// The order was recently fully filled by the matching engine
+    AlreadyFilled,
+    /// The order is not resting and was not recently filled
+    NotFound,
+}
+
+// ============================================================================
+// InvariantViolation error
+// ============================================================================
+
+/// A structural invariant of the book that does not hold.
+///
+/// Returned by [`CLOB::verify_invariants`] and [`CLOB::assert_consistent`].
+/// Apart from a crossed book loaded directly with `add_order`, any of these
+/// indicates an internal bug (linked-list corruption, count desync,
+/// quantity drift).
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InvariantViolation {
+    /// A price level's linked list or aggregates are inconsistent
+    #[error("{side:?} level {price}: {detail}")]
+    CorruptLevel {
+        /// Side of the level
+        side: Side,
+        /// Price of the level
+        price: u64,
+        /// What was wrong
+        detail: String,
+    },
+    
+    /// Side counts disagree with the levels or the slab
+    #[error("count mismatch: {0}")]
+    CountMismatch(String),
+    
+    /// The order index disagrees with the slab
+    #[error("order index mismatch: {0}")]
+    IndexMismatch(String),
+    
+    /// The best bid and best ask would match each other
+    #[error("book crossed: best bid {bid}, best ask {ask}")]
+    Crossed {
+        /// Best bid price
+        bid: u64,
+        /// Best ask price
+        ask: u64,
+    },
+}
+
+// ============================================================================
+// CLOB struct
+// ============================================================================
+
 /// Central Limit Order Book
 ///
 /// A high-performance order book using slab allocation for O(1) operations.
-#[derive(Debug)]
+///
+/// Cloning copies every slab slot, level and index entry, so a clone has
+/// the same slab keys and state root as the original. The copy shares
+/// nothing with the original, which makes it a fork for "what-if" matching:
+///
+/// ```
+/// use dark_hypercore::engine::MatchingEngine;
+/// use dark_hypercore::orderbook::CLOB;
+/// use dark_hypercore::types::{Order, Side};
+///
+/// let mut clob = CLOB::with_capacity(100);
+/// clob.add_order(Order::new(1, 100, Side::Sell, 100_000_000, 100_000_000, 0));
+///
+/// let mut fork = clob.clone();
+/// MatchingEngine::new().match_order(&mut fork, Order::new(2, 101, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+///
+/// assert!(fork.is_empty());
+/// assert_eq!(clob.best_ask(), Some(100_000_000));
+/// ```
+#[derive(Debug, Clone)]
 pub struct CLOB {
     /// Pre-allocated order storage
     /// Key: slab index, Value: OrderNode
@@ -69,17 +214,48 @@ pub struct CLOB {
     /// Order ID to slab key mapping (for O(1) cancel)
     order_index: HashMap<u64, usize>,
     
-    /// Next order ID (for auto-assignment)
-    next_order_id: u64,
+    /// User ID to the slab keys of that user's resting orders
+    /// (users with no resting orders have no entry)
+    user_index: HashMap<u64, HashSet<usize>>,
+    
+    /// Source of auto-assigned order IDs
+    order_ids: IdAllocator,
     
     /// Next trade ID
     next_trade_id: u64,
     
+    /// Book mutation sequence number (see `current_seq`)
+    seq: u64,
+    
     /// Total number of bid orders
     bid_count: usize,
     
     /// Total number of ask orders
     ask_count: usize,
+    
+    /// Which end of the price maps is "best"
+    price_order: PriceOrder,
+    
+    /// Where new orders join a price level's queue
+    level_ordering: LevelOrdering,
+    
+    /// Age at which an order yields priority at its level (0 = off)
+    priority_decay: u64,
+    
+    /// Keep emptied levels in the maps instead of removing them on cancel
+    retain_empty_levels: bool,
+    
+    /// How many recently filled order IDs to remember (0 = off)
+    fill_tracking: usize,
+    
+    /// Most orders one price level may hold (0 = unlimited)
+    max_level_orders: usize,
+    
+    /// Recently filled order IDs, oldest first
+    recent_fills: VecDeque<u64>,
+    
+    /// Membership set for `recent_fills`
+    recent_fill_set: HashSet<u64>,
 }
 
 impl Default for CLOB {
@@ -96,10 +272,20 @@ impl CLOB {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
             order_index: HashMap::new(),
-            next_order_id: 1,
+            user_index: HashMap::new(),
+            order_ids: IdAllocator::default(),
             next_trade_id: 1,
+            seq: 0,
             bid_count: 0,
             ask_count: 0,
+            price_order: PriceOrder::Standard,
+            level_ordering: LevelOrdering::Fifo,
+            priority_decay: 0,
+            retain_empty_levels: false,
+            fill_tracking: 0,
+            max_level_orders: 0,
+            recent_fills: VecDeque::new(),
+            recent_fill_set: HashSet::new(),
         }
     }
     
@@ -123,11 +309,355 @@ impl CLOB {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
             order_index: HashMap::with_capacity(order_capacity),
-            next_order_id: 1,
+            user_index: HashMap::new(),
+            order_ids: IdAllocator::default(),
             next_trade_id: 1,
+            seq: 0,
             bid_count: 0,
             ask_count: 0,
+            price_order: PriceOrder::Standard,
+            level_ordering: LevelOrdering::Fifo,
+            priority_decay: 0,
+            retain_empty_levels: false,
+            fill_tracking: 0,
+            max_level_orders: 0,
+            recent_fills: VecDeque::new(),
+            recent_fill_set: HashSet::new(),
+        }
+    }
+    
+    /// Set the price priority convention for this book
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::{CLOB, PriceOrder};
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_price_order(PriceOrder::Inverse);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 200_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// // Lower numeric price is the better bid in an inverse market
+    /// assert_eq!(clob.best_bid(), Some(100_000_000));
+    /// ```
+    pub fn with_price_order(mut self, price_order: PriceOrder) -> Self {
+        self.price_order = price_order;
+        self
+    }
+    
+    /// Get the price priority convention for this book
+    #[inline]
+    pub fn price_order(&self) -> PriceOrder {
+        self.price_order
+    }
+    
+    /// Set the queue discipline used when orders join a price level
+    ///
+    /// Defaults to [`LevelOrdering::Fifo`]; only change it for research.
+    /// The setting is not part of the state root or snapshot format.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::{CLOB, LevelOrdering};
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_level_ordering(LevelOrdering::Lifo);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// assert_eq!(clob.queue_position(2), Some(0));
+    /// ```
+    pub fn with_level_ordering(mut self, level_ordering: LevelOrdering) -> Self {
+        self.level_ordering = level_ordering;
+        self
+    }
+    
+    /// Get the queue discipline used when orders join a price level
+    #[inline]
+    pub fn level_ordering(&self) -> LevelOrdering {
+        self.level_ordering
+    }
+    
+    /// Auto-assign order IDs only from the reserved range `[start, end)`
+    ///
+    /// Books fed by different gateways can be given disjoint ranges (see
+    /// [`IdAllocator::partitioned`]) so their auto-assigned IDs never
+    /// collide. IDs are handed out in order from `start`; once `end` is
+    /// reached an order with ID 0 can no longer be added. Explicit IDs are
+    /// not checked against the range. The range is saved in snapshots, so a
+    /// restored book keeps allocating from it; it is not part of the state
+    /// root, but the next ID is.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty or includes 0.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_id_range(1_000, 2_000);
+    /// let key = clob.add_order(Order::new(0, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// assert_eq!(clob.get_order(key).unwrap().id, 1_000);
+    /// ```
+    pub fn with_id_range(mut self, start: u64, end: u64) -> Self {
+        self.order_ids = IdAllocator::new(start, end);
+        self
+    }
+    
+    /// Let orders lose time priority once they reach `max_age` (0 = off)
+    ///
+    /// For anti-sniping research. [`CLOB::apply_priority_decay`] moves
+    /// every order with `now - timestamp >= max_age` behind the younger
+    /// orders at its price; the matching engine applies it once per block
+    /// (see `MatchingEngine::decay_priorities`), not per order. Off by
+    /// default, leaving plain time priority. The setting is not part of
+    /// the state root or snapshot format, but the reordered queues are.
+    pub fn with_priority_decay(mut self, max_age: u64) -> Self {
+        self.priority_decay = max_age;
+        self
+    }
+    
+    /// Get the age at which orders yield priority (0 if off)
+    #[inline]
+    pub fn priority_decay(&self) -> u64 {
+        self.priority_decay
+    }
+    
+    /// Move aged orders behind younger ones at the same price
+    ///
+    /// At each level, orders aged at least the configured
+    /// [`CLOB::with_priority_decay`] threshold at `now` go to the back of
+    /// the queue, keeping their relative order, and younger orders keep
+    /// theirs ahead of them. The pass is a stable partition, so running it
+    /// again at the same `now` changes nothing. A no-op when decay is off.
+    ///
+    /// # Returns
+    ///
+    /// The number of aged orders that moved behind a younger one
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_priority_decay(1_000);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 500));
+    ///
+    /// assert_eq!(clob.apply_priority_decay(999), 0);
+    /// assert_eq!(clob.apply_priority_decay(1_000), 1);
+    /// assert_eq!(clob.queue_position(2), Some(0));
+    /// ```
+    pub fn apply_priority_decay(&mut self, now: u64) -> usize {
+        self.decay_queues(now, self.priority_decay)
+    }
+    
+    /// Move orders aged at least `max_age` at `now` behind younger ones
+    /// (0 = no-op), returning how many were overtaken
+    pub(crate) fn decay_queues(&mut self, now: u64, max_age: u64) -> usize {
+        if max_age == 0 {
+            return 0;
+        }
+        
+        let orders = &mut self.orders;
+        let mut moved = 0;
+        for level in self.bids.values_mut().chain(self.asks.values_mut()) {
+            let mut queue = Vec::with_capacity(level.order_count);
+            let mut current = level.head;
+            while let Some(key) = current {
+                let node = &orders[key];
+                queue.push((key, now.saturating_sub(node.order.timestamp) >= max_age));
+                current = node.next;
+            }
+            
+            // Only aged orders ahead of the last young one change place, but
+            // every aged order is re-queued to keep their relative order
+            let Some(last_young) = queue.iter().rposition(|&(_, aged)| !aged) else {
+                continue;
+            };
+            let overtaken = queue[..last_young].iter().filter(|&&(_, aged)| aged).count();
+            if overtaken == 0 {
+                continue;
+            }
+            for &(key, _) in queue.iter().filter(|&&(_, aged)| aged) {
+                level.remove(key, orders);
+                level.push_back(key, orders);
+            }
+            moved += overtaken;
         }
+        
+        if moved > 0 {
+            self.seq += 1;
+        }
+        moved
+    }
+    
+    /// Remember the IDs of the last `capacity` fully filled orders
+    ///
+    /// Covers resting orders filled by the engine and takers it filled in
+    /// full on arrival. Lets [`CLOB::cancel_order_detailed`] tell "already
+    /// filled" apart from "never existed". Off (0) by default so matching does no extra
+    /// work; the oldest ID is forgotten once `capacity` is reached. The
+    /// remembered IDs are not part of the state root or snapshots.
+    pub fn with_fill_tracking(mut self, capacity: usize) -> Self {
+        self.fill_tracking = capacity;
+        self.recent_fills = VecDeque::with_capacity(capacity);
+        self.recent_fill_set = HashSet::with_capacity(capacity);
+        self
+    }
+    
+    /// Get how many recently filled order IDs are remembered
+    #[inline]
+    pub fn fill_tracking(&self) -> usize {
+        self.fill_tracking
+    }
+    
+    /// Cap the number of orders a single price level may hold (0 = unlimited)
+    ///
+    /// Bounds the per-level work of pro-rata allocation, level patches and
+    /// queue walks against single-price spam. The book enforces it on
+    /// every insert: [`CLOB::try_add_order`] refuses an order for a full
+    /// level, [`CLOB::amend_order`] will not move an order onto one, and
+    /// [`CLOB::add_order`] panics. The matching engine rejects such orders
+    /// up front with `RejectReason::LevelFull`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_max_level_orders(1);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// assert!(clob.level_is_full(Side::Buy, 100_000_000));
+    /// assert!(!clob.level_is_full(Side::Buy, 99_000_000));
+    /// ```
+    pub fn with_max_level_orders(mut self, cap: usize) -> Self {
+        self.max_level_orders = cap;
+        self
+    }
+    
+    /// Get the per-level order cap (0 if unlimited)
+    #[inline]
+    pub fn max_level_orders(&self) -> usize {
+        self.max_level_orders
+    }
+    
+    /// Check whether the level at `price` on `side` has reached the cap
+    ///
+    /// Always false when no cap is set.
+    #[inline]
+    pub fn level_is_full(&self, side: Side, price: u64) -> bool {
+        if self.max_level_orders == 0 {
+            return false;
+        }
+        let level = match side {
+            Side::Buy => self.bids.get(&Reverse(price)),
+            Side::Sell => self.asks.get(&price),
+        };
+        level.is_some_and(|level| level.order_count >= self.max_level_orders)
+    }
+    
+    /// Get the number of orders in the most crowded price level (0 if empty)
+    pub fn largest_level_size(&self) -> usize {
+        self.bids
+            .values()
+            .chain(self.asks.values())
+            .map(|level| level.order_count)
+            .max()
+            .unwrap_or(0)
+    }
+    
+    /// Keep price levels emptied by cancels instead of removing them
+    ///
+    /// Under rapid add/cancel cycles at one price, this avoids freeing and
+    /// re-allocating the level's BTreeMap entry each time. A retained level
+    /// is reused by the next order at its price and dropped once a match
+    /// reaches it (or on [`CLOB::purge_empty_levels`]).
+    ///
+    /// Retained levels are invisible to the public read API: best prices,
+    /// level counts, summaries, checksum, and state root all skip them.
+    /// Only the raw [`CLOB::bids`]/[`CLOB::asks`] maps expose them.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_level_retention(true);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.cancel_order(1);
+    ///
+    /// assert_eq!(clob.best_bid(), None);
+    /// assert_eq!(clob.bid_levels(), 0);
+    /// assert_eq!(clob.bids().len(), 1);
+    /// ```
+    pub fn with_level_retention(mut self, enabled: bool) -> Self {
+        self.retain_empty_levels = enabled;
+        self
+    }
+    
+    /// Check if emptied levels are retained
+    #[inline]
+    pub fn retains_empty_levels(&self) -> bool {
+        self.retain_empty_levels
+    }
+    
+    /// Remove every retained empty level from both sides
+    ///
+    /// # Returns
+    ///
+    /// The number of levels removed
+    pub fn purge_empty_levels(&mut self) -> usize {
+        let before = self.bids.len() + self.asks.len();
+        self.bids.retain(|_, level| !level.is_empty());
+        self.asks.retain(|_, level| !level.is_empty());
+        before - self.bids.len() - self.asks.len()
+    }
+    
+    /// Drop retained empty levels sitting at the best end of a side
+    ///
+    /// Called by the matching engine before it walks a side, so retained
+    /// levels only live until a match reaches them. A no-op unless
+    /// retention is enabled. Returns the number of levels dropped.
+    pub fn prune_empty_best_levels(&mut self, side: Side) -> usize {
+        if !self.retain_empty_levels {
+            return 0;
+        }
+        
+        let before = self.bids.len() + self.asks.len();
+        match (side, self.price_order) {
+            (Side::Buy, PriceOrder::Standard) => {
+                while self.bids.first_key_value().is_some_and(|(_, l)| l.is_empty()) {
+                    self.bids.pop_first();
+                }
+            }
+            (Side::Buy, PriceOrder::Inverse) => {
+                while self.bids.last_key_value().is_some_and(|(_, l)| l.is_empty()) {
+                    self.bids.pop_last();
+                }
+            }
+            (Side::Sell, PriceOrder::Standard) => {
+                while self.asks.first_key_value().is_some_and(|(_, l)| l.is_empty()) {
+                    self.asks.pop_first();
+                }
+            }
+            (Side::Sell, PriceOrder::Inverse) => {
+                while self.asks.last_key_value().is_some_and(|(_, l)| l.is_empty()) {
+                    self.asks.pop_last();
+                }
+            }
+        }
+        before - self.bids.len() - self.asks.len()
     }
     
     // ========================================================================
@@ -146,6 +676,82 @@ impl CLOB {
         self.orders.len()
     }
     
+    /// Get the number of orders that fit before the slab must reallocate
+    #[inline]
+    pub fn remaining_capacity(&self) -> usize {
+        self.capacity().saturating_sub(self.order_count())
+    }
+    
+    /// Get the fraction of pre-allocated slots in use (0.0 to 1.0)
+    ///
+    /// Intended for monitoring only; a book with no allocated slots
+    /// reports 1.0 since the next insert will reallocate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// assert_eq!(clob.load_factor(), 0.0);
+    ///
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// assert!(clob.load_factor() > 0.0);
+    /// ```
+    pub fn load_factor(&self) -> f64 {
+        let capacity = self.capacity();
+        if capacity == 0 {
+            return 1.0;
+        }
+        self.order_count() as f64 / capacity as f64
+    }
+    
+    /// Estimate the heap and inline footprint of the book in bytes
+    ///
+    /// The estimate is the sum of:
+    ///
+    /// - Slab: `capacity() * size_of::<OrderNode>()`
+    /// - Order index: one `(u64, usize)` entry plus one control byte per
+    ///   hash map slot, scaled by 8/7 for the table's maximum load
+    /// - Price levels: `(key, PriceLevel)` per level, scaled by 3/2 for
+    ///   typical B-tree node occupancy
+    /// - The `CLOB` struct itself
+    ///
+    /// Allocator headers and padding are ignored, so treat the result as a
+    /// lower-bound approximation for checking the per-order memory target.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(1_000);
+    /// for i in 0..1_000u64 {
+    ///     clob.add_order(Order::new(i + 1, 100, Side::Buy, 100_000_000 + i % 10, 100_000_000, 0));
+    /// }
+    /// assert!(clob.estimated_memory_bytes() / clob.order_count() < 200);
+    /// ```
+    pub fn estimated_memory_bytes(&self) -> usize {
+        use std::mem::size_of;
+        
+        let slab = self.orders.capacity() * size_of::<OrderNode>();
+        let index_slot = size_of::<(u64, usize)>() + 1;
+        let index = self.order_index.capacity() * index_slot * 8 / 7;
+        let bid_levels = self.bids.len() * size_of::<(Reverse<u64>, PriceLevel)>();
+        let ask_levels = self.asks.len() * size_of::<(u64, PriceLevel)>();
+        let levels = (bid_levels + ask_levels) * 3 / 2;
+        let user_slot = size_of::<usize>() + 1;
+        let users: usize = self
+            .user_index
+            .values()
+            .map(|keys| size_of::<(u64, HashSet<usize>)>() + keys.capacity() * user_slot * 8 / 7)
+            .sum();
+        
+        size_of::<Self>() + slab + index + levels + users
+    }
+    
     /// Get the number of bid orders
     #[inline]
     pub fn bid_count(&self) -> usize {
@@ -165,15 +771,27 @@ impl CLOB {
     }
     
     /// Get the number of bid price levels
+    ///
+    /// Retained empty levels are not counted.
     #[inline]
     pub fn bid_levels(&self) -> usize {
-        self.bids.len()
+        if self.retain_empty_levels {
+            self.bids.values().filter(|l| !l.is_empty()).count()
+        } else {
+            self.bids.len()
+        }
     }
     
     /// Get the number of ask price levels
+    ///
+    /// Retained empty levels are not counted.
     #[inline]
     pub fn ask_levels(&self) -> usize {
-        self.asks.len()
+        if self.retain_empty_levels {
+            self.asks.values().filter(|l| !l.is_empty()).count()
+        } else {
+            self.asks.len()
+        }
     }
     
     // ========================================================================
@@ -192,6 +810,14 @@ impl CLOB {
     ///
     /// The slab key for the added order
     ///
+    /// # Panics
+    ///
+    /// Panics if the order has ID 0 and the book's ID range (see
+    /// [`CLOB::with_id_range`]) is exhausted, or if its level is at the
+    /// [`CLOB::with_max_level_orders`] cap. Use [`CLOB::try_add_order`]
+    /// where that can happen; the matching engine rejects such orders with
+    /// `RejectReason::OrderIdsExhausted` or `RejectReason::LevelFull` first.
+    ///
     /// # Example
     ///
     /// ```
@@ -204,14 +830,43 @@ impl CLOB {
     ///
     /// assert_eq!(clob.order_count(), 1);
     /// ```
-    pub fn add_order(&mut self, mut order: Order) -> usize {
+    pub fn add_order(&mut self, order: Order) -> usize {
+        self.try_add_order(order).expect("order ID range exhausted or price level full")
+    }
+    
+    /// Add an order to the book unless it does not fit
+    ///
+    /// # Returns
+    ///
+    /// The slab key for the added order, or None (leaving the book
+    /// unchanged) if its level is full or the order has ID 0 and the
+    /// book's ID range is exhausted
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_id_range(1, 2);
+    /// let order = Order::new(0, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+    ///
+    /// assert!(clob.try_add_order(order.clone()).is_some());
+    /// assert!(clob.try_add_order(order).is_none());
+    /// assert_eq!(clob.order_count(), 1);
+    /// ```
+    pub fn try_add_order(&mut self, mut order: Order) -> Option<usize> {
+        if self.level_is_full(order.side(), order.price) {
+            return None;
+        }
+        
         // Auto-assign order ID if not set
         if order.id == 0 {
-            order.id = self.next_order_id;
-            self.next_order_id += 1;
+            order.id = self.order_ids.allocate()?;
         }
         
         let order_id = order.id;
+        let user_id = order.user_id;
         let price = order.price;
         let side = order.side();
         
@@ -219,8 +874,9 @@ impl CLOB {
         let node = OrderNode::new(order);
         let key = self.orders.insert(node);
         
-        // Index the order for O(1) cancel
+        // Index the order for O(1) cancel and per-user queries
         self.order_index.insert(order_id, key);
+        self.user_index.entry(user_id).or_default().insert(key);
         
         // Add to the appropriate price level
         match side {
@@ -228,19 +884,29 @@ impl CLOB {
                 let level = self.bids
                     .entry(Reverse(price))
                     .or_insert_with(|| PriceLevel::new(price));
-                level.push_back(key, &mut self.orders);
+                Self::enqueue(level, self.level_ordering, key, &mut self.orders);
                 self.bid_count += 1;
             }
             Side::Sell => {
                 let level = self.asks
                     .entry(price)
                     .or_insert_with(|| PriceLevel::new(price));
-                level.push_back(key, &mut self.orders);
+                Self::enqueue(level, self.level_ordering, key, &mut self.orders);
                 self.ask_count += 1;
             }
         }
         
-        key
+        self.seq += 1;
+        Some(key)
+    }
+    
+    /// Link a new node into a level according to the queue discipline
+    #[inline]
+    fn enqueue(level: &mut PriceLevel, ordering: LevelOrdering, key: usize, orders: &mut Slab<OrderNode>) {
+        match ordering {
+            LevelOrdering::Fifo => level.push_back(key, orders),
+            LevelOrdering::Lifo => level.push_front(key, orders),
+        }
     }
     
     /// Remove an order by slab key
@@ -253,9 +919,20 @@ impl CLOB {
     ///
     /// The removed order, or None if not found
     pub fn remove_order(&mut self, key: usize) -> Option<Order> {
-        // Get order info before removal
-        let node = self.orders.get(key)?;
+        let order = self.detach_order(key)?;
+        self.seq += 1;
+        Some(order)
+    }
+    
+    /// Remove an order by slab key without advancing the sequence number
+    ///
+    /// For removals that are part of a change already sequenced, such as a
+    /// fill that empties the order.
+    pub(crate) fn detach_order(&mut self, key: usize) -> Option<Order> {
+        // Get order info before removal
+        let node = self.orders.get(key)?;
         let order_id = node.order_id();
+        let user_id = node.order.user_id;
         let price = node.price();
         let side = node.order.side();
         
@@ -264,10 +941,10 @@ impl CLOB {
             Side::Buy => {
                 if let Some(level) = self.bids.get_mut(&Reverse(price)) {
                     level.remove(key, &mut self.orders);
-                    self.bid_count -= 1;
+                    decrement_count(&mut self.bid_count, 1, "bid");
                     
-                    // Remove empty price levels
-                    if level.is_empty() {
+                    // Remove empty price levels (unless retained)
+                    if level.is_empty() && !self.retain_empty_levels {
                         self.bids.remove(&Reverse(price));
                     }
                 }
@@ -275,18 +952,19 @@ impl CLOB {
             Side::Sell => {
                 if let Some(level) = self.asks.get_mut(&price) {
                     level.remove(key, &mut self.orders);
-                    self.ask_count -= 1;
+                    decrement_count(&mut self.ask_count, 1, "ask");
                     
-                    // Remove empty price levels
-                    if level.is_empty() {
+                    // Remove empty price levels (unless retained)
+                    if level.is_empty() && !self.retain_empty_levels {
                         self.asks.remove(&price);
                     }
                 }
             }
         }
         
-        // Remove from index
+        // Remove from indexes
         self.order_index.remove(&order_id);
+        self.unindex_user(user_id, key);
         
         // Remove from slab and return the order
         Some(self.orders.remove(key).order)
@@ -321,6 +999,312 @@ impl CLOB {
         self.remove_order(key)
     }
     
+    /// Cancel an order, distinguishing "already filled" from "not found"
+    ///
+    /// "Already filled" is only reported for orders filled within the
+    /// window set by [`CLOB::with_fill_tracking`]; with tracking off,
+    /// filled orders report [`CancelOutcome::NotFound`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::{CancelOutcome, CLOB};
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100).with_fill_tracking(1_000);
+    /// let mut engine = MatchingEngine::new();
+    ///
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    /// engine.match_order(&mut clob, Order::new(2, 200, Side::Buy, 5_000_000_000_000, 100_000_000, 0), 0);
+    ///
+    /// assert_eq!(clob.cancel_order_detailed(1), CancelOutcome::AlreadyFilled);
+    /// assert_eq!(clob.cancel_order_detailed(99), CancelOutcome::NotFound);
+    /// ```
+    pub fn cancel_order_detailed(&mut self, order_id: u64) -> CancelOutcome {
+        if let Some(order) = self.cancel_order(order_id) {
+            return CancelOutcome::Canceled(order);
+        }
+        if self.recent_fill_set.contains(&order_id) {
+            return CancelOutcome::AlreadyFilled;
+        }
+        CancelOutcome::NotFound
+    }
+    
+    /// Cancel a list of orders by ID, in the given order
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - Order IDs to cancel
+    ///
+    /// # Returns
+    ///
+    /// One `(order_id, cancelled)` pair per input ID, in input order. An ID
+    /// that is not resting (including a repeat of one already cancelled by
+    /// this call) yields `false` and does not affect the others.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Sell, 200_000_000, 100_000_000, 0));
+    ///
+    /// assert_eq!(clob.cancel_orders(&[1, 9, 2]), vec![(1, true), (9, false), (2, true)]);
+    /// assert!(clob.is_empty());
+    /// ```
+    pub fn cancel_orders(&mut self, ids: &[u64]) -> Vec<(u64, bool)> {
+        ids.iter()
+            .map(|&id| (id, self.cancel_order(id).is_some()))
+            .collect()
+    }
+    
+    /// Remove every resting order the predicate rejects
+    ///
+    /// Keeps the orders for which `keep` returns `true` and cancels the
+    /// rest, keeping counts, levels and indexes consistent. Expiry sweeps,
+    /// per-user cancels and side cancels are all special cases.
+    ///
+    /// # Returns
+    ///
+    /// The removed orders, bids then asks, each side in matching priority
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 10));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 20));
+    ///
+    /// // Expire everything placed before t=15
+    /// let expired = clob.retain_orders(|order| order.timestamp >= 15);
+    /// assert_eq!(expired.len(), 1);
+    /// assert_eq!(expired[0].id, 1);
+    /// ```
+    pub fn retain_orders(&mut self, keep: impl Fn(&Order) -> bool) -> Vec<Order> {
+        // Collect first: the levels and lists are rewritten by each removal
+        let mut doomed = Vec::new();
+        for side in [Side::Buy, Side::Sell] {
+            for level in self.levels_by_priority(side) {
+                let mut current = level.head;
+                while let Some(key) = current {
+                    let node = &self.orders[key];
+                    if !keep(&node.order) {
+                        doomed.push(key);
+                    }
+                    current = node.next;
+                }
+            }
+        }
+        
+        doomed.into_iter().filter_map(|key| self.remove_order(key)).collect()
+    }
+    
+    /// Reduce the remaining quantity of a resting order
+    ///
+    /// Time priority is preserved because the order only gets smaller.
+    /// If the reduction reaches (or exceeds) the remaining quantity, the
+    /// order is cancelled outright.
+    ///
+    /// # Arguments
+    ///
+    /// * `order_id` - The unique order identifier
+    /// * `reduce_by` - Quantity to remove (fixed-point)
+    ///
+    /// # Returns
+    ///
+    /// The new remaining quantity (0 if the order was cancelled),
+    /// or None if the order is not resting on the book
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// assert_eq!(clob.reduce_order(1, 40_000_000), Some(60_000_000));
+    /// assert_eq!(clob.reduce_order(1, 100_000_000), Some(0));
+    /// assert!(!clob.contains_order(1));
+    /// ```
+    pub fn reduce_order(&mut self, order_id: u64, reduce_by: u64) -> Option<u64> {
+        let key = *self.order_index.get(&order_id)?;
+        let node = self.orders.get(key)?;
+        
+        // Reducing to zero is a cancel
+        if reduce_by >= node.remaining() {
+            self.remove_order(key);
+            return Some(0);
+        }
+        
+        let price = node.price();
+        let side = node.order.side();
+        
+        let level = match side {
+            Side::Buy => self.bids.get_mut(&Reverse(price)),
+            Side::Sell => self.asks.get_mut(&price),
+        };
+        if let Some(level) = level {
+            level.reduce_quantity(reduce_by);
+        }
+        
+        self.seq += 1;
+        let node = self.orders.get_mut(key)?;
+        node.order.remaining -= reduce_by;
+        Some(node.order.remaining)
+    }
+    
+    /// Change a resting order's price and open quantity
+    ///
+    /// Time priority follows the usual exchange rules:
+    ///
+    /// - Identical price and quantity is a no-op; the order is not touched
+    ///   and keeps its exact queue position
+    /// - Same price, smaller quantity is a [`CLOB::reduce_order`] and keeps
+    ///   its position
+    /// - Any price change or quantity increase re-queues the order at the
+    ///   tail of its (new) level
+    /// - A quantity of zero cancels the order
+    ///
+    /// The book does not match: amending to a price that crosses the
+    /// opposite side leaves the book crossed, so route such amends through
+    /// `MatchingEngine::amend`, which cancels and resubmits the order.
+    ///
+    /// # Arguments
+    ///
+    /// * `order_id` - The unique order identifier
+    /// * `new_price` - New limit price (fixed-point)
+    /// * `new_quantity` - New open quantity (fixed-point)
+    ///
+    /// # Returns
+    ///
+    /// The order's slab key after the amend, or None if it is not resting,
+    /// the amend cancelled it, or the amend is refused and the order left
+    /// as it was: the new price level is full, or the filled size plus
+    /// `new_quantity` would overflow
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// // No-op: still first in line
+    /// clob.amend_order(1, 100_000_000, 100_000_000);
+    /// assert_eq!(clob.queue_position(1), Some(0));
+    ///
+    /// // Size up: back of the queue
+    /// clob.amend_order(1, 100_000_000, 200_000_000);
+    /// assert_eq!(clob.queue_position(1), Some(1));
+    /// ```
+    pub fn amend_order(&mut self, order_id: u64, new_price: u64, new_quantity: u64) -> Option<usize> {
+        let key = *self.order_index.get(&order_id)?;
+        let order = &self.orders.get(key)?.order;
+        let (price, remaining) = (order.price, order.remaining);
+        
+        if new_quantity == 0 {
+            self.remove_order(key);
+            return None;
+        }
+        if new_price == price && new_quantity == remaining {
+            return Some(key);
+        }
+        if new_price == price && new_quantity < remaining {
+            self.reduce_order(order_id, remaining - new_quantity);
+            return Some(key);
+        }
+        
+        // Loses priority: re-enqueue at the tail with the filled size kept
+        if new_price != price && self.level_is_full(order.side(), new_price) {
+            return None;
+        }
+        let quantity = (order.quantity - remaining).checked_add(new_quantity)?;
+        let mut order = self.remove_order(key)?;
+        order.quantity = quantity;
+        order.remaining = new_quantity;
+        order.price = new_price;
+        Some(self.add_order(order))
+    }
+    
+    /// Cancel every order at a single price level
+    ///
+    /// The whole level is detached from the BTreeMap in one operation and its
+    /// linked list is walked once, which is cheaper than cancelling each
+    /// order ID individually.
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - Side of the book the level belongs to
+    /// * `price` - Price of the level (fixed-point)
+    ///
+    /// # Returns
+    ///
+    /// The cancelled orders in time priority (head to tail).
+    /// Empty if no level exists at that price.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 101, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// let cancelled = clob.cancel_price_level(Side::Sell, 5_000_000_000_000);
+    /// assert_eq!(cancelled.len(), 2);
+    /// assert!(clob.best_ask().is_none());
+    /// ```
+    pub fn cancel_price_level(&mut self, side: Side, price: u64) -> Vec<Order> {
+        // Detach the whole level up front
+        let level = match side {
+            Side::Buy => self.bids.remove(&Reverse(price)),
+            Side::Sell => self.asks.remove(&price),
+        };
+        
+        let level = match level {
+            Some(l) => l,
+            None => return Vec::new(),
+        };
+        
+        let mut cancelled = Vec::with_capacity(level.order_count);
+        
+        // Walk the queue from head to tail, freeing each node
+        let mut current = level.head;
+        while let Some(key) = current {
+            if !self.orders.contains(key) {
+                break;
+            }
+            let node = self.orders.remove(key);
+            current = node.next;
+            
+            self.order_index.remove(&node.order.id);
+            self.unindex_user(node.order.user_id, key);
+            cancelled.push(node.order);
+        }
+        
+        match side {
+            Side::Buy => decrement_count(&mut self.bid_count, cancelled.len(), "bid"),
+            Side::Sell => decrement_count(&mut self.ask_count, cancelled.len(), "ask"),
+        }
+        self.seq += cancelled.len() as u64;
+        
+        cancelled
+    }
+    
     /// Get a reference to an order by slab key
     #[inline]
     pub fn get_order(&self, key: usize) -> Option<&Order> {
@@ -345,60 +1329,464 @@ impl CLOB {
         self.order_index.contains_key(&order_id)
     }
     
+    /// Get the number of orders ahead of an order at its price level
+    ///
+    /// Walks `prev` pointers back to the level head, so the cost is
+    /// proportional to the order's position in the queue.
+    ///
+    /// # Returns
+    ///
+    /// `Some(0)` for the order at the head of its level (next to fill),
+    /// or `None` if the order is not resting on the book
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// assert_eq!(clob.queue_position(1), Some(0));
+    /// assert_eq!(clob.queue_position(2), Some(1));
+    /// ```
+    pub fn queue_position(&self, order_id: u64) -> Option<usize> {
+        let key = self.get_key(order_id)?;
+        let mut current = self.orders.get(key)?.prev;
+        let mut ahead = 0;
+        
+        while let Some(prev_key) = current {
+            ahead += 1;
+            current = self.orders.get(prev_key).and_then(|node| node.prev);
+        }
+        
+        Some(ahead)
+    }
+    
+    /// Get the queue and market state around a resting order
+    ///
+    /// Collects the order's queue position, the quantity resting ahead of
+    /// it at its level, its price distance from the touch on its own side
+    /// and the current BBO. Like [`CLOB::queue_position`], the cost is
+    /// proportional to the order's position in the queue.
+    ///
+    /// # Returns
+    ///
+    /// The context, or None if the order is not resting on the book
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 300_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 99_000_000, 200_000_000, 0));
+    /// clob.add_order(Order::new(3, 100, Side::Buy, 99_000_000, 100_000_000, 0));
+    ///
+    /// let context = clob.order_context(3).unwrap();
+    /// assert_eq!(context.queue_position, 1);
+    /// assert_eq!(context.quantity_ahead, 200_000_000);
+    /// assert_eq!(context.ticks_from_touch(500_000), Some(2));
+    /// ```
+    pub fn order_context(&self, order_id: u64) -> Option<OrderContext> {
+        let node = self.orders.get(self.get_key(order_id)?)?;
+        let mut current = node.prev;
+        let mut queue_position = 0;
+        let mut quantity_ahead: u64 = 0;
+        
+        while let Some(prev_key) = current {
+            let prev = self.orders.get(prev_key)?;
+            queue_position += 1;
+            quantity_ahead = quantity_ahead.saturating_add(prev.order.remaining);
+            current = prev.prev;
+        }
+        
+        let bbo = self.top_of_book();
+        let touch = match node.order.side() {
+            Side::Buy => bbo.bid_price,
+            Side::Sell => bbo.ask_price,
+        };
+        
+        Some(OrderContext {
+            queue_position,
+            quantity_ahead,
+            touch_distance: touch.map_or(0, |touch| touch.abs_diff(node.order.price)),
+            bbo,
+        })
+    }
+    
     // ========================================================================
     // Best Bid/Ask
     // ========================================================================
     
-    /// Get the best bid price (highest buy price)
+    /// Get the best bid price
+    ///
+    /// Highest buy price under [`PriceOrder::Standard`], lowest under
+    /// [`PriceOrder::Inverse`].
     ///
     /// # Returns
     ///
     /// The best bid price, or None if no bids exist
     #[inline]
     pub fn best_bid(&self) -> Option<u64> {
-        self.bids.keys().next().map(|r| r.0)
+        self.best_bid_level().map(|level| level.price)
     }
     
-    /// Get the best ask price (lowest sell price)
+    /// Get the best ask price
+    ///
+    /// Lowest sell price under [`PriceOrder::Standard`], highest under
+    /// [`PriceOrder::Inverse`].
     ///
     /// # Returns
     ///
     /// The best ask price, or None if no asks exist
     #[inline]
     pub fn best_ask(&self) -> Option<u64> {
-        self.asks.keys().next().copied()
+        self.best_ask_level().map(|level| level.price)
     }
     
-    /// Get the spread (best_ask - best_bid)
+    /// Get the spread (distance between best ask and best bid)
     ///
     /// # Returns
     ///
-    /// The spread, or None if either side is empty
+    /// The spread, or None if either side is empty or the book is crossed
     pub fn spread(&self) -> Option<u64> {
         match (self.best_bid(), self.best_ask()) {
-            (Some(bid), Some(ask)) if ask >= bid => Some(ask - bid),
+            (Some(bid), Some(ask)) => match self.price_order {
+                PriceOrder::Standard if ask >= bid => Some(ask - bid),
+                PriceOrder::Inverse if bid >= ask => Some(bid - ask),
+                _ => None,
+            },
             _ => None,
         }
     }
     
+    /// Get the best bid and ask with their aggregate sizes
+    ///
+    /// This is the canonical BBO quote: all four values come from one
+    /// borrow of the book, so they can never mix states.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::{CLOB, TopOfBook};
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 300_000_000, 0));
+    ///
+    /// assert_eq!(clob.top_of_book(), TopOfBook {
+    ///     bid_price: Some(100_000_000),
+    ///     bid_size: Some(300_000_000),
+    ///     ask_price: None,
+    ///     ask_size: None,
+    /// });
+    /// ```
+    pub fn top_of_book(&self) -> TopOfBook {
+        let bid = self.best_bid_level();
+        let ask = self.best_ask_level();
+        
+        TopOfBook {
+            bid_price: bid.map(|level| level.price),
+            bid_size: bid.map(|level| level.total_quantity),
+            ask_price: ask.map(|level| level.price),
+            ask_size: ask.map(|level| level.total_quantity),
+        }
+    }
+    
     /// Get the best bid price level
     pub fn best_bid_level(&self) -> Option<&PriceLevel> {
-        self.bids.values().next()
+        // Skips retained empty levels; without retention the first level is never empty
+        match self.price_order {
+            PriceOrder::Standard => self.bids.values().find(|l| !l.is_empty()),
+            PriceOrder::Inverse => self.bids.values().rev().find(|l| !l.is_empty()),
+        }
     }
     
     /// Get the best ask price level
     pub fn best_ask_level(&self) -> Option<&PriceLevel> {
-        self.asks.values().next()
+        // Skips retained empty levels; without retention the first level is never empty
+        match self.price_order {
+            PriceOrder::Standard => self.asks.values().find(|l| !l.is_empty()),
+            PriceOrder::Inverse => self.asks.values().rev().find(|l| !l.is_empty()),
+        }
     }
     
     /// Get the best bid price level (mutable)
     pub fn best_bid_level_mut(&mut self) -> Option<&mut PriceLevel> {
-        self.bids.values_mut().next()
+        match self.price_order {
+            PriceOrder::Standard => self.bids.values_mut().find(|l| !l.is_empty()),
+            PriceOrder::Inverse => self.bids.values_mut().rev().find(|l| !l.is_empty()),
+        }
     }
     
     /// Get the best ask price level (mutable)
     pub fn best_ask_level_mut(&mut self) -> Option<&mut PriceLevel> {
-        self.asks.values_mut().next()
+        match self.price_order {
+            PriceOrder::Standard => self.asks.values_mut().find(|l| !l.is_empty()),
+            PriceOrder::Inverse => self.asks.values_mut().rev().find(|l| !l.is_empty()),
+        }
+    }
+    
+    // ========================================================================
+    // Level Summaries (market data)
+    // ========================================================================
+    
+    /// Iterate a side's non-empty price levels in priority order (best first)
+    pub(crate) fn levels_by_priority(&self, side: Side) -> Box<dyn Iterator<Item = &PriceLevel> + '_> {
+        let levels: Box<dyn Iterator<Item = &PriceLevel>> = match (side, self.price_order) {
+            (Side::Buy, PriceOrder::Standard) => Box::new(self.bids.values()),
+            (Side::Buy, PriceOrder::Inverse) => Box::new(self.bids.values().rev()),
+            (Side::Sell, PriceOrder::Standard) => Box::new(self.asks.values()),
+            (Side::Sell, PriceOrder::Inverse) => Box::new(self.asks.values().rev()),
+        };
+        Box::new(levels.filter(|l| !l.is_empty()))
+    }
+    
+    /// Get the summary of the level at a specific price
+    ///
+    /// # Returns
+    ///
+    /// The level summary, or None if no level exists at that price
+    pub fn level_summary(&self, side: Side, price: u64) -> Option<LevelSummary> {
+        let level = match side {
+            Side::Buy => self.bids.get(&Reverse(price)),
+            Side::Sell => self.asks.get(&price),
+        };
+        level.filter(|l| !l.is_empty()).map(PriceLevel::summary)
+    }
+    
+    /// Get summaries of the top `max_levels` levels on a side, best first
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Buy, 4_900_000_000_000, 200_000_000, 0));
+    ///
+    /// let levels = clob.level_summaries(Side::Buy, 10);
+    /// assert_eq!(levels[0].price, 5_000_000_000_000);
+    /// assert_eq!(levels[1].total_quantity, 200_000_000);
+    /// ```
+    pub fn level_summaries(&self, side: Side, max_levels: usize) -> Vec<LevelSummary> {
+        self.levels_by_priority(side)
+            .take(max_levels)
+            .map(PriceLevel::summary)
+            .collect()
+    }
+    
+    /// Get the cumulative depth curve of the top `levels` levels on a side
+    ///
+    /// Each entry is a level's price and the total quantity from the touch
+    /// up to and including that level, best first: the series for a depth
+    /// chart, and how much can trade within a given distance of the touch.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Sell, 101_000_000, 200_000_000, 0));
+    ///
+    /// assert_eq!(clob.cumulative_depth(Side::Sell, 10), vec![
+    ///     (100_000_000, 100_000_000),
+    ///     (101_000_000, 300_000_000),
+    /// ]);
+    /// ```
+    pub fn cumulative_depth(&self, side: Side, levels: usize) -> Vec<(u64, u64)> {
+        let mut cumulative = 0u64;
+        self.levels_by_priority(side)
+            .take(levels)
+            .map(|level| {
+                cumulative = cumulative.saturating_add(level.total_quantity);
+                (level.price, cumulative)
+            })
+            .collect()
+    }
+    
+    /// Get the number of distinct users with resting orders
+    #[inline]
+    pub fn resting_user_count(&self) -> usize {
+        self.user_index.len()
+    }
+    
+    /// Check if a user has any resting orders
+    #[inline]
+    pub fn has_resting_orders(&self, user_id: u64) -> bool {
+        self.user_index.contains_key(&user_id)
+    }
+    
+    /// Get a user's resting size and notional on each side
+    ///
+    /// Reads only that user's orders through the user index. Notionals are
+    /// `price * remaining` summed at full precision and scaled back by 10^8,
+    /// saturating at `u64::MAX`.
+    ///
+    /// # Returns
+    ///
+    /// `(bid_quantity, ask_quantity, bid_notional, ask_notional)`, all zero
+    /// for a user with no resting orders
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 7, Side::Buy, 200_000_000, 300_000_000, 0));
+    ///
+    /// // 3.0 bid at 2.0 is 6.0 of notional
+    /// assert_eq!(clob.user_resting_exposure(7), (300_000_000, 0, 600_000_000, 0));
+    /// ```
+    pub fn user_resting_exposure(&self, user_id: u64) -> (u64, u64, u64, u64) {
+        let keys = match self.user_index.get(&user_id) {
+            Some(keys) => keys,
+            None => return (0, 0, 0, 0),
+        };
+        
+        let (mut bid_qty, mut ask_qty) = (0u64, 0u64);
+        let (mut bid_notional, mut ask_notional) = (0u128, 0u128);
+        for node in keys.iter().filter_map(|&key| self.orders.get(key)) {
+            let order = &node.order;
+            let notional = order.price as u128 * order.remaining as u128;
+            match order.side() {
+                Side::Buy => {
+                    bid_qty = bid_qty.saturating_add(order.remaining);
+                    bid_notional = bid_notional.saturating_add(notional);
+                }
+                Side::Sell => {
+                    ask_qty = ask_qty.saturating_add(order.remaining);
+                    ask_notional = ask_notional.saturating_add(notional);
+                }
+            }
+        }
+        
+        let scale = |raw: u128| u64::try_from(raw / SCALE as u128).unwrap_or(u64::MAX);
+        (bid_qty, ask_qty, scale(bid_notional), scale(ask_notional))
+    }
+    
+    /// Get the quantity-weighted average price of everything resting on a side
+    ///
+    /// Sum of `price * quantity` over all levels divided by the total
+    /// quantity, rounded down. Unlike mid or a VWAP over a fill size, this
+    /// covers the whole side. Returns `None` if the side has no quantity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Sell, 200_000_000, 100_000_000, 0));
+    ///
+    /// assert_eq!(clob.average_resting_price(Side::Sell), Some(150_000_000));
+    /// assert_eq!(clob.average_resting_price(Side::Buy), None);
+    /// ```
+    pub fn average_resting_price(&self, side: Side) -> Option<u64> {
+        let quantity: u128 = self.levels_by_priority(side).map(|level| level.total_quantity as u128).sum();
+        if quantity == 0 {
+            return None;
+        }
+        
+        // Two levels near MAX_VALUE overflow a u128 notional, so divide each
+        // term first and carry the remainders: floor(sum(a) / q) is
+        // sum(a / q) + floor(sum(a % q) / q)
+        let (whole, rest) = self.levels_by_priority(side).fold((0u128, 0u128), |(whole, rest), level| {
+            let notional = level.price as u128 * level.total_quantity as u128;
+            (whole + notional / quantity, rest + notional % quantity)
+        });
+        
+        // The average lies between the extreme prices, so it fits in a u64
+        Some((whole + rest / quantity) as u64)
+    }
+    
+    /// Walk levels outward from the touch, alternating sides
+    ///
+    /// Yields `(side, price, total_quantity)` as best bid, best ask, next
+    /// bid, next ask, and so on. Once one side runs out, the remaining
+    /// levels of the other side follow in priority order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Sell, 200_000_000, 300_000_000, 0));
+    ///
+    /// let levels: Vec<_> = clob.levels_from_touch().collect();
+    /// assert_eq!(levels, vec![
+    ///     (Side::Buy, 100_000_000, 100_000_000),
+    ///     (Side::Sell, 200_000_000, 300_000_000),
+    /// ]);
+    /// ```
+    pub fn levels_from_touch(&self) -> impl Iterator<Item = (Side, u64, u64)> + '_ {
+        let mut bids = self.levels_by_priority(Side::Buy);
+        let mut asks = self.levels_by_priority(Side::Sell);
+        let mut turn = Side::Buy;
+        
+        std::iter::from_fn(move || {
+            let side = turn;
+            turn = side.opposite();
+            
+            let (own, other) = match side {
+                Side::Buy => (&mut bids, &mut asks),
+                Side::Sell => (&mut asks, &mut bids),
+            };
+            
+            // Fall back to the other side once this one is exhausted
+            own.next()
+                .map(|level| (side, level))
+                .or_else(|| other.next().map(|level| (side.opposite(), level)))
+                .map(|(side, level)| (side, level.price, level.total_quantity))
+        })
+    }
+    
+    /// Iterate a side's resting orders in matching priority
+    ///
+    /// Best price first and queue order within each price: exactly the
+    /// order a crossing taker consumes them in. Each order carries its
+    /// live `remaining` quantity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Sell, 101_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 100, Side::Sell, 100_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(3, 100, Side::Sell, 100_000_000, 100_000_000, 0));
+    ///
+    /// let ids: Vec<_> = clob.iter_side(Side::Sell).map(|o| o.id).collect();
+    /// assert_eq!(ids, vec![2, 3, 1]);
+    /// ```
+    pub fn iter_side(&self, side: Side) -> impl Iterator<Item = &Order> + '_ {
+        self.levels_by_priority(side).flat_map(move |level| {
+            let mut current = level.head;
+            std::iter::from_fn(move || {
+                let node = self.orders.get(current?)?;
+                current = node.next;
+                Some(&node.order)
+            })
+        })
     }
     
     // ========================================================================
@@ -446,17 +1834,87 @@ impl CLOB {
     // ========================================================================
     
     /// Get the next trade ID and increment the counter
+    ///
+    /// Returns None, leaving the counter unchanged, once every ID below
+    /// `u64::MAX` has been issued: reusing an ID would give two trades the
+    /// same identity.
     #[inline]
-    pub fn next_trade_id(&mut self) -> u64 {
+    pub fn next_trade_id(&mut self) -> Option<u64> {
         let id = self.next_trade_id;
-        self.next_trade_id += 1;
-        id
+        self.next_trade_id = id.checked_add(1)?;
+        Some(id)
+    }
+    
+    /// Get how many trade IDs can still be issued
+    #[inline]
+    pub fn trade_ids_left(&self) -> u64 {
+        u64::MAX - self.next_trade_id
     }
     
     /// Get the current next order ID (without incrementing)
     #[inline]
     pub fn peek_next_order_id(&self) -> u64 {
-        self.next_order_id
+        self.order_ids.peek()
+    }
+    
+    /// Get the allocator behind auto-assigned order IDs
+    #[inline]
+    pub fn order_ids(&self) -> &IdAllocator {
+        &self.order_ids
+    }
+    
+    /// Get the current next trade ID (without incrementing)
+    #[inline]
+    pub fn peek_next_trade_id(&self) -> u64 {
+        self.next_trade_id
+    }
+    
+    /// Get the book's mutation sequence number
+    ///
+    /// Advances by one for every delta a market-data feed would publish:
+    /// an order added, removed (cancel or full fill), or resized (partial
+    /// fill or reduction), plus one for [`CLOB::clear`]. A cancelled level
+    /// counts once per order; a repricing amend is a removal plus an add.
+    /// Read-only queries never advance it, so the same operation stream
+    /// always produces the same sequence and a consumer tagging deltas
+    /// with it can detect gaps or reordering. Starts at 0, is saved in
+    /// snapshots, and is not part of the state root.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    /// clob.reduce_order(1, 50_000_000);
+    /// clob.best_bid();
+    ///
+    /// assert_eq!(clob.current_seq(), 2);
+    /// ```
+    #[inline]
+    pub fn current_seq(&self) -> u64 {
+        self.seq
+    }
+    
+    /// Count one book delta made outside the `CLOB` methods (a maker fill)
+    #[inline]
+    pub(crate) fn advance_seq(&mut self) {
+        self.seq += 1;
+    }
+    
+    /// Set the mutation sequence number (used when loading a snapshot or
+    /// following an event log)
+    #[inline]
+    pub(crate) fn restore_seq(&mut self, seq: u64) {
+        self.seq = seq;
+    }
+    
+    /// Restore both ID counters (used when loading a snapshot)
+    pub(crate) fn restore_id_counters(&mut self, next_order_id: u64, next_trade_id: u64) {
+        self.order_ids.restore(next_order_id);
+        self.next_trade_id = next_trade_id;
     }
     
     // ========================================================================
@@ -470,12 +1928,25 @@ impl CLOB {
     #[inline]
     pub fn remove_from_slab(&mut self, key: usize) -> Option<OrderNode> {
         if self.orders.contains(key) {
-            Some(self.orders.remove(key))
+            let node = self.orders.remove(key);
+            self.unindex_user(node.order.user_id, key);
+            Some(node)
         } else {
             None
         }
     }
     
+    /// Drop a slab key from its user's entry, removing the entry once empty
+    #[inline]
+    fn unindex_user(&mut self, user_id: u64, key: usize) {
+        if let Some(keys) = self.user_index.get_mut(&user_id) {
+            keys.remove(&key);
+            if keys.is_empty() {
+                self.user_index.remove(&user_id);
+            }
+        }
+    }
+    
     /// Remove an empty bid price level
     pub fn remove_bid_level(&mut self, price: u64) {
         self.bids.remove(&Reverse(price));
@@ -491,14 +1962,35 @@ impl CLOB {
         self.order_index.remove(&order_id);
     }
     
+    /// Remember a fully filled order ID (used by matching engine)
+    ///
+    /// A no-op unless [`CLOB::with_fill_tracking`] is enabled.
+    pub fn record_filled(&mut self, order_id: u64) {
+        if self.fill_tracking == 0 || !self.recent_fill_set.insert(order_id) {
+            return;
+        }
+        if self.recent_fills.len() == self.fill_tracking {
+            if let Some(oldest) = self.recent_fills.pop_front() {
+                self.recent_fill_set.remove(&oldest);
+            }
+        }
+        self.recent_fills.push_back(order_id);
+    }
+    
     /// Decrement bid count
+    ///
+    /// Asserts in debug builds if the count is already zero; release
+    /// builds leave it at zero.
     pub fn decrement_bid_count(&mut self) {
-        self.bid_count = self.bid_count.saturating_sub(1);
+        decrement_count(&mut self.bid_count, 1, "bid");
     }
     
     /// Decrement ask count
+    ///
+    /// Asserts in debug builds if the count is already zero; release
+    /// builds leave it at zero.
     pub fn decrement_ask_count(&mut self) {
-        self.ask_count = self.ask_count.saturating_sub(1);
+        decrement_count(&mut self.ask_count, 1, "ask");
     }
     
     // ========================================================================
@@ -517,6 +2009,11 @@ impl CLOB {
     /// 2. All ask orders (sorted by price ascending, then time)
     /// 3. Metadata (order count, next IDs)
     ///
+    /// Each order contributes every persisted field: ID, user, raw side
+    /// and type bytes, price, quantity, remaining, timestamp, fill totals
+    /// and client tag. Book settings, the mutation sequence and fill
+    /// tracking are not covered.
+    ///
     /// # Returns
     ///
     /// A 32-byte SHA-256 hash of the order book state.
@@ -538,24 +2035,23 @@ impl CLOB {
         
         let mut hasher = Sha256::new();
         
-        // Hash all bid orders (sorted high to low price, then FIFO within level)
+        // Hash all bid orders (sorted high to low price, then queue order within level)
         for (Reverse(price), level) in self.bids.iter() {
+            // Retained empty levels hold no state
+            if level.is_empty() {
+                continue;
+            }
+            
             // Hash price level metadata
             hasher.update(price.to_le_bytes());
             hasher.update(level.total_quantity.to_le_bytes());
             hasher.update(level.order_count.to_le_bytes());
             
-            // Hash each order in the level (FIFO order)
+            // Hash each order in the level (queue order)
             let mut current = level.head;
             while let Some(key) = current {
                 if let Some(node) = self.orders.get(key) {
-                    let order = &node.order;
-                    hasher.update(order.id.to_le_bytes());
-                    hasher.update(order.user_id.to_le_bytes());
-                    hasher.update(order.price.to_le_bytes());
-                    hasher.update(order.quantity.to_le_bytes());
-                    hasher.update(order.remaining.to_le_bytes());
-                    hasher.update(order.timestamp.to_le_bytes());
+                    hasher.update(order_state_bytes(&node.order));
                     current = node.next;
                 } else {
                     break;
@@ -566,24 +2062,23 @@ impl CLOB {
         // Separator between bids and asks
         hasher.update([0xFFu8; 8]);
         
-        // Hash all ask orders (sorted low to high price, then FIFO within level)
+        // Hash all ask orders (sorted low to high price, then queue order within level)
         for (price, level) in self.asks.iter() {
+            // Retained empty levels hold no state
+            if level.is_empty() {
+                continue;
+            }
+            
             // Hash price level metadata
             hasher.update(price.to_le_bytes());
             hasher.update(level.total_quantity.to_le_bytes());
             hasher.update(level.order_count.to_le_bytes());
             
-            // Hash each order in the level (FIFO order)
+            // Hash each order in the level (queue order)
             let mut current = level.head;
             while let Some(key) = current {
                 if let Some(node) = self.orders.get(key) {
-                    let order = &node.order;
-                    hasher.update(order.id.to_le_bytes());
-                    hasher.update(order.user_id.to_le_bytes());
-                    hasher.update(order.price.to_le_bytes());
-                    hasher.update(order.quantity.to_le_bytes());
-                    hasher.update(order.remaining.to_le_bytes());
-                    hasher.update(order.timestamp.to_le_bytes());
+                    hasher.update(order_state_bytes(&node.order));
                     current = node.next;
                 } else {
                     break;
@@ -595,7 +2090,7 @@ impl CLOB {
         hasher.update(self.order_count().to_le_bytes());
         hasher.update(self.bid_count.to_le_bytes());
         hasher.update(self.ask_count.to_le_bytes());
-        hasher.update(self.next_order_id.to_le_bytes());
+        hasher.update(self.order_ids.peek().to_le_bytes());
         hasher.update(self.next_trade_id.to_le_bytes());
         
         // Finalize and return the hash
@@ -605,15 +2100,485 @@ impl CLOB {
         root
     }
     
+    /// Encode every price level deterministically, as a preimage for an
+    /// external consensus commitment
+    ///
+    /// Unlike `compute_state_root`, this covers only the resting liquidity,
+    /// not counters such as the next order ID, so two books holding the
+    /// same orders in the same queues encode identically however they got
+    /// there (slab layout, cancelled orders, retained empty levels).
+    ///
+    /// # Layout
+    ///
+    /// Bids then asks, each side best price first. All integers are
+    /// little-endian.
+    ///
+    /// - Per side: level count (u64), then each level
+    /// - Per level: price, total quantity, order count (u64 each), then
+    ///   each order in queue order
+    /// - Per order: its 32-byte [`Order::fingerprint`], then its
+    ///   remaining quantity (u64), which the fingerprint leaves out
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// // Two level counts, one level header and one order
+    /// assert_eq!(clob.canonical_level_bytes().len(), 8 + 8 + 24 + 40);
+    /// ```
+    pub fn canonical_level_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        for side in [Side::Buy, Side::Sell] {
+            out.extend_from_slice(&(self.levels_by_priority(side).count() as u64).to_le_bytes());
+            for level in self.levels_by_priority(side) {
+                out.extend_from_slice(&level.price.to_le_bytes());
+                out.extend_from_slice(&level.total_quantity.to_le_bytes());
+                out.extend_from_slice(&(level.order_count as u64).to_le_bytes());
+                
+                let mut current = level.head;
+                while let Some(node) = current.and_then(|key| self.orders.get(key)) {
+                    out.extend_from_slice(&node.order.fingerprint());
+                    out.extend_from_slice(&node.order.remaining.to_le_bytes());
+                    current = node.next;
+                }
+            }
+        }
+        out
+    }
+    
+    /// Verify every structural invariant of the book.
+    ///
+    /// This walks every order, so it is O(n) and intended for tests,
+    /// fuzzing, and debugging rather than the hot path.
+    ///
+    /// # Checks
+    ///
+    /// - Each level's list is acyclic, doubly linked, and ends at `tail`
+    /// - Each node's price and side match its level, with `remaining > 0`
+    /// - Each level's `order_count` and `total_quantity` match its nodes
+    /// - No empty level remains in the maps (unless retention is enabled)
+    /// - `bid_count`/`ask_count` match the levels and the slab
+    /// - The order index maps exactly the resting orders to their keys
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// assert!(clob.verify_invariants().is_ok());
+    /// ```
+    pub fn verify_invariants(&self) -> Result<(), InvariantViolation> {
+        let mut bid_orders = 0usize;
+        for level in self.bids.values() {
+            bid_orders += self.verify_level(Side::Buy, level)?;
+        }
+        
+        let mut ask_orders = 0usize;
+        for level in self.asks.values() {
+            ask_orders += self.verify_level(Side::Sell, level)?;
+        }
+        
+        if bid_orders != self.bid_count {
+            return Err(InvariantViolation::CountMismatch(format!(
+                "bid_count {} but levels hold {}", self.bid_count, bid_orders
+            )));
+        }
+        if ask_orders != self.ask_count {
+            return Err(InvariantViolation::CountMismatch(format!(
+                "ask_count {} but levels hold {}", self.ask_count, ask_orders
+            )));
+        }
+        if bid_orders + ask_orders != self.orders.len() {
+            return Err(InvariantViolation::CountMismatch(format!(
+                "slab holds {} orders but levels hold {}", self.orders.len(), bid_orders + ask_orders
+            )));
+        }
+        
+        if self.order_index.len() != self.orders.len() {
+            return Err(InvariantViolation::IndexMismatch(format!(
+                "index has {} entries for {} orders", self.order_index.len(), self.orders.len()
+            )));
+        }
+        for (&order_id, &key) in &self.order_index {
+            match self.orders.get(key) {
+                Some(node) if node.order.id == order_id => {}
+                Some(node) => {
+                    return Err(InvariantViolation::IndexMismatch(format!(
+                        "order {} maps to key {} holding order {}", order_id, key, node.order.id
+                    )));
+                }
+                None => {
+                    return Err(InvariantViolation::IndexMismatch(format!(
+                        "order {} maps to vacant key {}", order_id, key
+                    )));
+                }
+            }
+        }
+        
+        let user_keys: usize = self.user_index.values().map(HashSet::len).sum();
+        if user_keys != self.orders.len() {
+            return Err(InvariantViolation::IndexMismatch(format!(
+                "user index has {} entries for {} orders", user_keys, self.orders.len()
+            )));
+        }
+        for (&user_id, keys) in &self.user_index {
+            for &key in keys {
+                match self.orders.get(key) {
+                    Some(node) if node.order.user_id == user_id => {}
+                    _ => {
+                        return Err(InvariantViolation::IndexMismatch(format!(
+                            "user {} maps to key {} not holding one of its orders", user_id, key
+                        )));
+                    }
+                }
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Cheap consistency check for production use
+    ///
+    /// A subset of [`CLOB::verify_invariants`] that only reads level
+    /// aggregates, so it is O(levels) rather than O(orders) and can run at
+    /// every block boundary.
+    ///
+    /// # Checks
+    ///
+    /// - Each level's price matches its map key, so the BBO read from the
+    ///   maps is the price the level's orders rest at
+    /// - Each level's `head`/`tail` agree with its `order_count`, and no
+    ///   empty level remains (unless retention is enabled)
+    /// - `bid_count`/`ask_count` match the level counts, and together match
+    ///   the slab and the order index
+    /// - The book is not crossed: the best bid does not match the best ask
+    ///
+    /// Books loaded with [`CLOB::add_order`] bypass matching and may be
+    /// crossed on purpose; [`MatchingEngine::uncross`](crate::engine::MatchingEngine::uncross)
+    /// them before relying on this check.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 4_900_000_000_000, 100_000_000, 0));
+    /// clob.add_order(Order::new(2, 101, Side::Sell, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// assert!(clob.assert_consistent().is_ok());
+    /// ```
+    pub fn assert_consistent(&self) -> Result<(), InvariantViolation> {
+        let mut bid_orders = 0usize;
+        for (&Reverse(price), level) in &self.bids {
+            bid_orders += self.check_level_summary(Side::Buy, price, level)?;
+        }
+        
+        let mut ask_orders = 0usize;
+        for (&price, level) in &self.asks {
+            ask_orders += self.check_level_summary(Side::Sell, price, level)?;
+        }
+        
+        if bid_orders != self.bid_count {
+            return Err(InvariantViolation::CountMismatch(format!(
+                "bid_count {} but levels count {}", self.bid_count, bid_orders
+            )));
+        }
+        if ask_orders != self.ask_count {
+            return Err(InvariantViolation::CountMismatch(format!(
+                "ask_count {} but levels count {}", self.ask_count, ask_orders
+            )));
+        }
+        if bid_orders + ask_orders != self.orders.len() {
+            return Err(InvariantViolation::CountMismatch(format!(
+                "slab holds {} orders but levels count {}", self.orders.len(), bid_orders + ask_orders
+            )));
+        }
+        if self.order_index.len() != self.orders.len() {
+            return Err(InvariantViolation::IndexMismatch(format!(
+                "index has {} entries for {} orders", self.order_index.len(), self.orders.len()
+            )));
+        }
+        
+        if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) {
+            if self.price_order.crosses(Side::Buy, bid, ask) {
+                return Err(InvariantViolation::Crossed { bid, ask });
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Check one level's aggregates against its key, returning its order count
+    fn check_level_summary(&self, side: Side, key: u64, level: &PriceLevel) -> Result<usize, InvariantViolation> {
+        let corrupt = |detail: String| InvariantViolation::CorruptLevel {
+            side,
+            price: key,
+            detail,
+        };
+        
+        if level.price != key {
+            return Err(corrupt(format!("level holds price {}", level.price)));
+        }
+        if level.is_empty() {
+            if self.retain_empty_levels && level.head.is_none() && level.tail.is_none() && level.total_quantity == 0 {
+                return Ok(0);
+            }
+            return Err(corrupt("empty level left in the book".to_string()));
+        }
+        if level.head.is_none() || level.tail.is_none() || level.total_quantity == 0 {
+            return Err(corrupt(format!("{} orders but no queue or quantity", level.order_count)));
+        }
+        
+        Ok(level.order_count)
+    }
+    
+    /// Verify one price level, returning the number of orders walked
+    fn verify_level(&self, side: Side, level: &PriceLevel) -> Result<usize, InvariantViolation> {
+        let corrupt = |detail: String| InvariantViolation::CorruptLevel {
+            side,
+            price: level.price,
+            detail,
+        };
+        
+        if level.is_empty() {
+            if self.retain_empty_levels && level.head.is_none() && level.tail.is_none() && level.total_quantity == 0 {
+                return Ok(0);
+            }
+            return Err(corrupt("empty level left in the book".to_string()));
+        }
+        
+        let mut walked = 0usize;
+        let mut quantity = 0u64;
+        let mut prev: Option<usize> = None;
+        let mut current = level.head;
+        
+        while let Some(key) = current {
+            // More steps than orders in the slab means a cycle
+            if walked >= self.orders.len() {
+                return Err(corrupt("cycle in order list".to_string()));
+            }
+            
+            let node = self.orders.get(key)
+                .ok_or_else(|| corrupt(format!("link to vacant key {}", key)))?;
+            
+            if node.prev != prev {
+                return Err(corrupt(format!(
+                    "key {} has prev {:?}, expected {:?}", key, node.prev, prev
+                )));
+            }
+            if node.price() != level.price || node.order.side() != side {
+                return Err(corrupt(format!("order {} does not belong to this level", node.order_id())));
+            }
+            if node.remaining() == 0 {
+                return Err(corrupt(format!("order {} rests with zero remaining", node.order_id())));
+            }
+            // Fills (and reductions) only ever take quantity away
+            let order = &node.order;
+            if order.remaining > order.quantity || order.cumulative_filled > order.quantity - order.remaining {
+                return Err(corrupt(format!(
+                    "order {} rests with remaining {} after filling {} of {}",
+                    order.id, order.remaining, order.cumulative_filled, order.quantity
+                )));
+            }
+            
+            walked += 1;
+            quantity = quantity.saturating_add(node.remaining());
+            prev = Some(key);
+            current = node.next;
+        }
+        
+        if level.tail != prev {
+            return Err(corrupt(format!("tail {:?} but list ends at {:?}", level.tail, prev)));
+        }
+        if walked != level.order_count {
+            return Err(corrupt(format!("order_count {} but list holds {}", level.order_count, walked)));
+        }
+        if quantity != level.total_quantity {
+            return Err(corrupt(format!(
+                "total_quantity {} but orders sum to {}", level.total_quantity, quantity
+            )));
+        }
+        
+        Ok(walked)
+    }
+    
+    /// Compute a fast, non-cryptographic checksum of the book contents.
+    ///
+    /// Intended for cheap "did anything change" checks and test assertions
+    /// on hot paths. It is NOT a substitute for [`CLOB::compute_state_root`]
+    /// in consensus.
+    ///
+    /// # Algorithm
+    ///
+    /// 64-bit FNV-1a over the same canonical iteration and order fields as
+    /// the state root (bids high to low, asks low to high, queue order
+    /// within a level). Only the resting orders are covered; the ID
+    /// counters are not, so adding and then cancelling the same order
+    /// restores the original checksum.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let empty = clob.checksum();
+    ///
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    /// assert_ne!(clob.checksum(), empty);
+    ///
+    /// clob.cancel_order(1);
+    /// assert_eq!(clob.checksum(), empty);
+    /// ```
+    pub fn checksum(&self) -> u64 {
+        let mut hash = FNV_OFFSET_BASIS;
+        
+        for level in self.bids.values() {
+            hash = self.checksum_level(hash, level);
+        }
+        
+        // Separator between bids and asks
+        hash = fnv1a(hash, &[0xFFu8; 8]);
+        
+        for level in self.asks.values() {
+            hash = self.checksum_level(hash, level);
+        }
+        
+        hash
+    }
+    
+    /// Fold one price level and its orders (head to tail) into an FNV-1a hash
+    fn checksum_level(&self, mut hash: u64, level: &PriceLevel) -> u64 {
+        // Retained empty levels hold no state
+        if level.is_empty() {
+            return hash;
+        }
+        
+        hash = fnv1a(hash, &level.price.to_le_bytes());
+        hash = fnv1a(hash, &level.total_quantity.to_le_bytes());
+        hash = fnv1a(hash, &(level.order_count as u64).to_le_bytes());
+        
+        let mut current = level.head;
+        while let Some(key) = current {
+            let node = match self.orders.get(key) {
+                Some(n) => n,
+                None => break,
+            };
+            hash = fnv1a(hash, &order_state_bytes(&node.order));
+            current = node.next;
+        }
+        
+        hash
+    }
+    
+    /// Clone the book with freshly seeded hash maps
+    ///
+    /// A plain `clone` copies each map's hasher and bucket layout, so the
+    /// copy iterates its maps exactly like the original. This copy keeps
+    /// the slab keys, levels and settings but rebuilds every hash map, so
+    /// anything that leaks hash iteration order behaves differently on it.
+    pub(crate) fn clone_rehashed(&self) -> CLOB {
+        let mut copy = self.clone();
+        copy.order_index = self.order_index.iter().map(|(&id, &key)| (id, key)).collect();
+        copy.user_index = self
+            .user_index
+            .iter()
+            .map(|(&user_id, keys)| (user_id, keys.iter().copied().collect()))
+            .collect();
+        copy.recent_fill_set = self.recent_fill_set.iter().copied().collect();
+        copy
+    }
+    
     /// Clear all orders from the book
     pub fn clear(&mut self) {
         self.orders.clear();
         self.bids.clear();
         self.asks.clear();
         self.order_index.clear();
+        self.user_index.clear();
         self.bid_count = 0;
         self.ask_count = 0;
+        self.seq += 1;
+    }
+    
+    /// Return the book to the state of a fresh `with_capacity` book,
+    /// keeping its allocations
+    ///
+    /// Unlike `clear`, this also resets the ID counters, the mutation
+    /// sequence, fill tracking and every builder setting.
+    pub(crate) fn reset(&mut self) {
+        self.clear();
+        self.order_ids = IdAllocator::default();
+        self.next_trade_id = 1;
+        self.seq = 0;
+        self.price_order = PriceOrder::Standard;
+        self.level_ordering = LevelOrdering::Fifo;
+        self.priority_decay = 0;
+        self.retain_empty_levels = false;
+        self.fill_tracking = 0;
+        self.max_level_orders = 0;
+        self.recent_fills.clear();
+        self.recent_fill_set.clear();
+    }
+}
+
+// ============================================================================
+// Checksum Helpers
+// ============================================================================
+
+/// FNV-1a 64-bit offset basis
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64-bit prime
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Size of one order's contribution to the state root and checksum
+const ORDER_STATE_LEN: usize = 8 + 8 + 1 + 1 + 8 + 8 + 8 + 8 + 8 + 16 + 16;
+
+/// Encode every persisted field of an order, little-endian, for hashing
+///
+/// The raw side and type bytes are used as stored, so two orders that
+/// differ only in an unrecognised byte still hash differently.
+fn order_state_bytes(order: &Order) -> [u8; ORDER_STATE_LEN] {
+    let mut out = [0u8; ORDER_STATE_LEN];
+    let mut at = 0;
+    let mut put = |bytes: &[u8]| {
+        out[at..at + bytes.len()].copy_from_slice(bytes);
+        at += bytes.len();
+    };
+    put(&order.id.to_le_bytes());
+    put(&order.user_id.to_le_bytes());
+    put(&[order.side_raw, order.order_type_raw]);
+    put(&order.price.to_le_bytes());
+    put(&order.quantity.to_le_bytes());
+    put(&order.remaining.to_le_bytes());
+    put(&order.timestamp.to_le_bytes());
+    put(&order.cumulative_filled.to_le_bytes());
+    put(&order.cumulative_notional.to_le_bytes());
+    put(&order.client_tag);
+    out
+}
+
+/// Fold bytes into an FNV-1a 64-bit hash
+#[inline]
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
     }
+    hash
 }
 
 // ============================================================================
@@ -623,6 +2588,8 @@ impl CLOB {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::types::price::MAX_FIXED;
+    use crate::types::OrderType;
     
     fn create_buy_order(id: u64, price: u64, quantity: u64) -> Order {
         Order::new(id, 100, Side::Buy, price, quantity, 0)
@@ -652,6 +2619,57 @@ mod tests {
         assert!(clob.is_empty());
     }
     
+    #[test]
+    fn test_clob_remaining_capacity() {
+        let mut clob = CLOB::with_capacity(64);
+        let capacity = clob.capacity();
+        
+        assert_eq!(clob.remaining_capacity(), capacity);
+        assert_eq!(clob.load_factor(), 0.0);
+        
+        for i in 0..capacity as u64 {
+            let before = clob.remaining_capacity();
+            clob.add_order(Order::new(i + 1, 100, Side::Buy, 100_000_000 + i, 100_000_000, 0));
+            assert_eq!(clob.remaining_capacity(), before - 1);
+        }
+        
+        // Full slab: no headroom left and load factor at 1.0
+        assert_eq!(clob.remaining_capacity(), 0);
+        assert_eq!(clob.load_factor(), 1.0);
+        
+        clob.cancel_order(1);
+        assert_eq!(clob.remaining_capacity(), 1);
+        assert!(clob.load_factor() < 1.0);
+    }
+    
+    #[test]
+    fn test_clob_estimated_memory_bytes() {
+        fn filled_book(orders: u64) -> CLOB {
+            let mut clob = CLOB::with_capacity(orders as usize);
+            for i in 0..orders {
+                let side = if i % 2 == 0 { Side::Buy } else { Side::Sell };
+                let price = match side {
+                    Side::Buy => 100_000_000 - (i % 500),
+                    Side::Sell => 200_000_000 + (i % 500),
+                };
+                clob.add_order(Order::new(i + 1, 100, side, price, 100_000_000, 0));
+            }
+            clob
+        }
+        
+        let small = filled_book(10_000);
+        let large = filled_book(20_000);
+        
+        // At least the raw node storage, and inside the per-order target
+        let per_order = small.estimated_memory_bytes() / small.order_count();
+        assert!(per_order >= std::mem::size_of::<OrderNode>());
+        assert!(per_order < 200, "estimated {} bytes per order", per_order);
+        
+        // Doubling the order count roughly doubles the estimate
+        let ratio = large.estimated_memory_bytes() as f64 / small.estimated_memory_bytes() as f64;
+        assert!((1.8..=2.2).contains(&ratio), "ratio {}", ratio);
+    }
+    
     #[test]
     fn test_clob_add_buy_order() {
         let mut clob = CLOB::with_capacity(100);
@@ -668,17 +2686,70 @@ mod tests {
     }
     
     #[test]
-    fn test_clob_add_sell_order() {
+    fn test_clob_add_sell_order() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        let order = create_sell_order(1, 5_100_000_000_000, 100_000_000);
+        clob.add_order(order);
+        
+        assert_eq!(clob.order_count(), 1);
+        assert_eq!(clob.bid_count(), 0);
+        assert_eq!(clob.ask_count(), 1);
+        assert!(clob.best_bid().is_none());
+        assert_eq!(clob.best_ask(), Some(5_100_000_000_000));
+    }
+    
+    #[test]
+    fn test_clob_cancel_order_detailed() {
+        let mut clob = CLOB::with_capacity(100).with_fill_tracking(2);
+        clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+        
+        assert_eq!(clob.cancel_order_detailed(7), CancelOutcome::NotFound);
+        assert!(matches!(clob.cancel_order_detailed(1), CancelOutcome::Canceled(o) if o.id == 1));
+        
+        // A cancelled order is not a filled one
+        assert_eq!(clob.cancel_order_detailed(1), CancelOutcome::NotFound);
+        
+        // Window of two: the oldest filled ID is forgotten
+        for id in [10, 11, 12] {
+            clob.record_filled(id);
+        }
+        assert_eq!(clob.cancel_order_detailed(10), CancelOutcome::NotFound);
+        assert_eq!(clob.cancel_order_detailed(11), CancelOutcome::AlreadyFilled);
+        assert_eq!(clob.cancel_order_detailed(12), CancelOutcome::AlreadyFilled);
+        
+        // Tracking off: filled IDs are never remembered
+        let mut untracked = CLOB::with_capacity(10);
+        untracked.record_filled(5);
+        assert_eq!(untracked.cancel_order_detailed(5), CancelOutcome::NotFound);
+    }
+    
+    #[test]
+    fn test_clob_top_of_book() {
         let mut clob = CLOB::with_capacity(100);
+        assert_eq!(clob.top_of_book(), TopOfBook::default());
         
-        let order = create_sell_order(1, 5_100_000_000_000, 100_000_000);
-        clob.add_order(order);
+        clob.add_order(Order::new(1, 100, Side::Buy, 4_900_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Buy, 4_900_000_000_000, 200_000_000, 0));
+        clob.add_order(Order::new(3, 100, Side::Buy, 4_800_000_000_000, 900_000_000, 0));
         
-        assert_eq!(clob.order_count(), 1);
-        assert_eq!(clob.bid_count(), 0);
-        assert_eq!(clob.ask_count(), 1);
-        assert!(clob.best_bid().is_none());
-        assert_eq!(clob.best_ask(), Some(5_100_000_000_000));
+        // One-sided book
+        let top = clob.top_of_book();
+        assert_eq!(top.bid_price, clob.best_bid());
+        assert_eq!(top.bid_size, Some(300_000_000));
+        assert_eq!(top.ask_price, None);
+        assert_eq!(top.ask_size, None);
+        
+        clob.add_order(Order::new(4, 100, Side::Sell, 5_100_000_000_000, 50_000_000, 0));
+        clob.add_order(Order::new(5, 100, Side::Sell, 5_000_000_000_000, 70_000_000, 0));
+        
+        let top = clob.top_of_book();
+        assert_eq!(top.bid_price, clob.best_bid());
+        assert_eq!(top.bid_size, clob.best_bid_level().map(|l| l.total_quantity));
+        assert_eq!(top.ask_price, clob.best_ask());
+        assert_eq!(top.ask_size, clob.best_ask_level().map(|l| l.total_quantity));
+        assert_eq!(top.ask_price, Some(5_000_000_000_000));
+        assert_eq!(top.ask_size, Some(70_000_000));
     }
     
     #[test]
@@ -723,6 +2794,63 @@ mod tests {
         assert_eq!(clob.ask_levels(), 3);
     }
     
+    #[test]
+    fn test_clob_inverse_price_order() {
+        let mut clob = CLOB::with_capacity(100).with_price_order(PriceOrder::Inverse);
+        assert_eq!(clob.price_order(), PriceOrder::Inverse);
+        
+        clob.add_order(create_buy_order(1, 4_900_000_000_000, 100_000_000)); // 49000
+        clob.add_order(create_buy_order(2, 4_800_000_000_000, 200_000_000)); // 48000
+        clob.add_order(create_sell_order(3, 5_000_000_000_000, 100_000_000)); // 50000
+        clob.add_order(create_sell_order(4, 5_100_000_000_000, 300_000_000)); // 51000
+        
+        // Selection is reversed relative to standard ordering
+        assert_eq!(clob.best_bid(), Some(4_800_000_000_000));
+        assert_eq!(clob.best_ask(), Some(5_100_000_000_000));
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 200_000_000);
+        assert_eq!(clob.best_ask_level().unwrap().total_quantity, 300_000_000);
+        
+        // Ask is numerically above bid, so the inverse book is crossed
+        assert!(clob.spread().is_none());
+        
+        // Standard ordering is the default
+        assert_eq!(CLOB::new().price_order(), PriceOrder::Standard);
+    }
+    
+    #[test]
+    fn test_price_order_crosses() {
+        assert!(PriceOrder::Standard.crosses(Side::Buy, 100, 90));
+        assert!(!PriceOrder::Standard.crosses(Side::Buy, 100, 110));
+        assert!(PriceOrder::Inverse.crosses(Side::Buy, 100, 110));
+        assert!(!PriceOrder::Inverse.crosses(Side::Buy, 100, 90));
+        assert!(PriceOrder::Inverse.crosses(Side::Sell, 100, 90));
+        assert!(PriceOrder::Inverse.crosses(Side::Sell, 100, 100));
+    }
+    
+    #[test]
+    fn test_clob_level_summaries() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 5_000_000_000_000, 200_000_000));
+        clob.add_order(create_buy_order(3, 4_900_000_000_000, 300_000_000));
+        clob.add_order(create_buy_order(4, 5_000_000_000_000, 400_000_000));
+        clob.cancel_order(2);
+        
+        let summary = clob.level_summary(Side::Buy, 5_000_000_000_000).unwrap();
+        assert_eq!(summary.price, 5_000_000_000_000);
+        assert_eq!(summary.total_quantity, 500_000_000);
+        assert_eq!(summary.order_count, 2);
+        assert!(clob.level_summary(Side::Sell, 5_000_000_000_000).is_none());
+        
+        // Best first, capped at the requested number of levels
+        let levels = clob.level_summaries(Side::Buy, 5);
+        assert_eq!(levels.len(), 2);
+        assert_eq!(levels[0], summary);
+        assert_eq!(levels[1].price, 4_900_000_000_000);
+        assert_eq!(clob.level_summaries(Side::Buy, 1).len(), 1);
+    }
+    
     #[test]
     fn test_clob_cancel_order() {
         let mut clob = CLOB::with_capacity(100);
@@ -745,6 +2873,549 @@ mod tests {
         assert!(cancelled.is_none());
     }
     
+    #[test]
+    fn test_clob_cumulative_depth() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 5_000_000_000_000, 50_000_000));
+        clob.add_order(create_buy_order(3, 4_900_000_000_000, 200_000_000));
+        clob.add_order(create_buy_order(4, 4_800_000_000_000, 25_000_000));
+        clob.add_order(create_buy_order(5, 4_700_000_000_000, 400_000_000));
+        
+        let depth = clob.cumulative_depth(Side::Buy, 3);
+        assert_eq!(depth, vec![
+            (5_000_000_000_000, 150_000_000),
+            (4_900_000_000_000, 350_000_000),
+            (4_800_000_000_000, 375_000_000),
+        ]);
+        assert!(depth.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+        
+        // The last entry is the sum over exactly those levels
+        let summed: u64 = clob.level_summaries(Side::Buy, 3).iter().map(|l| l.total_quantity).sum();
+        assert_eq!(depth.last().unwrap().1, summed);
+        
+        assert_eq!(clob.cumulative_depth(Side::Buy, 10).len(), 4);
+        assert!(clob.cumulative_depth(Side::Sell, 10).is_empty());
+    }
+    
+    #[test]
+    fn test_clob_average_resting_price() {
+        let mut clob = CLOB::with_capacity(100);
+        assert_eq!(clob.average_resting_price(Side::Buy), None);
+        
+        // 1.0 at 50000 and 3.0 at 49000 across three orders
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 4_900_000_000_000, 200_000_000));
+        clob.add_order(create_buy_order(3, 4_900_000_000_000, 100_000_000));
+        
+        // (50000 * 1 + 49000 * 3) / 4 = 49250, a quarter of the way from the bigger level
+        assert_eq!(clob.average_resting_price(Side::Buy), Some(4_925_000_000_000));
+        assert_eq!(clob.average_resting_price(Side::Sell), None);
+        
+        // Rounds down when the division is inexact: (1 * 1 + 2 * 2) / 3
+        let mut small = CLOB::with_capacity(10);
+        small.add_order(create_sell_order(1, 1, 1));
+        small.add_order(create_sell_order(2, 2, 2));
+        assert_eq!(small.average_resting_price(Side::Sell), Some(1));
+        
+        // Levels at MAX_VALUE whose notionals sum past u128
+        let mut extreme = CLOB::with_capacity(10);
+        extreme.add_order(create_sell_order(1, MAX_FIXED, MAX_FIXED));
+        extreme.add_order(create_sell_order(2, MAX_FIXED - SCALE, MAX_FIXED));
+        assert_eq!(extreme.average_resting_price(Side::Sell), Some(MAX_FIXED - SCALE / 2));
+        assert_eq!(extreme.user_resting_exposure(100), (0, u64::MAX, 0, u64::MAX));
+    }
+    
+    #[test]
+    fn test_clob_user_resting_exposure() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        // User 7: bids of 1.0 @ 50000 and 0.5 @ 49000, ask of 2.0 @ 51000
+        clob.add_order(Order::new(1, 7, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 7, Side::Buy, 4_900_000_000_000, 50_000_000, 0));
+        clob.add_order(Order::new(3, 7, Side::Sell, 5_100_000_000_000, 200_000_000, 0));
+        clob.add_order(Order::new(4, 8, Side::Buy, 5_000_000_000_000, 900_000_000, 0));
+        
+        assert_eq!(
+            clob.user_resting_exposure(7),
+            (150_000_000, 200_000_000, 7_450_000_000_000, 10_200_000_000_000)
+        );
+        assert_eq!(clob.user_resting_exposure(9), (0, 0, 0, 0));
+        
+        // Reductions and removals are reflected; the last order frees the entry
+        clob.reduce_order(1, 50_000_000);
+        clob.cancel_order(3);
+        assert_eq!(clob.user_resting_exposure(7), (100_000_000, 0, 4_950_000_000_000, 0));
+        clob.cancel_price_level(Side::Buy, 4_900_000_000_000);
+        clob.cancel_order(1);
+        assert_eq!(clob.user_resting_exposure(7), (0, 0, 0, 0));
+        assert!(!clob.user_index.contains_key(&7));
+        assert!(clob.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_clob_largest_level_size() {
+        let mut clob = CLOB::with_capacity(100).with_max_level_orders(3);
+        assert_eq!(clob.largest_level_size(), 0);
+        assert!(!clob.level_is_full(Side::Buy, 5_000_000_000_000));
+        
+        for id in 1..=3 {
+            clob.add_order(create_buy_order(id, 5_000_000_000_000, 100_000_000));
+        }
+        clob.add_order(create_sell_order(4, 5_100_000_000_000, 100_000_000));
+        
+        assert_eq!(clob.largest_level_size(), 3);
+        assert!(clob.level_is_full(Side::Buy, 5_000_000_000_000));
+        assert!(!clob.level_is_full(Side::Sell, 5_000_000_000_000));
+        assert!(!clob.level_is_full(Side::Sell, 5_100_000_000_000));
+        
+        // Every insert path respects the cap, leaving the book unchanged
+        assert!(clob.try_add_order(create_buy_order(5, 5_000_000_000_000, 100_000_000)).is_none());
+        clob.add_order(create_buy_order(6, 4_900_000_000_000, 100_000_000));
+        let root = clob.compute_state_root();
+        assert_eq!(clob.amend_order(6, 5_000_000_000_000, 100_000_000), None);
+        let added = crate::orderbook::BookEvent::Added {
+            order: create_buy_order(7, 5_000_000_000_000, 100_000_000),
+            assigned: false,
+            seq: clob.current_seq() + 1,
+        };
+        assert!(!clob.apply_event(&added));
+        assert_eq!(clob.compute_state_root(), root);
+        assert_eq!(clob.order_count(), 5);
+        
+        // A full level still takes a size change in place
+        assert!(clob.amend_order(1, 5_000_000_000_000, 200_000_000).is_some());
+        
+        clob.cancel_order(2);
+        assert!(!clob.level_is_full(Side::Buy, 5_000_000_000_000));
+        assert!(clob.amend_order(6, 5_000_000_000_000, 100_000_000).is_some());
+        
+        // Without a cap no level is ever full
+        let uncapped = CLOB::with_capacity(10);
+        assert!(!uncapped.level_is_full(Side::Buy, 5_000_000_000_000));
+    }
+    
+    #[test]
+    fn test_iter_side_matches_consumption_order() {
+        use crate::engine::MatchingEngine;
+        
+        let expected = [
+            (PriceOrder::Standard, vec![(2, 60_000_000), (4, 100_000_000), (1, 100_000_000), (3, 100_000_000)]),
+            (PriceOrder::Inverse, vec![(3, 100_000_000), (1, 100_000_000), (2, 60_000_000), (4, 100_000_000)]),
+        ];
+        for (price_order, expected) in expected {
+            let mut clob = CLOB::with_capacity(100).with_price_order(price_order);
+            clob.add_order(Order::new(1, 100, Side::Buy, 4_990_000_000_000, 100_000_000, 0));
+            clob.add_order(Order::new(2, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+            clob.add_order(Order::new(3, 100, Side::Buy, 4_980_000_000_000, 100_000_000, 0));
+            clob.add_order(Order::new(4, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+            clob.reduce_order(2, 40_000_000);
+            
+            // Best price first, FIFO within a price, live remaining quantity
+            let queue: Vec<_> = clob.iter_side(Side::Buy).map(|o| (o.id, o.remaining)).collect();
+            assert_eq!(queue, expected, "{:?}", price_order);
+            assert_eq!(clob.iter_side(Side::Sell).count(), 0);
+            
+            // A taker sweeping the side fills makers in the same order
+            let mut engine = MatchingEngine::new();
+            let result = engine.match_order(&mut clob, Order::market(9, 200, Side::Sell, 360_000_000, 0), 0);
+            let filled: Vec<_> = result.trades.iter().map(|t| (t.maker_order_id, t.quantity)).collect();
+            assert_eq!(filled, queue, "{:?}", price_order);
+        }
+    }
+    
+    /// A desynced count fails loudly in debug builds and clamps at zero
+    /// (for `verify_invariants` to report) in release builds
+    #[test]
+    #[cfg_attr(debug_assertions, should_panic(expected = "ask count underflow"))]
+    fn test_count_desync() {
+        let mut clob = CLOB::with_capacity(10);
+        clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        
+        // Force a desync: no asks rest, yet the matcher-facing hook is called
+        clob.decrement_ask_count();
+        
+        assert_eq!(clob.ask_count(), 0);
+        assert_eq!(clob.bid_count(), 1);
+        assert!(clob.verify_invariants().is_ok());
+        
+        clob.decrement_bid_count();
+        assert_eq!(clob.bid_count(), 0);
+        assert!(clob.verify_invariants().is_err());
+    }
+    
+    #[test]
+    fn test_retain_orders() {
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 101, Side::Buy, 4_990_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(3, 102, Side::Buy, 5_000_000_000_000, 200_000_000, 0));
+        clob.add_order(Order::new(4, 100, Side::Buy, 4_980_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(5, 103, Side::Sell, 5_010_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(6, 100, Side::Sell, 5_020_000_000_000, 100_000_000, 0));
+        
+        // Keep only bids above 49900
+        let removed = clob.retain_orders(|o| o.side() == Side::Buy && o.price > 4_990_000_000_000);
+        let ids: Vec<_> = removed.iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![2, 4, 5, 6]);
+        
+        assert_eq!((clob.bid_count(), clob.ask_count()), (2, 0));
+        assert_eq!((clob.bid_levels(), clob.ask_levels()), (1, 0));
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 300_000_000);
+        assert!(!clob.contains_order(2));
+        assert_eq!(clob.user_resting_exposure(100).0, 100_000_000);
+        clob.verify_invariants().unwrap();
+        
+        // Keeping everything is a no-op
+        assert!(clob.retain_orders(|_| true).is_empty());
+        assert_eq!(clob.order_count(), 2);
+    }
+    
+    #[test]
+    fn test_canonical_level_bytes_ignore_history() {
+        let orders = [
+            Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 1),
+            Order::new(2, 101, Side::Buy, 5_000_000_000_000, 200_000_000, 2),
+            Order::new(3, 102, Side::Sell, 5_010_000_000_000, 100_000_000, 3),
+        ];
+        
+        let mut direct = CLOB::with_capacity(10);
+        for order in &orders {
+            direct.add_order(order.clone());
+        }
+        
+        // Same orders after churn: other orders and a whole level came and
+        // went, emptied levels are retained, and the slab keys differ
+        let mut churned = CLOB::with_capacity(1_000).with_level_retention(true);
+        churned.add_order(Order::new(9, 109, Side::Buy, 4_990_000_000_000, 100_000_000, 0));
+        churned.add_order(Order::new(8, 108, Side::Sell, 5_010_000_000_000, 100_000_000, 0));
+        churned.add_order(orders[0].clone());
+        churned.cancel_order(8);
+        churned.add_order(orders[1].clone());
+        churned.add_order(orders[2].clone());
+        churned.cancel_order(9);
+        
+        assert_eq!(churned.canonical_level_bytes(), direct.canonical_level_bytes());
+        
+        // Queue order and remaining quantity both count
+        let mut reordered = CLOB::with_capacity(10);
+        for order in [&orders[1], &orders[0], &orders[2]] {
+            reordered.add_order(order.clone());
+        }
+        assert_ne!(reordered.canonical_level_bytes(), direct.canonical_level_bytes());
+        
+        direct.reduce_order(1, 1);
+        assert_ne!(churned.canonical_level_bytes(), direct.canonical_level_bytes());
+    }
+    
+    #[test]
+    fn test_clob_current_seq() {
+        let mut clob = CLOB::with_capacity(100);
+        assert_eq!(clob.current_seq(), 0);
+        
+        // Five mutations
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000));
+        clob.reduce_order(1, 10_000_000);
+        clob.cancel_order(3);
+        assert_eq!(clob.current_seq(), 5);
+        
+        // Reads and failed mutations leave it alone
+        clob.best_bid();
+        clob.top_of_book();
+        clob.level_summaries(Side::Buy, 10);
+        clob.compute_state_root();
+        clob.verify_invariants().unwrap();
+        assert!(clob.cancel_order(99).is_none());
+        assert!(clob.reduce_order(99, 1).is_none());
+        assert_eq!(clob.amend_order(2, 5_000_000_000_000, 100_000_000), clob.get_key(2));
+        assert_eq!(clob.current_seq(), 5);
+        
+        // A cancelled level is one delta per order; a reprice is two
+        clob.amend_order(2, 4_900_000_000_000, 100_000_000);
+        assert_eq!(clob.current_seq(), 7);
+        clob.add_order(create_buy_order(4, 4_900_000_000_000, 100_000_000));
+        clob.cancel_price_level(Side::Buy, 4_900_000_000_000);
+        assert_eq!(clob.current_seq(), 10);
+    }
+    
+    #[test]
+    fn test_clob_cancel_orders() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(4, 5_200_000_000_000, 100_000_000));
+        
+        let flags = clob.cancel_orders(&[3, 99, 1, 3, 0]);
+        assert_eq!(flags, vec![(3, true), (99, false), (1, true), (3, false), (0, false)]);
+        
+        // Only the valid IDs were removed
+        assert!(!clob.contains_order(1) && !clob.contains_order(3));
+        assert!(clob.contains_order(2) && clob.contains_order(4));
+        assert_eq!(clob.best_ask(), Some(5_200_000_000_000));
+        assert!(clob.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_clob_cancel_price_level() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        // Three orders at one ask level, plus one at another level
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 200_000_000));
+        clob.add_order(create_sell_order(3, 5_000_000_000_000, 300_000_000));
+        clob.add_order(create_sell_order(4, 5_100_000_000_000, 100_000_000));
+        
+        let cancelled = clob.cancel_price_level(Side::Sell, 5_000_000_000_000);
+        
+        // Returned in FIFO order
+        let ids: Vec<u64> = cancelled.iter().map(|o| o.id).collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+        
+        // Level removed from the BTreeMap, counts and index updated
+        assert!(!clob.asks().contains_key(&5_000_000_000_000));
+        assert_eq!(clob.ask_levels(), 1);
+        assert_eq!(clob.ask_count(), 1);
+        assert_eq!(clob.order_count(), 1);
+        assert!(!clob.contains_order(1));
+        assert!(!clob.contains_order(3));
+        assert!(clob.contains_order(4));
+        assert_eq!(clob.best_ask(), Some(5_100_000_000_000));
+        
+        // Missing level is a no-op
+        assert!(clob.cancel_price_level(Side::Buy, 5_000_000_000_000).is_empty());
+    }
+    
+    #[test]
+    fn test_clob_reduce_order() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        clob.add_order(create_sell_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_000_000_000_000, 200_000_000));
+        
+        // Partial reduction keeps the order and its queue position
+        assert_eq!(clob.reduce_order(1, 30_000_000), Some(70_000_000));
+        let level = clob.best_ask_level().unwrap();
+        assert_eq!(level.total_quantity, 270_000_000);
+        assert_eq!(level.order_count, 2);
+        assert_eq!(level.head, clob.get_key(1));
+        
+        // Reducing past zero clamps to a cancel
+        assert_eq!(clob.reduce_order(1, 500_000_000), Some(0));
+        assert!(!clob.contains_order(1));
+        let level = clob.best_ask_level().unwrap();
+        assert_eq!(level.total_quantity, 200_000_000);
+        assert_eq!(level.order_count, 1);
+        assert_eq!(clob.ask_count(), 1);
+        
+        // Unknown order
+        assert_eq!(clob.reduce_order(999, 1), None);
+    }
+    
+    #[test]
+    fn test_apply_priority_decay() {
+        let mut clob = CLOB::with_capacity(100).with_priority_decay(100);
+        let price = 5_000_000_000_000;
+        
+        // Queue at one price: ages 150, 50, 120, 10 at now = 200
+        for (id, timestamp) in [(1, 50), (2, 150), (3, 80), (4, 190)] {
+            clob.add_order(Order::new(id, 100, Side::Buy, price, 100_000_000, timestamp));
+        }
+        clob.add_order(Order::new(5, 100, Side::Sell, price + 1, 100_000_000, 0));
+        let seq = clob.current_seq();
+        
+        // Aged orders keep their relative order behind the young ones
+        assert_eq!(clob.apply_priority_decay(200), 2);
+        let queue: Vec<u64> = clob.iter_side(Side::Buy).map(|o| o.id).collect();
+        assert_eq!(queue, vec![2, 4, 1, 3]);
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 400_000_000);
+        assert!(clob.current_seq() > seq);
+        assert!(clob.verify_invariants().is_ok());
+        
+        // Stable: nothing moves again, and a lone aged order stays put
+        let root = clob.compute_state_root();
+        assert_eq!(clob.apply_priority_decay(200), 0);
+        assert_eq!(clob.compute_state_root(), root);
+        assert_eq!(clob.queue_position(5), Some(0));
+        
+        // Off by default
+        let mut plain = CLOB::with_capacity(100);
+        plain.add_order(Order::new(1, 100, Side::Buy, price, 100_000_000, 0));
+        plain.add_order(Order::new(2, 100, Side::Buy, price, 100_000_000, 1_000));
+        assert_eq!(plain.apply_priority_decay(u64::MAX), 0);
+        assert_eq!(plain.queue_position(1), Some(0));
+    }
+    
+    #[test]
+    fn test_order_context() {
+        let mut clob = CLOB::with_capacity(100);
+        let touch = 5_000_000_000_000;
+        
+        clob.add_order(create_sell_order(1, touch, 100_000_000));
+        clob.add_order(create_sell_order(2, touch + 3_000_000, 70_000_000));
+        clob.add_order(create_sell_order(3, touch + 3_000_000, 40_000_000));
+        clob.add_order(create_sell_order(4, touch + 3_000_000, 25_000_000));
+        clob.add_order(create_buy_order(5, touch - 1_000_000, 50_000_000));
+        clob.reduce_order(3, 10_000_000);
+        
+        let context = clob.order_context(4).unwrap();
+        assert_eq!(context.queue_position, 2);
+        assert_eq!(context.quantity_ahead, 100_000_000);
+        assert_eq!(context.touch_distance, 3_000_000);
+        assert_eq!(context.ticks_from_touch(1_000_000), Some(3));
+        assert_eq!(context.ticks_from_touch(0), None);
+        assert_eq!(context.bbo, clob.top_of_book());
+        
+        // Orders at the touch have nothing ahead and no distance
+        let head = clob.order_context(1).unwrap();
+        assert_eq!((head.queue_position, head.quantity_ahead, head.touch_distance), (0, 0, 0));
+        assert_eq!(clob.order_context(5).unwrap().touch_distance, 0);
+        
+        // Under inverse ordering the touch moves to the other end
+        let mut inverse = CLOB::with_capacity(100).with_price_order(PriceOrder::Inverse);
+        inverse.add_order(create_buy_order(1, touch, 10_000_000));
+        inverse.add_order(create_buy_order(2, touch + 2_000_000, 10_000_000));
+        assert_eq!(inverse.order_context(2).unwrap().touch_distance, 2_000_000);
+        
+        assert_eq!(clob.order_context(99), None);
+    }
+    
+    #[test]
+    fn test_clob_amend_order_priority() {
+        let mut clob = CLOB::with_capacity(100);
+        let price = 5_000_000_000_000;
+        
+        clob.add_order(create_buy_order(1, price, 100_000_000));
+        clob.add_order(create_buy_order(2, price, 100_000_000));
+        clob.add_order(create_buy_order(3, price, 100_000_000));
+        let key = clob.get_key(2).unwrap();
+        
+        // No-op amend of the middle order changes nothing
+        let root = clob.compute_state_root();
+        assert_eq!(clob.amend_order(2, price, 100_000_000), Some(key));
+        assert_eq!(clob.queue_position(2), Some(1));
+        assert_eq!(clob.compute_state_root(), root);
+        
+        // Shrinking keeps the position
+        assert_eq!(clob.amend_order(2, price, 60_000_000), Some(key));
+        assert_eq!(clob.queue_position(2), Some(1));
+        
+        // Growing resets it
+        clob.amend_order(2, price, 150_000_000).unwrap();
+        assert_eq!(clob.queue_position(2), Some(2));
+        assert_eq!(clob.queue_position(3), Some(1));
+        assert_eq!(clob.best_bid_level().unwrap().total_quantity, 350_000_000);
+        
+        // Moving price starts a new level; zero cancels
+        clob.amend_order(1, price - 1, 100_000_000).unwrap();
+        assert_eq!(clob.bid_levels(), 2);
+        assert_eq!(clob.amend_order(3, price, 0), None);
+        assert!(!clob.contains_order(3));
+        assert_eq!(clob.amend_order(99, price, 1), None);
+        
+        // A partly filled order whose new total would overflow is left alone
+        let mut partial = create_buy_order(4, price, 100_000_000);
+        partial.remaining = 40_000_000;
+        clob.add_order(partial);
+        let root = clob.compute_state_root();
+        assert_eq!(clob.amend_order(4, price - 1, u64::MAX), None);
+        assert_eq!(clob.compute_state_root(), root);
+        assert!(clob.amend_order(4, price - 1, u64::MAX - 60_000_000).is_some());
+        assert!(clob.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_clob_queue_position() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        for id in 1..=4 {
+            clob.add_order(create_buy_order(id, 5_000_000_000_000, 100_000_000));
+        }
+        clob.add_order(create_buy_order(5, 4_900_000_000_000, 100_000_000));
+        
+        assert_eq!(clob.queue_position(1), Some(0));
+        assert_eq!(clob.queue_position(3), Some(2));
+        assert_eq!(clob.queue_position(4), Some(3));
+        
+        // Each level has its own queue
+        assert_eq!(clob.queue_position(5), Some(0));
+        
+        // Cancelling ahead moves the order up
+        clob.cancel_order(2);
+        assert_eq!(clob.queue_position(3), Some(1));
+        
+        assert_eq!(clob.queue_position(2), None);
+        assert_eq!(clob.queue_position(99), None);
+    }
+    
+    #[test]
+    fn test_clob_level_retention() {
+        let mut plain = CLOB::with_capacity(100);
+        let mut retained = CLOB::with_capacity(100).with_level_retention(true);
+        
+        for clob in [&mut plain, &mut retained] {
+            clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+            clob.add_order(create_buy_order(2, 4_900_000_000_000, 100_000_000));
+            clob.add_order(create_sell_order(3, 5_100_000_000_000, 100_000_000));
+            clob.cancel_order(1);
+            clob.cancel_order(3);
+        }
+        
+        // The emptied levels are still in the maps...
+        assert_eq!(retained.bids().len(), 2);
+        assert_eq!(retained.asks().len(), 1);
+        assert!(retained.verify_invariants().is_ok());
+        
+        // ...but public behavior matches a book without retention
+        assert_eq!(retained.best_bid(), Some(4_900_000_000_000));
+        assert_eq!(retained.best_ask(), None);
+        assert_eq!(retained.bid_levels(), 1);
+        assert_eq!(retained.ask_levels(), 0);
+        assert_eq!(retained.level_summary(Side::Buy, 5_000_000_000_000), None);
+        assert_eq!(retained.level_summaries(Side::Buy, 10), plain.level_summaries(Side::Buy, 10));
+        assert_eq!(retained.checksum(), plain.checksum());
+        assert_eq!(retained.compute_state_root(), plain.compute_state_root());
+        
+        // The next order at the price reuses the retained level
+        retained.add_order(create_buy_order(4, 5_000_000_000_000, 100_000_000));
+        assert_eq!(retained.best_bid(), Some(5_000_000_000_000));
+        assert_eq!(retained.bids().len(), 2);
+        
+        assert_eq!(retained.purge_empty_levels(), 1);
+        assert!(retained.asks().is_empty());
+        assert!(retained.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_clob_levels_from_touch() {
+        let mut clob = CLOB::with_capacity(100);
+        
+        clob.add_order(create_buy_order(1, 4_900_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 5_000_000_000_000, 200_000_000));
+        clob.add_order(create_buy_order(3, 4_800_000_000_000, 300_000_000));
+        clob.add_order(create_sell_order(4, 5_100_000_000_000, 400_000_000));
+        
+        let levels: Vec<_> = clob.levels_from_touch().collect();
+        assert_eq!(levels, vec![
+            (Side::Buy, 5_000_000_000_000, 200_000_000),
+            (Side::Sell, 5_100_000_000_000, 400_000_000),
+            (Side::Buy, 4_900_000_000_000, 100_000_000),
+            (Side::Buy, 4_800_000_000_000, 300_000_000),
+        ]);
+        
+        // Terminates cleanly once both sides are exhausted
+        let mut iter = clob.levels_from_touch().skip(4);
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next(), None);
+        
+        assert_eq!(CLOB::new().levels_from_touch().count(), 0);
+    }
+    
     #[test]
     fn test_clob_contains_order() {
         let mut clob = CLOB::with_capacity(100);
@@ -791,6 +3462,113 @@ mod tests {
         assert_eq!(clob.peek_next_order_id(), 2);
     }
     
+    #[test]
+    fn test_clob_state_root_depends_on_explicit_side_byte() {
+        // The wire encoding is pinned; enum discriminants are not
+        assert_eq!((Side::Buy.to_u8(), Side::Sell.to_u8()), (0, 1));
+        assert_eq!((OrderType::Limit.to_u8(), OrderType::Market.to_u8()), (0, 1));
+        
+        // Byte 2 is not a valid side but still rests as a bid, so the two
+        // books differ only in the stored byte
+        let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 7);
+        let odd = Order { side_raw: 2, ..order.clone() };
+        
+        let mut a = CLOB::with_capacity(10);
+        let mut b = CLOB::with_capacity(10);
+        a.add_order(order);
+        b.add_order(odd);
+        assert_eq!(a.best_bid(), b.best_bid());
+        assert_eq!(a.bids().values().next().unwrap().summary(), b.bids().values().next().unwrap().summary());
+        
+        assert_ne!(a.compute_state_root(), b.compute_state_root());
+        assert_ne!(a.checksum(), b.checksum());
+    }
+    
+    #[test]
+    fn test_state_root_covers_every_order_field() {
+        let base = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 300_000_000, 7);
+        let mut filled = base.clone();
+        filled.record_fill(100_000_000, 5_000_000_000_000);
+        
+        let variants = [
+            Order { cumulative_filled: 1, ..base.clone() },
+            Order { cumulative_notional: 1, ..base.clone() },
+            base.clone().with_client_tag([1; 16]),
+            filled,
+        ];
+        let book = |order: Order| {
+            let mut clob = CLOB::with_capacity(10);
+            clob.add_order(order);
+            clob
+        };
+        
+        let reference = book(base);
+        for order in variants {
+            let other = book(order.clone());
+            assert_ne!(other.compute_state_root(), reference.compute_state_root(), "{:?}", order);
+            assert_ne!(other.checksum(), reference.checksum(), "{:?}", order);
+        }
+    }
+    
+    #[test]
+    fn test_clob_checksum() {
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_sell_order(2, 5_100_000_000_000, 100_000_000));
+        
+        let before = clob.checksum();
+        assert_eq!(before, clob.checksum(), "Checksum must be stable");
+        
+        // Add + cancel of the same order restores the checksum
+        clob.add_order(create_buy_order(3, 4_900_000_000_000, 100_000_000));
+        assert_ne!(clob.checksum(), before);
+        clob.cancel_order(3);
+        assert_eq!(clob.checksum(), before);
+        
+        // A logically identical book built separately has the same checksum
+        let mut other = CLOB::new();
+        other.add_order(create_sell_order(2, 5_100_000_000_000, 100_000_000));
+        other.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        assert_eq!(other.checksum(), before);
+    }
+    
+    #[test]
+    fn test_clob_verify_invariants() {
+        let mut clob = CLOB::with_capacity(100);
+        assert_eq!(clob.verify_invariants(), Ok(()));
+        
+        clob.add_order(create_buy_order(1, 5_000_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 5_000_000_000_000, 200_000_000));
+        clob.add_order(create_buy_order(3, 5_000_000_000_000, 300_000_000));
+        clob.add_order(create_sell_order(4, 5_100_000_000_000, 100_000_000));
+        clob.cancel_order(2);
+        clob.reduce_order(3, 50_000_000);
+        assert_eq!(clob.verify_invariants(), Ok(()));
+        
+        // Quantity drift is detected
+        clob.bids_mut().get_mut(&Reverse(5_000_000_000_000)).unwrap().total_quantity += 1;
+        assert!(matches!(
+            clob.verify_invariants(),
+            Err(InvariantViolation::CorruptLevel { side: Side::Buy, .. })
+        ));
+        clob.bids_mut().get_mut(&Reverse(5_000_000_000_000)).unwrap().total_quantity -= 1;
+        
+        // A broken back-link is detected
+        let key3 = clob.get_key(3).unwrap();
+        clob.orders_mut().get_mut(key3).unwrap().prev = None;
+        assert!(clob.verify_invariants().is_err());
+        let key1 = clob.get_key(1).unwrap();
+        clob.orders_mut().get_mut(key3).unwrap().prev = Some(key1);
+        assert_eq!(clob.verify_invariants(), Ok(()));
+        
+        // Count desync is detected
+        clob.decrement_ask_count();
+        assert!(matches!(
+            clob.verify_invariants(),
+            Err(InvariantViolation::CountMismatch(_))
+        ));
+    }
+    
     #[test]
     fn test_clob_clear() {
         let mut clob = CLOB::with_capacity(100);
@@ -851,5 +3629,91 @@ mod tests {
         assert_eq!(clob.get_key(42), Some(key));
         assert!(clob.get_key(999).is_none());
     }
+    
+    #[test]
+    fn test_assert_consistent() {
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(create_buy_order(1, 4_900_000_000_000, 100_000_000));
+        clob.add_order(create_buy_order(2, 4_800_000_000_000, 200_000_000));
+        clob.add_order(create_sell_order(3, 5_000_000_000_000, 100_000_000));
+        assert!(clob.assert_consistent().is_ok());
+        
+        // A level stored under the wrong key desyncs the BBO from the orders
+        let mut desynced = clob.clone();
+        desynced.bids_mut().get_mut(&Reverse(4_900_000_000_000)).unwrap().price = 4_950_000_000_000;
+        assert!(matches!(
+            desynced.assert_consistent(),
+            Err(InvariantViolation::CorruptLevel { side: Side::Buy, price: 4_900_000_000_000, .. })
+        ));
+        
+        // So does a level count that drifted from the side count
+        let mut miscounted = clob.clone();
+        miscounted.asks_mut().get_mut(&5_000_000_000_000).unwrap().order_count = 2;
+        assert!(matches!(miscounted.assert_consistent(), Err(InvariantViolation::CountMismatch(_))));
+        
+        // Loading a crossing order without matching leaves the book crossed
+        clob.add_order(create_sell_order(4, 4_900_000_000_000, 100_000_000));
+        assert_eq!(
+            clob.assert_consistent(),
+            Err(InvariantViolation::Crossed { bid: 4_900_000_000_000, ask: 4_900_000_000_000 })
+        );
+        assert!(clob.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_clone_rehashed_reseeds_maps() {
+        let mut clob = CLOB::with_capacity(1_000);
+        for id in 1..=1_000 {
+            clob.add_order(Order::new(id, id % 50, Side::Buy, 100_000_000 + id % 7, 100_000_000, id));
+        }
+        let order = |book: &CLOB| book.order_index.keys().copied().collect::<Vec<_>>();
+        
+        // A plain clone iterates identically; the rehashed copy does not
+        assert_eq!(order(&clob.clone()), order(&clob));
+        let copy = clob.clone_rehashed();
+        assert_ne!(order(&copy), order(&clob));
+        
+        // Everything observable is unchanged, slab keys included
+        assert_eq!(copy.compute_state_root(), clob.compute_state_root());
+        assert_eq!(copy.get_key(500), clob.get_key(500));
+        assert_eq!(copy.user_resting_exposure(7), clob.user_resting_exposure(7));
+        assert!(copy.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_disjoint_id_ranges_never_overlap() {
+        let mut a = CLOB::with_capacity(100).with_id_range(1, 51);
+        let mut b = CLOB::with_capacity(100).with_id_range(51, 101);
+        let mut ids = std::collections::HashSet::new();
+        for i in 0..50 {
+            let ka = a.add_order(Order::new(0, 100, Side::Buy, 100_000_000 + i, 100_000_000, i));
+            let kb = b.add_order(Order::new(0, 101, Side::Sell, 900_000_000 + i, 100_000_000, i));
+            assert!(ids.insert(a.get_order(ka).unwrap().id));
+            assert!(ids.insert(b.get_order(kb).unwrap().id));
+        }
+        assert_eq!(ids.len(), 100);
+        assert!(a.order_ids().is_exhausted() && b.order_ids().is_exhausted());
+        
+        // Exhaustion is reported, not a panic, on the fallible paths
+        let root = a.compute_state_root();
+        let auto = Order::new(0, 100, Side::Buy, 100_000_000, 100_000_000, 0);
+        assert!(a.try_add_order(auto.clone()).is_none());
+        let seq = a.current_seq() + 1;
+        let auto = Order { id: a.peek_next_order_id(), ..auto };
+        assert!(!a.apply_event(&crate::orderbook::BookEvent::Added { order: auto, assigned: true, seq }));
+        assert_eq!(a.compute_state_root(), root);
+        
+        // Explicit IDs still work once the range is used up, and reset restores the default range
+        a.add_order(Order::new(500, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+        a.reset();
+        assert_eq!(*a.order_ids(), IdAllocator::default());
+    }
+    
+    #[test]
+    #[should_panic(expected = "order ID range exhausted")]
+    fn test_exhausted_id_range_panics_on_auto_assign() {
+        let mut clob = CLOB::with_capacity(10).with_id_range(7, 8);
+        clob.add_order(Order::new(0, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(0, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    }
 }
-
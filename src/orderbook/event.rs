@@ -0,0 +1,187 @@
+//! Book events for read replicas.
+//!
+//! A [`MatchingEngine`](crate::engine::MatchingEngine) built with
+//! `with_book_events(true)` logs every change it makes to a book as a
+//! [`BookEvent`]. Applying the log in order to an empty book with
+//! [`CLOB::apply_event`] rebuilds the same orders, queues and ID counters,
+//! so the replica's state root matches the source's.
+//!
+//! Every event carries the source book's sequence number (see
+//! [`CLOB::current_seq`]) once the event has been applied, so a consumer
+//! can spot gaps and reordering, and a replica seeded from a snapshot
+//! knows which events it has already seen.
+//!
+//! Only engine-driven changes are logged. Mutations made directly on the
+//! `CLOB` (`add_order`, `cancel_order`, ...) bypass the engine, so a feed
+//! that allows them must log the equivalent events itself.
+
+use std::cmp::Reverse;
+
+use crate::orderbook::CLOB;
+use crate::types::{Order, Side};
+
+/// One change to a book, in the order the engine made it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookEvent {
+    /// An order came to rest
+    Added {
+        /// The order as it rests, with its final ID
+        order: Order,
+        
+        /// Whether the book assigned `order.id` from its allocator (the
+        /// order arrived with ID 0)
+        assigned: bool,
+        
+        /// Book sequence number after the add
+        seq: u64,
+    },
+    
+    /// A resting order traded; it leaves the book once nothing remains
+    Filled {
+        /// Resting order ID
+        order_id: u64,
+        
+        /// Trade that filled it
+        trade_id: u64,
+        
+        /// Quantity filled (fixed-point)
+        quantity: u64,
+        
+        /// Execution price (fixed-point)
+        price: u64,
+        
+        /// Book sequence number after the fill
+        seq: u64,
+    },
+    
+    /// A resting order left the book without trading (cancel/replace)
+    Removed {
+        /// Resting order ID
+        order_id: u64,
+        
+        /// Book sequence number after the removal
+        seq: u64,
+    },
+    
+    /// Aged orders moved behind younger ones (see [`CLOB::apply_priority_decay`])
+    Decayed {
+        /// Time the ages were measured at
+        now: u64,
+        
+        /// Age at which orders yielded priority
+        max_age: u64,
+        
+        /// Book sequence number after the requeue
+        seq: u64,
+    },
+}
+
+impl BookEvent {
+    /// Get the source book's sequence number once this event is applied
+    #[inline]
+    pub fn seq(&self) -> u64 {
+        match *self {
+            BookEvent::Added { seq, .. }
+            | BookEvent::Filled { seq, .. }
+            | BookEvent::Removed { seq, .. }
+            | BookEvent::Decayed { seq, .. } => seq,
+        }
+    }
+}
+
+impl CLOB {
+    /// Apply one logged event to this book
+    ///
+    /// # Returns
+    ///
+    /// `false`, leaving the book unchanged, if the event does not fit it:
+    /// an event whose sequence number is not past this book's (already
+    /// applied), an `Added` order whose ID is already resting (or whose
+    /// level is full, or whose assigned ID is not this book's next one), or a
+    /// `Filled` or `Removed` naming an order that is not (or a fill larger
+    /// than what remains).
+    /// Once applied, the book takes the event's sequence number.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::engine::MatchingEngine;
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// let mut engine = MatchingEngine::new().with_book_events(true);
+    /// engine.match_order(&mut clob, Order::new(1, 100, Side::Sell, 100_000_000, 300_000_000, 0), 0);
+    /// engine.match_order(&mut clob, Order::new(2, 101, Side::Buy, 100_000_000, 100_000_000, 0), 0);
+    ///
+    /// let mut replica = CLOB::with_capacity(100);
+    /// for event in engine.take_book_events() {
+    ///     assert!(replica.apply_event(&event));
+    /// }
+    /// assert_eq!(replica.compute_state_root(), clob.compute_state_root());
+    /// assert_eq!(replica.current_seq(), clob.current_seq());
+    /// ```
+    pub fn apply_event(&mut self, event: &BookEvent) -> bool {
+        if event.seq() <= self.current_seq() {
+            return false;
+        }
+        match *event {
+            BookEvent::Added { ref order, assigned, .. } => {
+                if self.contains_order(order.id) {
+                    return false;
+                }
+                
+                // Allocate the same ID here so the counters stay in step
+                let mut order = order.clone();
+                if assigned {
+                    if self.order_ids().is_exhausted() || self.peek_next_order_id() != order.id {
+                        return false;
+                    }
+                    order.id = 0;
+                }
+                if self.try_add_order(order).is_none() {
+                    return false;
+                }
+            }
+            BookEvent::Filled { order_id, trade_id, quantity, price, .. } => {
+                let (Some(key), Some(after_trade)) = (self.get_key(order_id), trade_id.checked_add(1)) else {
+                    return false;
+                };
+                let Some(node) = self.orders_mut().get_mut(key).filter(|node| node.remaining() >= quantity) else {
+                    return false;
+                };
+                node.fill(quantity);
+                node.order.record_fill(quantity, price);
+                let (side, level_price, filled) = (node.order.side(), node.price(), node.remaining() == 0);
+                
+                let level = match side {
+                    Side::Buy => self.bids_mut().get_mut(&Reverse(level_price)),
+                    Side::Sell => self.asks_mut().get_mut(&level_price),
+                };
+                if let Some(level) = level {
+                    level.reduce_quantity(quantity);
+                }
+                // One sequence step per fill, as in the engine, even when it empties the order
+                self.advance_seq();
+                if filled {
+                    self.detach_order(key);
+                    self.record_filled(order_id);
+                }
+                
+                // Trades are logged in ID order, so the last one seen sets the counter
+                let next_trade_id = self.peek_next_trade_id().max(after_trade);
+                self.restore_id_counters(self.peek_next_order_id(), next_trade_id);
+            }
+            BookEvent::Removed { order_id, .. } => {
+                if self.cancel_order(order_id).is_none() {
+                    return false;
+                }
+            }
+            BookEvent::Decayed { now, max_age, .. } => {
+                self.decay_queues(now, max_age);
+            }
+        }
+        self.restore_seq(event.seq());
+        true
+    }
+}
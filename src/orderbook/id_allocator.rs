@@ -0,0 +1,159 @@
+//! Order ID allocation within reserved ranges.
+//!
+//! A book auto-assigns IDs to orders submitted with ID 0. When several
+//! gateways each run a book (or feed one sequencer) their auto-assigned
+//! IDs must never collide, so each is given a disjoint half-open range
+//! `[start, end)` of the ID space and allocates from it in order. Within
+//! a range allocation is a plain counter, so it stays deterministic.
+//!
+//! ## Example
+//!
+//! ```
+//! use dark_hypercore::orderbook::IdAllocator;
+//!
+//! // Gateway 2 of 4 gets the third quarter of the ID space
+//! let mut ids = IdAllocator::partitioned(2, 4);
+//! let first = ids.allocate().unwrap();
+//!
+//! assert_eq!(first, ids.start());
+//! assert!(!IdAllocator::partitioned(1, 4).contains(first));
+//! ```
+
+/// Sequential allocator over a reserved range of order IDs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdAllocator {
+    /// First ID of the range
+    start: u64,
+    
+    /// One past the last ID of the range
+    end: u64,
+    
+    /// Next ID to hand out (`end` once exhausted)
+    next: u64,
+}
+
+impl Default for IdAllocator {
+    /// The whole ID space, `[1, u64::MAX)`
+    fn default() -> Self {
+        Self::new(1, u64::MAX)
+    }
+}
+
+impl IdAllocator {
+    /// Create an allocator over `[start, end)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is empty or includes 0, which marks an order
+    /// for auto-assignment and can never be allocated.
+    pub fn new(start: u64, end: u64) -> Self {
+        assert!(start > 0 && start < end, "invalid order ID range {}..{}", start, end);
+        Self { start, end, next: start }
+    }
+    
+    /// Create the allocator for slice `index` of `parts` equal slices of the ID space
+    ///
+    /// Slices are disjoint and cover `[1, u64::MAX)`; the last one takes
+    /// any rounding remainder. The same `(index, parts)` always gives the
+    /// same range.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `index >= parts`.
+    pub fn partitioned(index: u64, parts: u64) -> Self {
+        assert!(index < parts, "partition {} out of {}", index, parts);
+        let width = (u64::MAX - 1) / parts;
+        let start = 1 + index * width;
+        let end = if index == parts - 1 { u64::MAX } else { start + width };
+        Self::new(start, end)
+    }
+    
+    /// Take the next ID, or None once the range is exhausted
+    #[inline]
+    pub fn allocate(&mut self) -> Option<u64> {
+        if self.next >= self.end {
+            return None;
+        }
+        let id = self.next;
+        self.next += 1;
+        Some(id)
+    }
+    
+    /// Get the next ID without taking it (`end` once exhausted)
+    #[inline]
+    pub fn peek(&self) -> u64 {
+        self.next
+    }
+    
+    /// Get the first ID of the range
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+    
+    /// Get one past the last ID of the range
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+    
+    /// Check whether an ID falls inside the range
+    #[inline]
+    pub fn contains(&self, id: u64) -> bool {
+        (self.start..self.end).contains(&id)
+    }
+    
+    /// Get the number of IDs left to allocate
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.end.saturating_sub(self.next)
+    }
+    
+    /// Check whether every ID in the range has been handed out
+    #[inline]
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining() == 0
+    }
+    
+    /// Move the counter (used when loading a snapshot)
+    #[inline]
+    pub(crate) fn restore(&mut self, next: u64) {
+        self.next = next;
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_partitions_are_disjoint_and_cover_the_space() {
+        let parts: Vec<_> = (0..3).map(|i| IdAllocator::partitioned(i, 3)).collect();
+        assert_eq!(parts[0].start(), 1);
+        assert_eq!(parts[2].end(), u64::MAX);
+        for pair in parts.windows(2) {
+            assert_eq!(pair[0].end(), pair[1].start());
+        }
+        assert_eq!(IdAllocator::partitioned(0, 1), IdAllocator::default());
+    }
+    
+    #[test]
+    fn test_allocate_until_exhausted() {
+        let mut ids = IdAllocator::new(10, 12);
+        assert_eq!(ids.allocate(), Some(10));
+        assert_eq!(ids.allocate(), Some(11));
+        assert!(ids.is_exhausted());
+        assert_eq!(ids.allocate(), None);
+        assert_eq!(ids.peek(), 12);
+    }
+    
+    #[test]
+    #[should_panic(expected = "invalid order ID range")]
+    fn test_range_cannot_include_zero() {
+        IdAllocator::new(0, 10);
+    }
+}
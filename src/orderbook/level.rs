@@ -3,27 +3,98 @@
 //! ## Design
 //!
 //! A `PriceLevel` represents all orders at a single price point.
-//! Orders are maintained in a doubly-linked list for FIFO ordering
-//! (price-time priority).
+//! Orders are maintained in a doubly-linked list in matching order: FIFO
+//! (price-time priority) by default, or LIFO on books configured with
+//! [`LevelOrdering::Lifo`](crate::orderbook::LevelOrdering::Lifo).
 //!
 //! ## Queue Structure
 //!
 //! ```text
-//! head (oldest) <-> order2 <-> order3 <-> tail (newest)
+//! head (next to match) <-> order2 <-> order3 <-> tail (last to match)
 //! ```
 //!
-//! - New orders are appended at the tail
+//! - New orders are appended at the tail (FIFO, the oldest order is at the
+//!   head) or pushed at the head (LIFO, the newest order is at the head)
 //! - Matching consumes orders from the head
 //! - Any order can be removed in O(1) using the slab key
 
 use slab::Slab;
 use crate::orderbook::OrderNode;
 
+/// Aggregate view of a single price level.
+///
+/// This is the stable, public market-data surface for a level. It carries
+/// no internal slab keys, so consumers are decoupled from the linked-list
+/// layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct LevelSummary {
+    /// Price for this level (fixed-point, scaled by 10^8)
+    pub price: u64,
+    
+    /// Total remaining quantity at this level (fixed-point)
+    pub total_quantity: u64,
+    
+    /// Number of orders at this level
+    pub order_count: usize,
+}
+
+/// Best bid and ask with their aggregate sizes, read in one call.
+///
+/// Each side is `None` when that side of the book is empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TopOfBook {
+    /// Best bid price (fixed-point)
+    pub bid_price: Option<u64>,
+    
+    /// Total remaining quantity at the best bid (fixed-point)
+    pub bid_size: Option<u64>,
+    
+    /// Best ask price (fixed-point)
+    pub ask_price: Option<u64>,
+    
+    /// Total remaining quantity at the best ask (fixed-point)
+    pub ask_size: Option<u64>,
+}
+
+/// Queue and market state around one resting order, read in one call.
+///
+/// These are the raw inputs of a fill-probability model. The book has no
+/// tick size, so the distance from the touch is a price difference; divide
+/// by the market's tick with [`OrderContext::ticks_from_touch`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct OrderContext {
+    /// Number of orders ahead of this one at its level
+    pub queue_position: usize,
+    
+    /// Total remaining quantity of those orders (fixed-point)
+    pub quantity_ahead: u64,
+    
+    /// Distance from the best price on the order's side (fixed-point, 0 at the touch)
+    pub touch_distance: u64,
+    
+    /// Best bid and ask at the time of the call
+    pub bbo: TopOfBook,
+}
+
+impl OrderContext {
+    /// Get the distance from the touch in ticks, rounded down
+    ///
+    /// # Returns
+    ///
+    /// The tick count, or None if `tick_size` is 0
+    pub fn ticks_from_touch(&self, tick_size: u64) -> Option<u64> {
+        self.touch_distance.checked_div(tick_size)
+    }
+}
+
 /// A price level containing orders at a single price.
 ///
-/// Orders are stored in a FIFO queue (doubly-linked list).
-/// The actual order data lives in the slab; this struct only
-/// holds the queue metadata.
+/// Orders are stored in a queue (doubly-linked list) in matching order,
+/// FIFO unless the book uses LIFO. The actual order data lives in the
+/// slab; this struct only holds the queue metadata.
 #[derive(Debug, Clone)]
 pub struct PriceLevel {
     /// Price for this level (fixed-point, scaled by 10^8)
@@ -67,9 +138,19 @@ impl PriceLevel {
         self.order_count == 0
     }
     
+    /// Get an aggregate summary of this level
+    #[inline]
+    pub fn summary(&self) -> LevelSummary {
+        LevelSummary {
+            price: self.price,
+            total_quantity: self.total_quantity,
+            order_count: self.order_count,
+        }
+    }
+    
     /// Add an order to the tail of the queue
     ///
-    /// This maintains FIFO ordering - oldest orders are matched first.
+    /// Used by FIFO books, where the oldest orders are matched first.
     ///
     /// # Arguments
     ///
@@ -101,6 +182,41 @@ impl PriceLevel {
         self.total_quantity = self.total_quantity.saturating_add(quantity);
     }
     
+    /// Add an order to the head of the queue
+    ///
+    /// The order becomes the next to match (LIFO). Only used by books
+    /// configured with [`LevelOrdering::Lifo`](crate::orderbook::LevelOrdering::Lifo).
+    ///
+    /// # Arguments
+    ///
+    /// * `key` - The slab key for the order node
+    /// * `slab` - The slab containing all order nodes
+    ///
+    /// # Panics
+    ///
+    /// Panics if the key doesn't exist in the slab
+    pub fn push_front(&mut self, key: usize, slab: &mut Slab<OrderNode>) {
+        let node = slab.get_mut(key).expect("Invalid slab key");
+        let quantity = node.remaining();
+        
+        // Update linked list pointers
+        node.prev = None;
+        node.next = self.head;
+        
+        if let Some(head_key) = self.head {
+            // Link the old head back to the new node
+            let head_node = slab.get_mut(head_key).expect("Invalid head key");
+            head_node.prev = Some(key);
+        } else {
+            // Empty list - this is also the tail
+            self.tail = Some(key);
+        }
+        
+        self.head = Some(key);
+        self.order_count += 1;
+        self.total_quantity = self.total_quantity.saturating_add(quantity);
+    }
+    
     /// Remove an order from the queue by slab key
     ///
     /// # Arguments
@@ -140,7 +256,7 @@ impl PriceLevel {
         node.prev = None;
         node.next = None;
         
-        self.order_count -= 1;
+        decrement_count(&mut self.order_count, 1, "level order");
         self.total_quantity = self.total_quantity.saturating_sub(quantity);
         
         quantity
@@ -164,6 +280,17 @@ impl PriceLevel {
     }
 }
 
+/// Subtract `by` from a book counter, saturating at zero
+///
+/// A counter going below zero means the book's bookkeeping has desynced.
+/// Debug builds fail an assertion so tests catch it; release builds clamp
+/// at zero and keep running, leaving `CLOB::verify_invariants` to report it.
+#[inline]
+pub(crate) fn decrement_count(count: &mut usize, by: usize, counter: &str) {
+    debug_assert!(*count >= by, "{} count underflow: {} - {}", counter, *count, by);
+    *count = count.saturating_sub(by);
+}
+
 // ============================================================================
 // Unit Tests
 // ============================================================================
@@ -242,6 +369,41 @@ mod tests {
         assert!(node3.next.is_none());
     }
     
+    #[test]
+    fn test_price_level_push_front() {
+        let mut slab = Slab::with_capacity(10);
+        let mut level = PriceLevel::new(5_000_000_000_000);
+        
+        let key1 = create_test_node(&mut slab, 1, 100_000_000);
+        let key2 = create_test_node(&mut slab, 2, 200_000_000);
+        let key3 = create_test_node(&mut slab, 3, 300_000_000);
+        
+        level.push_front(key1, &mut slab);
+        level.push_front(key2, &mut slab);
+        level.push_front(key3, &mut slab);
+        
+        // Newest at the head: key3 <-> key2 <-> key1
+        assert_eq!(level.order_count, 3);
+        assert_eq!(level.total_quantity, 600_000_000);
+        assert_eq!(level.head, Some(key3));
+        assert_eq!(level.tail, Some(key1));
+        assert_eq!(slab.get(key3).unwrap().next, Some(key2));
+        assert_eq!(slab.get(key2).unwrap().prev, Some(key3));
+        assert_eq!(slab.get(key2).unwrap().next, Some(key1));
+        assert_eq!(slab.get(key1).unwrap().prev, Some(key2));
+        
+        // Removing the head and the tail relinks from both ends
+        level.remove(key3, &mut slab);
+        assert_eq!(level.head, Some(key2));
+        assert!(slab.get(key2).unwrap().prev.is_none());
+        
+        level.remove(key1, &mut slab);
+        assert_eq!(level.head, Some(key2));
+        assert_eq!(level.tail, Some(key2));
+        assert!(slab.get(key2).unwrap().next.is_none());
+        assert_eq!(level.total_quantity, 200_000_000);
+    }
+    
     #[test]
     fn test_price_level_remove_middle() {
         let mut slab = Slab::with_capacity(10);
@@ -347,6 +509,29 @@ mod tests {
         assert_eq!(level.total_quantity, 0);
     }
     
+    #[test]
+    fn test_price_level_summary() {
+        let mut slab = Slab::with_capacity(10);
+        let mut level = PriceLevel::new(5_000_000_000_000);
+        
+        let key1 = create_test_node(&mut slab, 1, 100_000_000);
+        let key2 = create_test_node(&mut slab, 2, 200_000_000);
+        let key3 = create_test_node(&mut slab, 3, 300_000_000);
+        level.push_back(key1, &mut slab);
+        level.push_back(key2, &mut slab);
+        level.push_back(key3, &mut slab);
+        level.remove(key2, &mut slab);
+        
+        let summary = level.summary();
+        assert_eq!(summary, LevelSummary {
+            price: 5_000_000_000_000,
+            total_quantity: 400_000_000,
+            order_count: 2,
+        });
+        assert_eq!(summary.total_quantity, level.total_quantity);
+        assert_eq!(summary.order_count, level.order_count);
+    }
+    
     #[test]
     fn test_price_level_peek_head() {
         let mut slab = Slab::with_capacity(10);
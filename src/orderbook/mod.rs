@@ -6,13 +6,23 @@
 //!
 //! - **Slab-based storage**: O(1) order insertion, removal, and lookup
 //! - **Price levels**: Orders grouped by price using BTreeMap
-//! - **Price-time priority**: FIFO ordering at each price level
+//! - **Price-time priority**: FIFO ordering at each price level by default
+//!   (LIFO is available for research, see [`LevelOrdering`])
 //!
 //! ## Components
 //!
 //! - [`OrderNode`]: Wrapper around `Order` with linked-list pointers for price level
 //! - [`PriceLevel`]: Collection of orders at a single price point
+//! - [`LevelSummary`]: Public aggregate view of a price level
+//! - [`TopOfBook`]: Best bid/ask with aggregate sizes (BBO quote)
+//! - [`OrderContext`]: Queue position, quantity ahead and BBO around a resting order
 //! - [`CLOB`]: Main order book with bid/ask sides
+//! - [`OrderBookView`]: Read-only borrow of a book for market-data consumers
+//! - [`ClobPool`]: Recycles cleared books across independent batches
+//! - [`IdAllocator`]: Reserved order ID ranges for multi-gateway deployments
+//! - [`BookEvent`]: Logged book changes for rebuilding read replicas
+//! - [`snapshot`]: Binary snapshots for state sync ([`SnapshotError`] on bad input)
+//! - [`scenario`]: Plain-text scenario files for seeding known books
 //!
 //! ## Performance
 //!
@@ -44,8 +54,20 @@
 pub mod node;
 pub mod level;
 pub mod clob;
+pub mod snapshot;
+pub mod scenario;
+pub mod view;
+pub mod pool;
+pub mod id_allocator;
+pub mod event;
 
 pub use node::OrderNode;
-pub use level::PriceLevel;
-pub use clob::CLOB;
+pub use level::{LevelSummary, OrderContext, PriceLevel, TopOfBook};
+pub use clob::{CancelOutcome, CLOB, InvariantViolation, LevelOrdering, PriceOrder};
+pub use snapshot::SnapshotError;
+pub use scenario::ScenarioError;
+pub use pool::ClobPool;
+pub use id_allocator::IdAllocator;
+pub use event::BookEvent;
+pub use view::OrderBookView;
 
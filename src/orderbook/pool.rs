@@ -0,0 +1,132 @@
+//! Reusable order books for batch processing.
+//!
+//! A server that runs many independent batches (per-block simulation,
+//! replays) would otherwise pay for a fresh `CLOB::with_capacity` slab and
+//! index on every batch. [`ClobPool`] hands out books and takes them back
+//! reset but with their allocations intact, so steady-state batches
+//! allocate nothing up front.
+//!
+//! ## Example
+//!
+//! ```
+//! use dark_hypercore::engine::MatchingEngine;
+//! use dark_hypercore::orderbook::ClobPool;
+//! use dark_hypercore::types::{Order, Side};
+//!
+//! let mut pool = ClobPool::new(10_000);
+//!
+//! for block in 0..3 {
+//!     let mut clob = pool.acquire();
+//!     let mut engine = MatchingEngine::new();
+//!     engine.match_order(&mut clob, Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, block), 0);
+//!     assert_eq!(clob.order_count(), 1);
+//!     pool.release(clob);
+//! }
+//!
+//! assert_eq!(pool.len(), 1);
+//! ```
+
+use crate::orderbook::CLOB;
+
+/// Pool of recycled order books.
+#[derive(Debug, Default)]
+pub struct ClobPool {
+    /// Order capacity of books the pool creates
+    order_capacity: usize,
+    
+    /// Books ready to hand out, already reset
+    free: Vec<CLOB>,
+}
+
+impl ClobPool {
+    /// Create an empty pool whose new books pre-allocate `order_capacity`
+    pub fn new(order_capacity: usize) -> Self {
+        Self {
+            order_capacity,
+            free: Vec::new(),
+        }
+    }
+    
+    /// Take a book from the pool, creating one if none is free
+    ///
+    /// The book is indistinguishable from `CLOB::with_capacity`: empty,
+    /// with ID counters and sequence at their start and default settings.
+    /// Builder settings can be applied as usual.
+    pub fn acquire(&mut self) -> CLOB {
+        self.free
+            .pop()
+            .unwrap_or_else(|| CLOB::with_capacity(self.order_capacity))
+    }
+    
+    /// Return a book to the pool
+    ///
+    /// The book is reset to a fresh state but keeps its slab and index
+    /// capacity, including any growth from the batch it served.
+    pub fn release(&mut self, mut clob: CLOB) {
+        clob.reset();
+        self.free.push(clob);
+    }
+    
+    /// Number of books waiting in the pool
+    pub fn len(&self) -> usize {
+        self.free.len()
+    }
+    
+    /// Check if the pool has no books waiting
+    pub fn is_empty(&self) -> bool {
+        self.free.is_empty()
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{MatchResult, MatchingEngine};
+    use crate::orderbook::CancelOutcome;
+    use crate::types::{Order, Side};
+    
+    fn run_batch(clob: &mut CLOB) -> Vec<MatchResult> {
+        let mut engine = MatchingEngine::new();
+        let orders = [
+            Order::new(1, 100, Side::Sell, 5_010_000_000_000, 200_000_000, 1),
+            Order::new(2, 101, Side::Sell, 5_020_000_000_000, 100_000_000, 2),
+            Order::new(3, 102, Side::Buy, 4_990_000_000_000, 150_000_000, 3),
+            Order::new(4, 103, Side::Buy, 5_020_000_000_000, 250_000_000, 4),
+        ];
+        orders
+            .into_iter()
+            .map(|order| engine.match_order(clob, order, 0))
+            .collect()
+    }
+    
+    #[test]
+    fn test_recycled_book_matches_fresh_book() {
+        let mut pool = ClobPool::new(100);
+        
+        // Dirty a book with non-default settings, then recycle it
+        let mut used = pool.acquire().with_level_retention(true).with_fill_tracking(8);
+        run_batch(&mut used);
+        used.cancel_order(3);
+        let capacity = used.capacity();
+        pool.release(used);
+        assert_eq!(pool.len(), 1);
+        
+        let mut recycled = pool.acquire();
+        assert!(pool.is_empty());
+        assert!(recycled.capacity() >= capacity);
+        
+        let mut fresh = CLOB::with_capacity(100);
+        assert_eq!(run_batch(&mut recycled), run_batch(&mut fresh));
+        assert_eq!(recycled.compute_state_root(), fresh.compute_state_root());
+        assert_eq!(recycled.current_seq(), fresh.current_seq());
+        assert_eq!(recycled.peek_next_order_id(), fresh.peek_next_order_id());
+        assert_eq!(recycled.next_trade_id(), fresh.next_trade_id());
+        assert_eq!(recycled.fill_tracking(), 0);
+        assert_eq!(recycled.cancel_order_detailed(1), CancelOutcome::NotFound);
+        recycled.verify_invariants().unwrap();
+    }
+}
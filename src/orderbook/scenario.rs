@@ -0,0 +1,208 @@
+//! Plain-text scenario files for seeding a known order book.
+//!
+//! ## Format
+//!
+//! One resting limit order per line:
+//!
+//! ```text
+//! # side  price     quantity  user
+//! buy     49999.50  1.5       100
+//! sell    50000.50  2         200
+//! ```
+//!
+//! - Fields are separated by whitespace
+//! - `side` is `buy` or `sell` (case-insensitive)
+//! - `price` and `quantity` are decimals with at most 8 decimal places,
+//!   parsed with [`to_fixed_strict`] and [`quantity_to_fixed`]; an
+//!   over-precise quantity can be floored instead with
+//!   [`CLOB::from_scenario_with_precision`]
+//! - `user` is an unsigned integer user ID
+//! - Blank lines and `#` comments are ignored
+//!
+//! Orders get IDs 1, 2, 3, ... and timestamps equal to their ID, in file
+//! order, so the same file always produces the same book and state root.
+//! Scenarios seed a book directly without matching, so a line that would
+//! cross the opposite side is rejected.
+
+use crate::orderbook::CLOB;
+use crate::types::price::{quantity_to_fixed, to_fixed_strict, PrecisionPolicy};
+use crate::types::{Order, Side};
+
+/// Error loading a scenario. Line numbers are 1-based.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ScenarioError {
+    /// A required field is absent
+    #[error("line {line}: missing {field}")]
+    MissingField { line: usize, field: &'static str },
+    
+    /// The line has more than four fields
+    #[error("line {line}: unexpected trailing field {value:?}")]
+    TrailingField { line: usize, value: String },
+    
+    /// Side is not `buy` or `sell`
+    #[error("line {line}: invalid side {value:?}")]
+    BadSide { line: usize, value: String },
+    
+    /// Price is not a positive decimal with at most 8 places
+    #[error("line {line}: invalid price {value:?}")]
+    BadPrice { line: usize, value: String },
+    
+    /// Quantity is not a positive decimal (with at most 8 places unless floored)
+    #[error("line {line}: invalid quantity {value:?}")]
+    BadQuantity { line: usize, value: String },
+    
+    /// User is not an unsigned integer
+    #[error("line {line}: invalid user {value:?}")]
+    BadUser { line: usize, value: String },
+    
+    /// The order would cross the opposite side of the book
+    #[error("line {line}: order crosses the opposite side")]
+    Crossed { line: usize },
+}
+
+impl CLOB {
+    /// Build a book from a scenario (see the [module docs](self) for the format)
+    ///
+    /// # Arguments
+    ///
+    /// * `scenario` - Scenario text, one order per line
+    ///
+    /// # Returns
+    ///
+    /// The seeded book, or the first [`ScenarioError`] encountered
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    ///
+    /// let clob = CLOB::from_scenario("
+    ///     buy  49999.5 1 100
+    ///     sell 50000.5 2 200
+    /// ").unwrap();
+    ///
+    /// assert_eq!(clob.best_bid(), Some(4_999_950_000_000));
+    /// assert_eq!(clob.best_ask(), Some(5_000_050_000_000));
+    /// ```
+    pub fn from_scenario(scenario: &str) -> Result<CLOB, ScenarioError> {
+        Self::from_scenario_with_precision(scenario, PrecisionPolicy::Reject)
+    }
+    
+    /// Build a book from a scenario, choosing how over-precise quantities are handled
+    ///
+    /// Prices are always parsed strictly; `quantity_policy` decides whether
+    /// a quantity with more than 8 decimal places is rejected or floored.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::price::PrecisionPolicy;
+    ///
+    /// let scenario = "buy 100 1.123456789 7";
+    /// assert!(CLOB::from_scenario(scenario).is_err());
+    ///
+    /// let clob = CLOB::from_scenario_with_precision(scenario, PrecisionPolicy::Floor).unwrap();
+    /// assert_eq!(clob.best_bid_level().unwrap().total_quantity, 112_345_678);
+    /// ```
+    pub fn from_scenario_with_precision(
+        scenario: &str,
+        quantity_policy: PrecisionPolicy,
+    ) -> Result<CLOB, ScenarioError> {
+        let mut clob = CLOB::new();
+        
+        for (index, raw) in scenario.lines().enumerate() {
+            let line = index + 1;
+            let content = raw.split('#').next().unwrap_or("").trim();
+            if content.is_empty() {
+                continue;
+            }
+            
+            let mut fields = content.split_whitespace();
+            let mut next = |field| fields.next().ok_or(ScenarioError::MissingField { line, field });
+            let (side, price, quantity, user) = (next("side")?, next("price")?, next("quantity")?, next("user")?);
+            if let Some(extra) = fields.next() {
+                return Err(ScenarioError::TrailingField { line, value: extra.to_string() });
+            }
+            
+            let side = match side.to_ascii_lowercase().as_str() {
+                "buy" => Side::Buy,
+                "sell" => Side::Sell,
+                _ => return Err(ScenarioError::BadSide { line, value: side.to_string() }),
+            };
+            let price = to_fixed_strict(price)
+                .filter(|&p| p > 0)
+                .ok_or_else(|| ScenarioError::BadPrice { line, value: price.to_string() })?;
+            let quantity = quantity_to_fixed(quantity, quantity_policy)
+                .ok_or_else(|| ScenarioError::BadQuantity { line, value: quantity.to_string() })?;
+            let user = user
+                .parse::<u64>()
+                .map_err(|_| ScenarioError::BadUser { line, value: user.to_string() })?;
+            
+            let opposite = match side {
+                Side::Buy => clob.best_ask(),
+                Side::Sell => clob.best_bid(),
+            };
+            if opposite.is_some_and(|best| clob.price_order().crosses(side, price, best)) {
+                return Err(ScenarioError::Crossed { line });
+            }
+            
+            let id = clob.peek_next_order_id();
+            clob.add_order(Order::new(0, user, side, price, quantity, id));
+        }
+        
+        Ok(clob)
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_two_order_scenario() {
+        let clob = CLOB::from_scenario(
+            "# demo book\n\
+             BUY  50000.00 1.5 100\n\
+             \n\
+             sell 50001.25 0.25 200  # tight ask\n",
+        )
+        .unwrap();
+        
+        assert_eq!(clob.best_bid(), Some(5_000_000_000_000));
+        assert_eq!(clob.best_ask(), Some(5_000_125_000_000));
+        assert_eq!(clob.order_count(), 2);
+        
+        let bid = clob.get_order(clob.get_key(1).unwrap()).unwrap();
+        assert_eq!((bid.user_id, bid.quantity, bid.timestamp), (100, 150_000_000, 1));
+        let ask = clob.get_order(clob.get_key(2).unwrap()).unwrap();
+        assert_eq!((ask.user_id, ask.quantity, ask.timestamp), (200, 25_000_000, 2));
+        
+        // Same text, same book
+        let again = CLOB::from_scenario("buy 50000 1.5 100\nsell 50001.25 0.25 200").unwrap();
+        assert_eq!(again.compute_state_root(), clob.compute_state_root());
+    }
+    
+    #[test]
+    fn test_scenario_errors() {
+        let cases = [
+            ("buy 1 1", ScenarioError::MissingField { line: 1, field: "user" }),
+            ("buy 1 1 7 extra", ScenarioError::TrailingField { line: 1, value: "extra".into() }),
+            ("\nhold 1 1 7", ScenarioError::BadSide { line: 2, value: "hold".into() }),
+            ("buy -1 1 7", ScenarioError::BadPrice { line: 1, value: "-1".into() }),
+            ("buy 1 0 7", ScenarioError::BadQuantity { line: 1, value: "0".into() }),
+            ("buy 1 1.123456789 7", ScenarioError::BadQuantity { line: 1, value: "1.123456789".into() }),
+            ("buy 1.000000001 1 7", ScenarioError::BadPrice { line: 1, value: "1.000000001".into() }),
+            ("buy 1 1 bob", ScenarioError::BadUser { line: 1, value: "bob".into() }),
+            ("sell 10 1 7\nbuy 10 1 8", ScenarioError::Crossed { line: 2 }),
+        ];
+        
+        for (scenario, expected) in cases {
+            assert_eq!(CLOB::from_scenario(scenario).unwrap_err(), expected, "{:?}", scenario);
+        }
+    }
+}
@@ -0,0 +1,765 @@
+//! Binary snapshots of the order book for state sync.
+//!
+//! ## Layout
+//!
+//! All integers are little-endian. Orders use their SSZ encoding.
+//!
+//! ```text
+//! header:  version u8 | price_order u8 | id_range_start u64 | id_range_end u64
+//!          | next_order_id u64 | next_trade_id u64 | seq u64
+//!          | level_count u64 | order_count u64
+//! level:   side u8 | price u64 | order_count u64 | total_quantity u64
+//!          | head u64 | tail u64
+//! node:    key u64 | prev u64 | next u64 | order (SSZ)
+//! ```
+//!
+//! Levels are written bids first (best to worst), then asks, each followed
+//! by its nodes from head to tail. Keys and links are the writer's slab
+//! layout, with `u64::MAX` for "no link". Retained empty levels are skipped.
+//!
+//! The ID range is the book's reserved auto-assignment range (see
+//! [`CLOB::with_id_range`]); `next_order_id` must fall inside it, or equal
+//! its end once the range is exhausted. `seq` is the book's mutation
+//! sequence number (see [`CLOB::current_seq`]), so a replica loaded from a
+//! snapshot can pick up the [`BookEvent`](crate::orderbook::BookEvent)
+//! feed where the snapshot left off.
+//!
+//! The reader rebuilds each level from record order, but still checks that
+//! the links describe that same list: `head` is the first key and `tail` the
+//! last, each node's `prev`/`next` name its neighbours, and no key appears
+//! twice. A snapshot whose links disagree came from a corrupt book.
+//!
+//! ## Level Patches
+//!
+//! [`CLOB::serialize_level`] encodes a single level so a replica can be
+//! patched with [`CLOB::apply_level`] instead of reloading the whole book:
+//!
+//! ```text
+//! header:  version u8 | side u8 | price u64 | order_count u64
+//! order:   order (SSZ), head to tail
+//! ```
+//!
+//! ## Untrusted Input
+//!
+//! Snapshots may arrive from other nodes, so [`CLOB::from_ssz_bytes`] and
+//! [`CLOB::apply_level`] never panic on malformed bytes. They return a
+//! [`SnapshotError`] instead.
+
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+use crate::orderbook::{PriceLevel, PriceOrder, CLOB};
+use crate::types::{Order, OrderType, Side};
+
+/// Current snapshot format version
+pub const SNAPSHOT_VERSION: u8 = 5;
+
+/// Encoded size of the snapshot header
+pub const HEADER_LEN: usize = 1 + 1 + 8 + 8 + 8 + 8 + 8 + 8 + 8;
+
+/// Encoded size of a level header
+pub const LEVEL_HEADER_LEN: usize = 1 + 8 + 8 + 8 + 8 + 8;
+
+/// SSZ size of an `Order` (fixed-size container)
+pub const ORDER_SSZ_LEN: usize = 8 + 8 + 1 + 8 + 8 + 8 + 8 + 1 + 8 + 16 + 16;
+
+/// Encoded size of a node record
+pub const NODE_RECORD_LEN: usize = 8 + 8 + 8 + ORDER_SSZ_LEN;
+
+/// Encoded size of a level patch header
+pub const LEVEL_PATCH_HEADER_LEN: usize = 1 + 1 + 8 + 8;
+
+/// Link value meaning "no node"
+const NO_LINK: u64 = u64::MAX;
+
+/// Error loading an order book snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SnapshotError {
+    /// Input is shorter than the fixed header
+    #[error("snapshot header truncated: need {expected} bytes, got {actual}")]
+    TruncatedHeader { expected: usize, actual: usize },
+    
+    /// Header carries a version this build cannot read
+    #[error("unsupported snapshot version {0}")]
+    UnsupportedVersion(u8),
+    
+    /// Header carries an unknown price order tag
+    #[error("invalid price order tag {0}")]
+    BadPriceOrder(u8),
+    
+    /// Header's order ID range is empty, includes 0, or does not hold the next ID
+    #[error("invalid order ID range {start}..{end} with next ID {next}")]
+    BadIdRange { start: u64, end: u64, next: u64 },
+    
+    /// Level header at this index is truncated or invalid
+    #[error("level record {0} is truncated or malformed")]
+    BadLevelRecord(usize),
+    
+    /// Order record at this index (across all levels) is truncated or invalid
+    #[error("order record {0} is truncated or malformed")]
+    BadOrderRecord(usize),
+    
+    /// Two records carry the same order ID
+    #[error("duplicate order ID {0}")]
+    DuplicateOrderId(u64),
+    
+    /// Two level headers describe the same price
+    #[error("duplicate {side:?} level at price {price}")]
+    DuplicateLevel { side: Side, price: u64 },
+    
+    /// A level's head/tail or node links do not form a simple list
+    #[error("level record {level} has corrupt links: {reason}")]
+    CorruptLevel { level: usize, reason: &'static str },
+    
+    /// Declared counts or totals disagree with the records
+    #[error("count mismatch: {0}")]
+    CountMismatch(String),
+    
+    /// Bytes remain after the last declared level
+    #[error("{0} trailing bytes after snapshot")]
+    TrailingBytes(usize),
+    
+    /// A level patch holds more orders than the book's per-level cap
+    #[error("level patch has {count} orders, above the cap of {cap}")]
+    LevelOverCap { count: usize, cap: usize },
+}
+
+impl CLOB {
+    /// Serialize the book into a snapshot
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    ///
+    /// let bytes = clob.to_ssz_bytes();
+    /// let restored = CLOB::from_ssz_bytes(&bytes).unwrap();
+    ///
+    /// assert_eq!(restored.compute_state_root(), clob.compute_state_root());
+    /// ```
+    pub fn to_ssz_bytes(&self) -> Vec<u8> {
+        let levels: Vec<(Side, &PriceLevel)> = self
+            .bids()
+            .values()
+            .map(|level| (Side::Buy, level))
+            .chain(self.asks().values().map(|level| (Side::Sell, level)))
+            .filter(|(_, level)| !level.is_empty())
+            .collect();
+        
+        let mut out = Vec::with_capacity(
+            HEADER_LEN + levels.len() * LEVEL_HEADER_LEN + self.order_count() * NODE_RECORD_LEN,
+        );
+        
+        out.push(SNAPSHOT_VERSION);
+        out.push(price_order_tag(self.price_order()));
+        out.extend_from_slice(&self.order_ids().start().to_le_bytes());
+        out.extend_from_slice(&self.order_ids().end().to_le_bytes());
+        out.extend_from_slice(&self.peek_next_order_id().to_le_bytes());
+        out.extend_from_slice(&self.peek_next_trade_id().to_le_bytes());
+        out.extend_from_slice(&self.current_seq().to_le_bytes());
+        out.extend_from_slice(&(levels.len() as u64).to_le_bytes());
+        out.extend_from_slice(&(self.order_count() as u64).to_le_bytes());
+        
+        for (side, level) in levels {
+            out.push(side.to_u8());
+            out.extend_from_slice(&level.price.to_le_bytes());
+            out.extend_from_slice(&(level.order_count as u64).to_le_bytes());
+            out.extend_from_slice(&level.total_quantity.to_le_bytes());
+            out.extend_from_slice(&encode_link(level.head).to_le_bytes());
+            out.extend_from_slice(&encode_link(level.tail).to_le_bytes());
+            
+            let mut current = level.head;
+            while let Some(key) = current {
+                let node = match self.orders().get(key) {
+                    Some(n) => n,
+                    None => break,
+                };
+                out.extend_from_slice(&(key as u64).to_le_bytes());
+                out.extend_from_slice(&encode_link(node.prev).to_le_bytes());
+                out.extend_from_slice(&encode_link(node.next).to_le_bytes());
+                let order = ssz_rs::serialize(&node.order).expect("fixed-size Order always serializes");
+                out.extend_from_slice(&order);
+                current = node.next;
+            }
+        }
+        
+        out
+    }
+    
+    /// Load a book from a snapshot produced by [`CLOB::to_ssz_bytes`]
+    ///
+    /// Each level's records are re-inserted head to tail, so time priority
+    /// is preserved. Slab keys in the rebuilt book may differ from the
+    /// writer's.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`] if the bytes are truncated, the order ID
+    /// range is invalid or does not hold the next ID, an order
+    /// record is invalid or does not belong to its level, an order ID or
+    /// level repeats, a level's links are inconsistent or cyclic, or
+    /// declared counts disagree with the records.
+    pub fn from_ssz_bytes(bytes: &[u8]) -> Result<CLOB, SnapshotError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(SnapshotError::TruncatedHeader {
+                expected: HEADER_LEN,
+                actual: bytes.len(),
+            });
+        }
+        
+        let mut reader = Reader::new(bytes);
+        let header_err = || SnapshotError::TruncatedHeader {
+            expected: HEADER_LEN,
+            actual: bytes.len(),
+        };
+        
+        let version = reader.u8().ok_or_else(header_err)?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let price_order_raw = reader.u8().ok_or_else(header_err)?;
+        let price_order = price_order_from_tag(price_order_raw)
+            .ok_or(SnapshotError::BadPriceOrder(price_order_raw))?;
+        let id_start = reader.u64().ok_or_else(header_err)?;
+        let id_end = reader.u64().ok_or_else(header_err)?;
+        let next_order_id = reader.u64().ok_or_else(header_err)?;
+        let next_trade_id = reader.u64().ok_or_else(header_err)?;
+        let seq = reader.u64().ok_or_else(header_err)?;
+        let level_count = reader.u64().ok_or_else(header_err)?;
+        let order_count = reader.u64().ok_or_else(header_err)?;
+        
+        // A counter outside the range would hand out another range's IDs
+        if id_start == 0 || id_start >= id_end || !(id_start..=id_end).contains(&next_order_id) {
+            return Err(SnapshotError::BadIdRange { start: id_start, end: id_end, next: next_order_id });
+        }
+        
+        // Never trust declared counts for allocation
+        let capacity = (order_count as usize).min(bytes.len() / NODE_RECORD_LEN);
+        let mut clob = CLOB::with_capacity(capacity)
+            .with_price_order(price_order)
+            .with_id_range(id_start, id_end);
+        
+        let mut seen_ids: HashSet<u64> = HashSet::with_capacity(capacity);
+        let mut seen_levels: HashSet<(Side, u64)> = HashSet::new();
+        let mut seen_keys: HashSet<u64> = HashSet::with_capacity(capacity);
+        let mut record_index = 0usize;
+        
+        for level_index in 0..level_count as usize {
+            let bad_level = || SnapshotError::BadLevelRecord(level_index);
+            
+            let side = reader.u8().and_then(Side::from_u8).ok_or_else(bad_level)?;
+            let price = reader.u64().ok_or_else(bad_level)?;
+            let level_orders = reader.u64().ok_or_else(bad_level)?;
+            let total_quantity = reader.u64().ok_or_else(bad_level)?;
+            let head = reader.u64().ok_or_else(bad_level)?;
+            let tail = reader.u64().ok_or_else(bad_level)?;
+            let corrupt = |reason| SnapshotError::CorruptLevel { level: level_index, reason };
+            
+            if level_orders == 0 {
+                return Err(bad_level());
+            }
+            if !seen_levels.insert((side, price)) {
+                return Err(SnapshotError::DuplicateLevel { side, price });
+            }
+            
+            let mut quantity = 0u64;
+            let mut prev_key = NO_LINK;
+            let mut expected_key = head;
+            for _ in 0..level_orders {
+                let bad_order = SnapshotError::BadOrderRecord(record_index);
+                
+                // Records are already in queue order; the links must agree with it
+                let record = reader.take(NODE_RECORD_LEN).ok_or(bad_order.clone())?;
+                let mut links = Reader::new(&record[..24]);
+                let (key, prev, next) = match (links.u64(), links.u64(), links.u64()) {
+                    (Some(key), Some(prev), Some(next)) => (key, prev, next),
+                    _ => return Err(bad_order),
+                };
+                if key == NO_LINK || key != expected_key {
+                    return Err(corrupt("node is not linked from its predecessor"));
+                }
+                if !seen_keys.insert(key) {
+                    return Err(corrupt("node key repeats (cycle)"));
+                }
+                if prev != prev_key {
+                    return Err(corrupt("prev link does not name the previous node"));
+                }
+                prev_key = key;
+                expected_key = next;
+                
+                let order = decode_order(&record[24..], side, price).ok_or(bad_order.clone())?;
+                if !seen_ids.insert(order.id) {
+                    return Err(SnapshotError::DuplicateOrderId(order.id));
+                }
+                
+                quantity = quantity.checked_add(order.remaining).ok_or(bad_order)?;
+                clob.add_order(order);
+                record_index += 1;
+            }
+            
+            if expected_key != NO_LINK {
+                return Err(corrupt("last node links past the end of the level"));
+            }
+            if tail != prev_key {
+                return Err(corrupt("tail does not name the last node"));
+            }
+            if quantity != total_quantity {
+                return Err(SnapshotError::CountMismatch(format!(
+                    "level {} declares total_quantity {} but orders sum to {}",
+                    level_index, total_quantity, quantity
+                )));
+            }
+        }
+        
+        if record_index as u64 != order_count {
+            return Err(SnapshotError::CountMismatch(format!(
+                "header declares {} orders but levels hold {}",
+                order_count, record_index
+            )));
+        }
+        if reader.remaining() > 0 {
+            return Err(SnapshotError::TrailingBytes(reader.remaining()));
+        }
+        
+        clob.restore_id_counters(next_order_id, next_trade_id);
+        clob.restore_seq(seq);
+        Ok(clob)
+    }
+    
+    /// Serialize the orders at one price level into a level patch
+    ///
+    /// # Arguments
+    ///
+    /// * `side` - Side of the level
+    /// * `price` - Price of the level (fixed-point)
+    ///
+    /// # Returns
+    ///
+    /// The encoded level, or None if no orders rest at that price
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut source = CLOB::with_capacity(100);
+    /// source.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+    /// source.add_order(Order::new(2, 100, Side::Buy, 5_000_000_000_000, 200_000_000, 0));
+    ///
+    /// let patch = source.serialize_level(Side::Buy, 5_000_000_000_000).unwrap();
+    ///
+    /// let mut replica = CLOB::with_capacity(100);
+    /// replica.apply_level(&patch).unwrap();
+    /// assert_eq!(replica.queue_position(2), Some(1));
+    /// ```
+    pub fn serialize_level(&self, side: Side, price: u64) -> Option<Vec<u8>> {
+        let level = match side {
+            Side::Buy => self.bids().get(&Reverse(price)),
+            Side::Sell => self.asks().get(&price),
+        }
+        .filter(|level| !level.is_empty())?;
+        
+        let mut out = Vec::with_capacity(LEVEL_PATCH_HEADER_LEN + level.order_count * ORDER_SSZ_LEN);
+        out.push(SNAPSHOT_VERSION);
+        out.push(side.to_u8());
+        out.extend_from_slice(&price.to_le_bytes());
+        out.extend_from_slice(&(level.order_count as u64).to_le_bytes());
+        
+        let mut current = level.head;
+        while let Some(key) = current {
+            let node = match self.orders().get(key) {
+                Some(n) => n,
+                None => break,
+            };
+            let order = ssz_rs::serialize(&node.order).expect("fixed-size Order always serializes");
+            out.extend_from_slice(&order);
+            current = node.next;
+        }
+        
+        Some(out)
+    }
+    
+    /// Replace one price level with the contents of a level patch
+    ///
+    /// Orders currently resting at the patch's side and price are removed,
+    /// then the patch's orders are added head to tail. A patch with no
+    /// orders clears the level. The book is left untouched on error.
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`SnapshotError`] if the patch is truncated or has
+    /// trailing bytes, an order record is invalid or does not belong to
+    /// the level, an order ID repeats within the patch or rests elsewhere
+    /// in the book, or the patch holds more orders than the book's
+    /// [`CLOB::with_max_level_orders`] cap.
+    pub fn apply_level(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        if bytes.len() < LEVEL_PATCH_HEADER_LEN {
+            return Err(SnapshotError::TruncatedHeader {
+                expected: LEVEL_PATCH_HEADER_LEN,
+                actual: bytes.len(),
+            });
+        }
+        
+        let mut reader = Reader::new(bytes);
+        let version = reader.u8().expect("length checked");
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        let side = reader.u8().and_then(Side::from_u8).ok_or(SnapshotError::BadLevelRecord(0))?;
+        let price = reader.u64().expect("length checked");
+        let order_count = reader.u64().expect("length checked");
+        
+        // Never trust declared counts for allocation
+        let capacity = (order_count as usize).min(reader.remaining() / ORDER_SSZ_LEN);
+        let mut orders = Vec::with_capacity(capacity);
+        let mut seen_ids: HashSet<u64> = HashSet::with_capacity(capacity);
+        
+        for index in 0..order_count as usize {
+            let bad_order = SnapshotError::BadOrderRecord(index);
+            let record = reader.take(ORDER_SSZ_LEN).ok_or(bad_order.clone())?;
+            let order = decode_order(record, side, price).ok_or(bad_order)?;
+            
+            // IDs resting at this level are about to be replaced
+            let resting_elsewhere = self
+                .get_key(order.id)
+                .and_then(|key| self.get_order(key))
+                .is_some_and(|resting| resting.side() != side || resting.price != price);
+            if !seen_ids.insert(order.id) || resting_elsewhere {
+                return Err(SnapshotError::DuplicateOrderId(order.id));
+            }
+            orders.push(order);
+        }
+        if reader.remaining() > 0 {
+            return Err(SnapshotError::TrailingBytes(reader.remaining()));
+        }
+        
+        let cap = self.max_level_orders();
+        if cap != 0 && orders.len() > cap {
+            return Err(SnapshotError::LevelOverCap { count: orders.len(), cap });
+        }
+        
+        // The level is empty and every ID is explicit and free, so each insert fits
+        self.cancel_price_level(side, price);
+        for order in orders {
+            let added = self.try_add_order(order);
+            debug_assert!(added.is_some(), "checked level patch order did not fit");
+        }
+        Ok(())
+    }
+}
+
+/// Decode an order record and check it belongs to the given level
+fn decode_order(bytes: &[u8], side: Side, price: u64) -> Option<Order> {
+    let order: Order = ssz_rs::deserialize(bytes).ok()?;
+    
+    let valid = order.id != 0
+        && Side::from_u8(order.side_raw) == Some(side)
+        && OrderType::from_u8(order.order_type_raw) == Some(OrderType::Limit)
+        && order.price == price
+        && order.remaining > 0
+        && order.remaining <= order.quantity
+        && order.cumulative_filled <= order.quantity - order.remaining;
+    valid.then_some(order)
+}
+
+/// Encode an optional slab link
+#[inline]
+fn encode_link(link: Option<usize>) -> u64 {
+    link.map_or(NO_LINK, |key| key as u64)
+}
+
+/// Encode a price order as a header tag
+fn price_order_tag(price_order: PriceOrder) -> u8 {
+    match price_order {
+        PriceOrder::Standard => 0,
+        PriceOrder::Inverse => 1,
+    }
+}
+
+/// Decode a price order header tag
+fn price_order_from_tag(tag: u8) -> Option<PriceOrder> {
+    match tag {
+        0 => Some(PriceOrder::Standard),
+        1 => Some(PriceOrder::Inverse),
+        _ => None,
+    }
+}
+
+/// Bounds-checked cursor over snapshot bytes
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+    
+    fn remaining(&self) -> usize {
+        self.bytes.len() - self.pos
+    }
+    
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if self.remaining() < len {
+            return None;
+        }
+        let slice = &self.bytes[self.pos..self.pos + len];
+        self.pos += len;
+        Some(slice)
+    }
+    
+    fn u8(&mut self) -> Option<u8> {
+        self.take(1).map(|b| b[0])
+    }
+    
+    fn u64(&mut self) -> Option<u64> {
+        self.take(8).map(|b| u64::from_le_bytes(b.try_into().expect("8-byte slice")))
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::BookEvent;
+    
+    fn sample_book() -> CLOB {
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 1));
+        clob.add_order(Order::new(2, 101, Side::Buy, 5_000_000_000_000, 200_000_000, 2));
+        clob.add_order(Order::new(3, 102, Side::Buy, 4_900_000_000_000, 300_000_000, 3));
+        clob.add_order(Order::new(4, 103, Side::Sell, 5_100_000_000_000, 400_000_000, 4));
+        clob.next_trade_id();
+        clob
+    }
+    
+    /// Byte offset of the first order record's SSZ payload
+    fn first_order_offset() -> usize {
+        HEADER_LEN + LEVEL_HEADER_LEN + 24
+    }
+    
+    #[test]
+    fn test_order_ssz_len() {
+        let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+        assert_eq!(ssz_rs::serialize(&order).unwrap().len(), ORDER_SSZ_LEN);
+    }
+    
+    #[test]
+    fn test_snapshot_roundtrip() {
+        let clob = sample_book();
+        let bytes = clob.to_ssz_bytes();
+        
+        assert_eq!(bytes.len(), HEADER_LEN + 3 * LEVEL_HEADER_LEN + 4 * NODE_RECORD_LEN);
+        
+        let restored = CLOB::from_ssz_bytes(&bytes).expect("valid snapshot");
+        assert_eq!(restored.compute_state_root(), clob.compute_state_root());
+        assert_eq!(restored.peek_next_trade_id(), clob.peek_next_trade_id());
+        assert_eq!(restored.queue_position(2), Some(1));
+        assert!(restored.verify_invariants().is_ok());
+    }
+    
+    #[test]
+    fn test_snapshot_keeps_id_range() {
+        let mut clob = CLOB::with_capacity(10).with_id_range(1, 3);
+        clob.add_order(Order::new(0, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(0, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        assert!(clob.order_ids().is_exhausted());
+        
+        // The restored book is still exhausted rather than spilling into the next range
+        let mut restored = CLOB::from_ssz_bytes(&clob.to_ssz_bytes()).unwrap();
+        assert_eq!(*restored.order_ids(), *clob.order_ids());
+        assert!(restored.try_add_order(Order::new(0, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0)).is_none());
+        assert_eq!(restored.compute_state_root(), clob.compute_state_root());
+    }
+    
+    #[test]
+    fn test_snapshot_keeps_seq() {
+        let mut clob = sample_book();
+        clob.cancel_order(1);
+        let seq = clob.current_seq();
+        
+        let mut restored = CLOB::from_ssz_bytes(&clob.to_ssz_bytes()).unwrap();
+        assert_eq!(restored.current_seq(), seq);
+        
+        // The restored book follows the source's event feed from here on
+        let event = BookEvent::Removed { order_id: 2, seq: seq + 1 };
+        assert!(clob.apply_event(&event));
+        assert!(restored.apply_event(&event));
+        assert!(!restored.apply_event(&BookEvent::Removed { order_id: 3, seq }));
+        assert_eq!(restored.current_seq(), clob.current_seq());
+        assert_eq!(restored.compute_state_root(), clob.compute_state_root());
+    }
+    
+    #[test]
+    fn test_level_patch_roundtrip() {
+        let source = sample_book();
+        let patch = source.serialize_level(Side::Buy, 5_000_000_000_000).unwrap();
+        assert_eq!(patch.len(), LEVEL_PATCH_HEADER_LEN + 2 * ORDER_SSZ_LEN);
+        assert!(source.serialize_level(Side::Sell, 5_000_000_000_000).is_none());
+        
+        let mut replica = CLOB::with_capacity(100);
+        replica.apply_level(&patch).unwrap();
+        
+        let level = source.bids().get(&Reverse(5_000_000_000_000)).unwrap();
+        let patched = replica.bids().get(&Reverse(5_000_000_000_000)).unwrap();
+        assert_eq!(patched.summary(), level.summary());
+        assert_eq!(replica.queue_position(1), Some(0));
+        assert_eq!(replica.queue_position(2), Some(1));
+        for id in [1, 2] {
+            let original = source.get_order(source.get_key(id).unwrap());
+            assert_eq!(replica.get_order(replica.get_key(id).unwrap()), original);
+        }
+        
+        // Re-applying replaces rather than duplicates
+        replica.apply_level(&patch).unwrap();
+        assert_eq!(replica.order_count(), 2);
+        assert!(replica.verify_invariants().is_ok());
+        
+        // A replica capped below the patch refuses it and keeps its level
+        let mut capped = CLOB::with_capacity(100).with_max_level_orders(1);
+        capped.add_order(Order::new(9, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0));
+        let root = capped.compute_state_root();
+        assert_eq!(capped.apply_level(&patch), Err(SnapshotError::LevelOverCap { count: 2, cap: 1 }));
+        assert_eq!(capped.compute_state_root(), root);
+        
+        // A patch within the cap still applies
+        let mut single = CLOB::with_capacity(100);
+        single.add_order(Order::new(3, 102, Side::Buy, 4_900_000_000_000, 300_000_000, 3));
+        capped.apply_level(&single.serialize_level(Side::Buy, 4_900_000_000_000).unwrap()).unwrap();
+        assert_eq!(capped.order_count(), 2);
+    }
+    
+    #[test]
+    fn test_level_patch_rejects_bad_input() {
+        let source = sample_book();
+        let patch = source.serialize_level(Side::Buy, 5_000_000_000_000).unwrap();
+        
+        // Order 1 already rests at another price in the target
+        let mut target = CLOB::with_capacity(100);
+        target.add_order(Order::new(1, 100, Side::Sell, 6_000_000_000_000, 100_000_000, 0));
+        assert_eq!(target.apply_level(&patch), Err(SnapshotError::DuplicateOrderId(1)));
+        assert_eq!(target.order_count(), 1);
+        
+        let mut target = CLOB::with_capacity(100);
+        assert_eq!(
+            target.apply_level(&patch[..patch.len() - 1]),
+            Err(SnapshotError::BadOrderRecord(1))
+        );
+        
+        let mut extra = patch.clone();
+        extra.push(0);
+        assert_eq!(target.apply_level(&extra), Err(SnapshotError::TrailingBytes(1)));
+        assert!(target.is_empty());
+    }
+    
+    #[test]
+    fn test_snapshot_truncated() {
+        let bytes = sample_book().to_ssz_bytes();
+        
+        assert_eq!(
+            CLOB::from_ssz_bytes(&bytes[..10]).unwrap_err(),
+            SnapshotError::TruncatedHeader { expected: HEADER_LEN, actual: 10 }
+        );
+        
+        // Cut partway through the last order record
+        let cut = &bytes[..bytes.len() - 5];
+        assert_eq!(CLOB::from_ssz_bytes(cut).unwrap_err(), SnapshotError::BadOrderRecord(3));
+        
+        let mut extra = bytes.clone();
+        extra.push(0);
+        assert_eq!(CLOB::from_ssz_bytes(&extra).unwrap_err(), SnapshotError::TrailingBytes(1));
+    }
+    
+    #[test]
+    fn test_snapshot_duplicate_order_id() {
+        let mut bytes = sample_book().to_ssz_bytes();
+        
+        // Rewrite the second bid's ID to match the first
+        let second = first_order_offset() + NODE_RECORD_LEN;
+        bytes[second..second + 8].copy_from_slice(&1u64.to_le_bytes());
+        
+        assert_eq!(CLOB::from_ssz_bytes(&bytes).unwrap_err(), SnapshotError::DuplicateOrderId(1));
+    }
+    
+    #[test]
+    fn test_snapshot_corrupt_links() {
+        let bytes = sample_book().to_ssz_bytes();
+        
+        // Offsets into the first bid level (two orders)
+        let head = HEADER_LEN + 1 + 8 + 8 + 8;
+        let tail = head + 8;
+        let first = HEADER_LEN + LEVEL_HEADER_LEN;
+        let second = first + NODE_RECORD_LEN;
+        let read = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+        let corrupt = |patch: &dyn Fn(&mut Vec<u8>)| {
+            let mut bad = bytes.clone();
+            patch(&mut bad);
+            CLOB::from_ssz_bytes(&bad).unwrap_err()
+        };
+        
+        // Second node's next points back at the first: a cycle
+        let first_key = read(first);
+        let err = corrupt(&|b| b[second + 16..second + 24].copy_from_slice(&first_key.to_le_bytes()));
+        assert!(matches!(err, SnapshotError::CorruptLevel { level: 0, .. }), "{err:?}");
+        
+        // Second node reuses the first node's key
+        let err = corrupt(&|b| b[second..second + 8].copy_from_slice(&first_key.to_le_bytes()));
+        assert!(matches!(err, SnapshotError::CorruptLevel { level: 0, .. }), "{err:?}");
+        
+        // Broken back link
+        let err = corrupt(&|b| b[second + 8..second + 16].copy_from_slice(&NO_LINK.to_le_bytes()));
+        assert!(matches!(err, SnapshotError::CorruptLevel { level: 0, .. }), "{err:?}");
+        
+        // Head or tail naming the wrong node
+        let second_key = read(second);
+        let err = corrupt(&|b| b[head..head + 8].copy_from_slice(&second_key.to_le_bytes()));
+        assert!(matches!(err, SnapshotError::CorruptLevel { level: 0, .. }), "{err:?}");
+        let err = corrupt(&|b| b[tail..tail + 8].copy_from_slice(&first_key.to_le_bytes()));
+        assert!(matches!(err, SnapshotError::CorruptLevel { level: 0, .. }), "{err:?}");
+        
+        // The untouched snapshot still loads
+        assert!(CLOB::from_ssz_bytes(&bytes).is_ok());
+    }
+    
+    #[test]
+    fn test_snapshot_bad_records() {
+        let bytes = sample_book().to_ssz_bytes();
+        
+        let mut bad_version = bytes.clone();
+        bad_version[0] = 9;
+        assert_eq!(CLOB::from_ssz_bytes(&bad_version).unwrap_err(), SnapshotError::UnsupportedVersion(9));
+        
+        // Order ID 0 would be auto-assigned, so it is never valid in a snapshot
+        let mut zero_id = bytes.clone();
+        let first = first_order_offset();
+        zero_id[first..first + 8].copy_from_slice(&0u64.to_le_bytes());
+        assert_eq!(CLOB::from_ssz_bytes(&zero_id).unwrap_err(), SnapshotError::BadOrderRecord(0));
+        
+        // Header claims more orders than the levels hold
+        let mut count = bytes.clone();
+        count[HEADER_LEN - 8..HEADER_LEN].copy_from_slice(&5u64.to_le_bytes());
+        assert!(matches!(CLOB::from_ssz_bytes(&count).unwrap_err(), SnapshotError::CountMismatch(_)));
+        
+        // The next ID must fall inside the ID range
+        let mut range = bytes.clone();
+        range[2..10].copy_from_slice(&50u64.to_le_bytes());
+        assert_eq!(
+            CLOB::from_ssz_bytes(&range).unwrap_err(),
+            SnapshotError::BadIdRange { start: 50, end: u64::MAX, next: 1 }
+        );
+        
+        // Garbage never panics
+        for len in 0..bytes.len() {
+            let _ = CLOB::from_ssz_bytes(&bytes[..len]);
+        }
+    }
+}
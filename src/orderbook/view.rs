@@ -0,0 +1,229 @@
+//! Read-only views of an order book for market-data consumers.
+//!
+//! [`OrderBookView`] borrows a [`CLOB`] and exposes only its read
+//! accessors. Handing a component a view instead of `&CLOB` keeps the
+//! book's mutating API (including the engine-facing `*_mut` accessors)
+//! out of its reach entirely:
+//!
+//! ```compile_fail
+//! use dark_hypercore::orderbook::CLOB;
+//! use dark_hypercore::types::{Order, Side};
+//!
+//! let mut clob = CLOB::with_capacity(10);
+//! let view = clob.view();
+//! view.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+//! ```
+
+use crate::orderbook::{LevelSummary, OrderContext, PriceLevel, PriceOrder, TopOfBook, CLOB};
+use crate::types::{Order, Side};
+
+/// Read-only borrow of an order book.
+///
+/// Cheap to copy; every accessor reads the live book it was taken from.
+#[derive(Debug, Clone, Copy)]
+pub struct OrderBookView<'a> {
+    clob: &'a CLOB,
+}
+
+impl CLOB {
+    /// Borrow the book as a read-only [`OrderBookView`]
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::orderbook::CLOB;
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut clob = CLOB::with_capacity(100);
+    /// clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+    ///
+    /// let view = clob.view();
+    /// assert_eq!(view.best_bid(), Some(100_000_000));
+    /// assert_eq!(view.order(1).unwrap().user_id, 100);
+    /// ```
+    #[inline]
+    pub fn view(&self) -> OrderBookView<'_> {
+        OrderBookView { clob: self }
+    }
+}
+
+impl<'a> OrderBookView<'a> {
+    // ========================================================================
+    // Best Bid/Ask
+    // ========================================================================
+    
+    /// Get the best bid price
+    #[inline]
+    pub fn best_bid(&self) -> Option<u64> {
+        self.clob.best_bid()
+    }
+    
+    /// Get the best ask price
+    #[inline]
+    pub fn best_ask(&self) -> Option<u64> {
+        self.clob.best_ask()
+    }
+    
+    /// Get the spread, or None if either side is empty or the book is crossed
+    #[inline]
+    pub fn spread(&self) -> Option<u64> {
+        self.clob.spread()
+    }
+    
+    /// Get the best bid and ask with their aggregate sizes
+    #[inline]
+    pub fn top_of_book(&self) -> TopOfBook {
+        self.clob.top_of_book()
+    }
+    
+    // ========================================================================
+    // Depth
+    // ========================================================================
+    
+    /// Get the summary of the level at a specific price
+    #[inline]
+    pub fn level_summary(&self, side: Side, price: u64) -> Option<LevelSummary> {
+        self.clob.level_summary(side, price)
+    }
+    
+    /// Get summaries of the top `max_levels` levels on a side, best first
+    #[inline]
+    pub fn depth(&self, side: Side, max_levels: usize) -> Vec<LevelSummary> {
+        self.clob.level_summaries(side, max_levels)
+    }
+    
+    /// Iterate a side's levels in priority order (best first)
+    pub fn levels(&self, side: Side) -> impl Iterator<Item = LevelSummary> + 'a {
+        self.clob.levels_by_priority(side).map(PriceLevel::summary)
+    }
+    
+    /// Walk levels outward from the touch, alternating sides
+    ///
+    /// See [`CLOB::levels_from_touch`].
+    #[inline]
+    pub fn levels_from_touch(&self) -> impl Iterator<Item = (Side, u64, u64)> + 'a {
+        self.clob.levels_from_touch()
+    }
+    
+    // ========================================================================
+    // Orders
+    // ========================================================================
+    
+    /// Get a resting order by ID
+    pub fn order(&self, order_id: u64) -> Option<&'a Order> {
+        self.clob.get_key(order_id).and_then(|key| self.clob.get_order(key))
+    }
+    
+    /// Get the number of orders ahead of an order at its price level
+    #[inline]
+    pub fn queue_position(&self, order_id: u64) -> Option<usize> {
+        self.clob.queue_position(order_id)
+    }
+    
+    /// Get the queue and market state around a resting order
+    ///
+    /// See [`CLOB::order_context`].
+    #[inline]
+    pub fn order_context(&self, order_id: u64) -> Option<OrderContext> {
+        self.clob.order_context(order_id)
+    }
+    
+    /// Iterate a side's resting orders in matching priority
+    ///
+    /// See [`CLOB::iter_side`].
+    #[inline]
+    pub fn iter_side(&self, side: Side) -> impl Iterator<Item = &'a Order> + 'a {
+        self.clob.iter_side(side)
+    }
+    
+    // ========================================================================
+    // Statistics
+    // ========================================================================
+    
+    /// Get the total number of resting orders
+    #[inline]
+    pub fn order_count(&self) -> usize {
+        self.clob.order_count()
+    }
+    
+    /// Get the number of resting bid orders
+    #[inline]
+    pub fn bid_count(&self) -> usize {
+        self.clob.bid_count()
+    }
+    
+    /// Get the number of resting ask orders
+    #[inline]
+    pub fn ask_count(&self) -> usize {
+        self.clob.ask_count()
+    }
+    
+    /// Get the number of bid price levels
+    #[inline]
+    pub fn bid_levels(&self) -> usize {
+        self.clob.bid_levels()
+    }
+    
+    /// Get the number of ask price levels
+    #[inline]
+    pub fn ask_levels(&self) -> usize {
+        self.clob.ask_levels()
+    }
+    
+    /// Check if the book has no resting orders
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.clob.is_empty()
+    }
+    
+    /// Get the book's price ordering
+    #[inline]
+    pub fn price_order(&self) -> PriceOrder {
+        self.clob.price_order()
+    }
+    
+    /// Compute the book's state root
+    #[inline]
+    pub fn compute_state_root(&self) -> [u8; 32] {
+        self.clob.compute_state_root()
+    }
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// Market-data consumer that can only ever see a view
+    fn bbo_line(view: OrderBookView<'_>) -> (Option<u64>, Option<u64>, usize) {
+        (view.best_bid(), view.best_ask(), view.order_count())
+    }
+    
+    #[test]
+    fn test_view_reflects_live_book() {
+        let mut clob = CLOB::with_capacity(100);
+        clob.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+        clob.add_order(Order::new(2, 100, Side::Buy, 90_000_000, 200_000_000, 0));
+        clob.add_order(Order::new(3, 200, Side::Sell, 110_000_000, 300_000_000, 0));
+        
+        let view = clob.view();
+        assert_eq!(bbo_line(view), (Some(100_000_000), Some(110_000_000), 3));
+        assert_eq!(view.top_of_book(), clob.top_of_book());
+        assert_eq!(view.spread(), Some(10_000_000));
+        assert_eq!(view.depth(Side::Buy, 10), clob.level_summaries(Side::Buy, 10));
+        
+        let bids: Vec<u64> = view.levels(Side::Buy).map(|level| level.price).collect();
+        assert_eq!(bids, vec![100_000_000, 90_000_000]);
+        assert_eq!(view.order(3).map(|o| o.remaining), Some(300_000_000));
+        assert_eq!((view.bid_count(), view.ask_count(), view.bid_levels()), (2, 1, 2));
+        assert_eq!(view.compute_state_root(), clob.compute_state_root());
+        
+        // A fresh view sees later mutations
+        clob.cancel_order(1);
+        assert_eq!(bbo_line(clob.view()), (Some(90_000_000), Some(110_000_000), 2));
+        assert!(clob.view().order(1).is_none());
+    }
+}
@@ -0,0 +1,287 @@
+//! Test utilities for comparing order books.
+//!
+//! State roots tell you *that* two books differ; [`BookDiff`] tells you
+//! *where*. Use [`assert_books_equivalent`] in tests that derive the same
+//! book two different ways (replay, snapshots, sharding, ...).
+//!
+//! [`interleave`] merges two order streams in a seeded, reproducible
+//! order, for testing priority under contention without real threads.
+//!
+//! ## Example
+//!
+//! ```
+//! use dark_hypercore::orderbook::CLOB;
+//! use dark_hypercore::testing::{assert_books_equivalent, BookDiff};
+//! use dark_hypercore::types::{Order, Side};
+//!
+//! let mut a = CLOB::with_capacity(10);
+//! let mut b = CLOB::with_capacity(10);
+//! a.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+//! b.add_order(Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0));
+//!
+//! assert!(BookDiff::between(&a, &b).is_empty());
+//! assert_books_equivalent(&a, &b);
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::orderbook::CLOB;
+use crate::types::Order;
+
+/// A single difference between two books.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookDifference {
+    /// Order rests only in the left book
+    OnlyInLeft(Order),
+    
+    /// Order rests only in the right book
+    OnlyInRight(Order),
+    
+    /// Order rests in both books with different contents
+    Changed { left: Order, right: Order },
+    
+    /// Order rests in both books at different queue positions
+    QueuePosition { order_id: u64, left: usize, right: usize },
+    
+    /// A book-level counter differs
+    Counter { name: &'static str, left: u64, right: u64 },
+}
+
+impl fmt::Display for BookDifference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookDifference::OnlyInLeft(order) => write!(f, "order {} only in left: {:?}", order.id, order),
+            BookDifference::OnlyInRight(order) => write!(f, "order {} only in right: {:?}", order.id, order),
+            BookDifference::Changed { left, right } => {
+                write!(f, "order {} differs:\n    left:  {:?}\n    right: {:?}", left.id, left, right)
+            }
+            BookDifference::QueuePosition { order_id, left, right } => {
+                write!(f, "order {} queue position differs: left {}, right {}", order_id, left, right)
+            }
+            BookDifference::Counter { name, left, right } => {
+                write!(f, "{} differs: left {}, right {}", name, left, right)
+            }
+        }
+    }
+}
+
+/// Every difference between two books, ordered by order ID.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BookDiff {
+    /// Differences found, counters first, then orders by ascending ID
+    pub differences: Vec<BookDifference>,
+}
+
+impl BookDiff {
+    /// Compare two books order by order
+    pub fn between(left: &CLOB, right: &CLOB) -> Self {
+        let mut differences = Vec::new();
+        
+        let counters = [
+            ("next_order_id", left.peek_next_order_id(), right.peek_next_order_id()),
+            ("next_trade_id", left.peek_next_trade_id(), right.peek_next_trade_id()),
+        ];
+        for (name, l, r) in counters {
+            if l != r {
+                differences.push(BookDifference::Counter { name, left: l, right: r });
+            }
+        }
+        
+        let left_orders = resting_orders(left);
+        let mut right_orders = resting_orders(right);
+        
+        for (order_id, left_order) in left_orders {
+            let right_order = match right_orders.remove(&order_id) {
+                Some(o) => o,
+                None => {
+                    differences.push(BookDifference::OnlyInLeft(left_order));
+                    continue;
+                }
+            };
+            
+            if left_order != right_order {
+                differences.push(BookDifference::Changed { left: left_order, right: right_order });
+                continue;
+            }
+            
+            let positions = (left.queue_position(order_id), right.queue_position(order_id));
+            if let (Some(l), Some(r)) = positions {
+                if l != r {
+                    differences.push(BookDifference::QueuePosition { order_id, left: l, right: r });
+                }
+            }
+        }
+        
+        differences.extend(right_orders.into_values().map(BookDifference::OnlyInRight));
+        
+        Self { differences }
+    }
+    
+    /// Check if the books are identical
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.differences.is_empty()
+    }
+}
+
+impl fmt::Display for BookDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.differences.is_empty() {
+            return write!(f, "books are identical");
+        }
+        writeln!(f, "{} difference(s):", self.differences.len())?;
+        for difference in &self.differences {
+            writeln!(f, "  {}", difference)?;
+        }
+        Ok(())
+    }
+}
+
+/// Assert two books have the same state root, printing a [`BookDiff`] if not
+///
+/// # Panics
+///
+/// Panics with both roots in hex and every differing order
+pub fn assert_books_equivalent(left: &CLOB, right: &CLOB) {
+    let (left_root, right_root) = (left.compute_state_root(), right.compute_state_root());
+    if left_root == right_root {
+        return;
+    }
+    
+    panic!(
+        "books are not equivalent\n  left root:  {}\n  right root: {}\n{}",
+        hex::encode(left_root),
+        hex::encode(right_root),
+        BookDiff::between(left, right)
+    );
+}
+
+/// Deterministically interleave two order streams
+///
+/// Models two sources (e.g. competing market makers) racing to the engine:
+/// each output slot is drawn from `a` or `b` by a seeded PRNG until one
+/// runs out, then the rest of the other follows. Each stream keeps its own
+/// relative order, every input order appears exactly once, and the same
+/// seed always produces the same sequence.
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::testing::interleave;
+/// use dark_hypercore::types::{Order, Side};
+///
+/// let a: Vec<_> = (1..=3).map(|id| Order::new(id, 1, Side::Buy, 100_000_000, 100_000_000, 0)).collect();
+/// let b: Vec<_> = (4..=6).map(|id| Order::new(id, 2, Side::Sell, 100_000_000, 100_000_000, 0)).collect();
+///
+/// let merged = interleave(a.clone(), b.clone(), 7);
+/// assert_eq!(merged.len(), 6);
+/// assert_eq!(merged, interleave(a, b, 7));
+/// ```
+pub fn interleave(a: Vec<Order>, b: Vec<Order>, seed: u64) -> Vec<Order> {
+    let mut out = Vec::with_capacity(a.len() + b.len());
+    let mut a = a.into_iter().peekable();
+    let mut b = b.into_iter().peekable();
+    let mut state = seed;
+    
+    while a.peek().is_some() && b.peek().is_some() {
+        let next = if splitmix64(&mut state) & 1 == 0 { a.next() } else { b.next() };
+        out.extend(next);
+    }
+    out.extend(a);
+    out.extend(b);
+    out
+}
+
+/// SplitMix64 step: a tiny, portable PRNG with a fixed output sequence
+fn splitmix64(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// Collect every resting order, keyed by ID
+fn resting_orders(clob: &CLOB) -> BTreeMap<u64, Order> {
+    clob.orders()
+        .iter()
+        .map(|(_, node)| (node.order.id, node.order.clone()))
+        .collect()
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    
+    fn book(orders: &[Order]) -> CLOB {
+        let mut clob = CLOB::with_capacity(10);
+        for order in orders {
+            clob.add_order(order.clone());
+        }
+        clob
+    }
+    
+    #[test]
+    fn test_interleave_is_deterministic_and_complete() {
+        let a: Vec<_> = (1..=50).map(|id| Order::new(id, 1, Side::Buy, 100_000_000, 100_000_000, 0)).collect();
+        let b: Vec<_> = (101..=130).map(|id| Order::new(id, 2, Side::Sell, 200_000_000, 100_000_000, 0)).collect();
+        
+        let merged = interleave(a.clone(), b.clone(), 42);
+        assert_eq!(merged, interleave(a.clone(), b.clone(), 42));
+        assert_ne!(merged, interleave(a.clone(), b.clone(), 43));
+        
+        // Every order exactly once
+        let mut ids: Vec<u64> = merged.iter().map(|o| o.id).collect();
+        ids.sort_unstable();
+        let mut expected: Vec<u64> = a.iter().chain(&b).map(|o| o.id).collect();
+        expected.sort_unstable();
+        assert_eq!(ids, expected);
+        
+        // Each stream keeps its own order
+        let from_a: Vec<_> = merged.iter().filter(|o| o.user_id == 1).cloned().collect();
+        let from_b: Vec<_> = merged.iter().filter(|o| o.user_id == 2).cloned().collect();
+        assert_eq!(from_a, a);
+        assert_eq!(from_b, b);
+        
+        assert_eq!(interleave(vec![], b.clone(), 1), b);
+    }
+    
+    #[test]
+    fn test_diff_reports_each_difference() {
+        let a = Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0);
+        let b = Order::new(2, 100, Side::Buy, 100_000_000, 100_000_000, 0);
+        let c = Order::new(3, 100, Side::Sell, 200_000_000, 100_000_000, 0);
+        let mut c_changed = c.clone();
+        c_changed.remaining = 50_000_000;
+        let d = Order::new(4, 100, Side::Sell, 300_000_000, 100_000_000, 0);
+        
+        let left = book(&[a.clone(), b.clone(), c.clone()]);
+        let right = book(&[b.clone(), a.clone(), c_changed.clone(), d.clone()]);
+        
+        let diff = BookDiff::between(&left, &right);
+        assert_eq!(diff.differences, vec![
+            BookDifference::QueuePosition { order_id: 1, left: 0, right: 1 },
+            BookDifference::QueuePosition { order_id: 2, left: 1, right: 0 },
+            BookDifference::Changed { left: c, right: c_changed },
+            BookDifference::OnlyInRight(d),
+        ]);
+    }
+    
+    #[test]
+    #[should_panic(expected = "order 3 differs")]
+    fn test_assert_books_equivalent_names_diverging_order() {
+        let order = Order::new(3, 100, Side::Buy, 100_000_000, 100_000_000, 0);
+        
+        let left = book(std::slice::from_ref(&order));
+        let mut right = book(std::slice::from_ref(&order));
+        right.reduce_order(3, 10_000_000);
+        
+        assert_books_equivalent(&left, &right);
+    }
+}
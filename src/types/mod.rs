@@ -7,9 +7,12 @@
 //!
 //! - [`Order`]: A limit order in the order book
 //! - [`Side`]: Buy or Sell
-//! - [`OrderType`]: Type of order (Limit only in Phase 1)
+//! - [`OrderType`]: Type of order (Limit or Market)
 //! - [`Trade`]: An executed trade between two orders
+//! - [`TradeBatchError`]: Error decoding a versioned batch of trades
+//! - [`AnonTrade`]: A trade stripped of user and order IDs for public feeds
 //! - [`ExecutionReceipt`]: Batch execution summary
+//! - [`ExecutionReceiptBuilder`]: Accumulates a batch into a receipt with per-user totals
 //!
 //! ## Fixed-Point Arithmetic
 //!
@@ -23,6 +26,6 @@ pub mod price;
 
 // Re-export all types at module level
 pub use order::{Order, Side, OrderType};
-pub use trade::Trade;
-pub use receipt::ExecutionReceipt;
+pub use trade::{AnonTrade, Settlement, Trade, TradeBatchError};
+pub use receipt::{ExecutionReceipt, ExecutionReceiptBuilder, UserFillSummary};
 
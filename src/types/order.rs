@@ -12,7 +12,14 @@
 //! Prices and quantities are stored as u64 scaled by 10^8 (SCALE constant).
 //! This provides 8 decimal places of precision without floating-point errors.
 
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
 use ssz_rs::prelude::*;
+use sha2::{Sha256, Digest};
+
+use crate::types::price::from_fixed;
 
 // Note: SCALE constant is defined in price.rs module
 // Use: crate::types::price::SCALE
@@ -62,19 +69,43 @@ impl Side {
     }
 }
 
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Side::Buy => "BUY",
+            Side::Sell => "SELL",
+        })
+    }
+}
+
 // ============================================================================
 // OrderType enum
 // ============================================================================
 
 /// Order type enumeration
 ///
-/// Phase 1 only supports Limit orders.
-/// Future phases may add Market, Stop, etc.
+/// Future phases may add Stop, etc.
+///
+/// ## Stop Activation Order
+///
+/// Stops are not implemented yet. When they are, every node must activate
+/// the stops triggered by one last-price update in the same sequence, or
+/// the resulting cascades diverge. The post-match stop scan must activate
+/// them:
+///
+/// 1. By trigger price in the direction of the move: ascending for a rising
+///    price (buy stops), descending for a falling price (sell stops)
+/// 2. Then by order ID, ascending
+///
+/// Each activation is matched to completion before the next is considered,
+/// and stops triggered by that match join the same ordered scan.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum OrderType {
     /// Limit order - executes at specified price or better
     #[default]
     Limit,
+    /// Market order - sweeps the opposite side at any price and never rests
+    Market,
 }
 
 impl OrderType {
@@ -82,6 +113,7 @@ impl OrderType {
     pub fn to_u8(self) -> u8 {
         match self {
             OrderType::Limit => 0,
+            OrderType::Market => 1,
         }
     }
     
@@ -89,6 +121,7 @@ impl OrderType {
     pub fn from_u8(value: u8) -> Option<Self> {
         match value {
             0 => Some(OrderType::Limit),
+            1 => Some(OrderType::Market),
             _ => None,
         }
     }
@@ -107,7 +140,7 @@ impl OrderType {
 /// ## SSZ Layout
 ///
 /// The struct is serialized as a fixed-size container:
-/// - Total size: 57 bytes (8+8+1+8+8+8+8+8 = 57)
+/// - Total size: 90 bytes (8+8+1+8+8+8+8+1+8+16+16 = 90)
 ///
 /// ## Example
 ///
@@ -154,6 +187,18 @@ pub struct Order {
     /// Order type as u8 (0=Limit)
     /// Stored as u8 for SSZ compatibility
     pub order_type_raw: u8,
+    
+    /// Total quantity executed over the order's lifetime (fixed-point)
+    /// Unlike `quantity - remaining`, this excludes size-reducing cancels
+    pub cumulative_filled: u64,
+    
+    /// Total executed notional over the order's lifetime
+    /// Sum of `price * quantity` per fill, scaled by 10^16
+    pub cumulative_notional: u128,
+    
+    /// Opaque client-chosen tag, echoed on every trade for this order
+    /// Never interpreted by the engine; all zeros if unset
+    pub client_tag: [u8; 16],
 }
 
 impl Order {
@@ -193,9 +238,88 @@ impl Order {
             remaining: quantity, // Initially, remaining = quantity
             timestamp,
             order_type_raw: OrderType::Limit.to_u8(),
+            cumulative_filled: 0,
+            cumulative_notional: 0,
+            client_tag: [0; 16],
+        }
+    }
+    
+    /// Create a new market order
+    ///
+    /// Market orders carry no limit price (stored as 0). They match against
+    /// the opposite side until filled or the book runs out, and any
+    /// remainder is discarded rather than resting.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::types::{Order, OrderType, Side};
+    ///
+    /// let order = Order::market(1, 100, Side::Sell, 100_000_000, 0);
+    /// assert_eq!(order.order_type(), OrderType::Market);
+    /// ```
+    pub fn market(id: u64, user_id: u64, side: Side, quantity: u64, timestamp: u64) -> Self {
+        Self {
+            order_type_raw: OrderType::Market.to_u8(),
+            ..Self::new(id, user_id, side, 0, quantity, timestamp)
         }
     }
     
+    /// Attach an opaque client tag
+    ///
+    /// The tag rides along with the order and is copied onto every trade
+    /// it takes part in, so a client can match fills to its own records.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let order = Order::new(1, 100, Side::Buy, 100_000_000, 100_000_000, 0).with_client_tag(*b"desk-7/quote-042");
+    /// assert_eq!(&order.client_tag, b"desk-7/quote-042");
+    /// ```
+    pub fn with_client_tag(mut self, tag: [u8; 16]) -> Self {
+        self.client_tag = tag;
+        self
+    }
+    
+    /// Stable identity hash of the order as submitted
+    ///
+    /// SHA-256 over the SSZ encoding of the order with its mutable fill
+    /// state (`remaining`, `cumulative_filled`, `cumulative_notional`)
+    /// zeroed, so the fingerprint is the same before and after any fill or
+    /// size reduction. Every other field, including the client tag, is
+    /// covered. An order submitted with ID 0 gets a different fingerprint
+    /// once the book assigns it an ID.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+    /// let mut partly_filled = order.clone();
+    /// partly_filled.remaining = 40_000_000;
+    ///
+    /// assert_eq!(order.fingerprint(), partly_filled.fingerprint());
+    /// ```
+    pub fn fingerprint(&self) -> [u8; 32] {
+        let submitted = Order {
+            remaining: 0,
+            cumulative_filled: 0,
+            cumulative_notional: 0,
+            ..self.clone()
+        };
+        let bytes = ssz_rs::serialize(&submitted).expect("fixed-size Order always serializes");
+        Sha256::digest(&bytes).into()
+    }
+    
+    /// Check if this is a market order
+    #[inline]
+    pub fn is_market(&self) -> bool {
+        self.order_type_raw == OrderType::Market.to_u8()
+    }
+    
     /// Get the order side
     pub fn side(&self) -> Side {
         Side::from_u8(self.side_raw).unwrap_or(Side::Buy)
@@ -235,6 +359,66 @@ impl Order {
         self.remaining = self.remaining.saturating_sub(actual_fill);
         actual_fill
     }
+    
+    /// Add an execution to the lifetime fill totals
+    ///
+    /// # Arguments
+    ///
+    /// * `quantity` - Executed quantity (fixed-point)
+    /// * `price` - Execution price (fixed-point)
+    pub fn record_fill(&mut self, quantity: u64, price: u64) {
+        self.cumulative_filled = self.cumulative_filled.saturating_add(quantity);
+        self.cumulative_notional = self
+            .cumulative_notional
+            .saturating_add(price as u128 * quantity as u128);
+    }
+    
+    /// Get the volume-weighted average fill price (fixed-point, truncated)
+    ///
+    /// # Returns
+    ///
+    /// The average price, or None if nothing has been filled
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::types::{Order, Side};
+    ///
+    /// let mut order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 300_000_000, 0);
+    /// order.record_fill(100_000_000, 4_900_000_000_000);
+    /// order.record_fill(200_000_000, 5_000_000_000_000);
+    ///
+    /// assert_eq!(order.average_fill_price(), Some(4_966_666_666_666));
+    /// ```
+    pub fn average_fill_price(&self) -> Option<u64> {
+        if self.cumulative_filled == 0 {
+            return None;
+        }
+        Some((self.cumulative_notional / self.cumulative_filled as u128) as u64)
+    }
+}
+
+/// One-line summary with human-readable price and size, for logs.
+///
+/// `Debug` keeps the raw fixed-point fields.
+///
+/// ```
+/// use dark_hypercore::types::{Order, Side};
+///
+/// let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+/// assert_eq!(order.to_string(), "Order#1 user=100 BUY 1.00000000 @ 50000.00000000");
+///
+/// let market = Order::market(2, 100, Side::Sell, 50_000_000, 0);
+/// assert_eq!(market.to_string(), "Order#2 user=100 SELL 0.50000000 @ MARKET");
+/// ```
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Order#{} user={} {} {} @ ", self.id, self.user_id, self.side(), from_fixed(self.quantity))?;
+        match self.order_type() {
+            OrderType::Limit => f.write_str(&from_fixed(self.price)),
+            OrderType::Market => f.write_str("MARKET"),
+        }
+    }
 }
 
 // ============================================================================
@@ -244,6 +428,47 @@ impl Order {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
+    
+    #[test]
+    fn test_display() {
+        let order = Order::new(7, 100, Side::Sell, 5_000_012_345_678, 150_000_000, 0);
+        let line = order.to_string();
+        
+        assert!(line.contains("SELL"));
+        assert!(line.contains("@ 50000.12345678"));
+        assert_eq!(line, "Order#7 user=100 SELL 1.50000000 @ 50000.12345678");
+        assert_eq!(Side::Buy.to_string(), "BUY");
+    }
+    
+    #[test]
+    fn test_order_fingerprint() {
+        let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 1703577600000)
+            .with_client_tag([7; 16]);
+        let fingerprint = order.fingerprint();
+        
+        // Fill state does not change the identity
+        let mut filled = order.clone();
+        filled.remaining = 25_000_000;
+        filled.cumulative_filled = 75_000_000;
+        filled.cumulative_notional = 375_000_000_000_000_000_000;
+        assert_eq!(filled.fingerprint(), fingerprint);
+        
+        // Every immutable field does
+        let changed = [
+            Order { id: 2, ..order.clone() },
+            Order { user_id: 101, ..order.clone() },
+            Order { side_raw: Side::Sell.to_u8(), ..order.clone() },
+            Order { price: 5_000_000_000_001, ..order.clone() },
+            Order { quantity: 100_000_001, ..order.clone() },
+            Order { timestamp: 1703577600001, ..order.clone() },
+            Order { order_type_raw: OrderType::Market.to_u8(), ..order.clone() },
+            order.clone().with_client_tag([8; 16]),
+        ];
+        for other in &changed {
+            assert_ne!(other.fingerprint(), fingerprint, "{:?}", other);
+        }
+    }
     
     #[test]
     fn test_side_conversion() {
@@ -264,7 +489,9 @@ mod tests {
     fn test_order_type_conversion() {
         assert_eq!(OrderType::Limit.to_u8(), 0);
         assert_eq!(OrderType::from_u8(0), Some(OrderType::Limit));
-        assert_eq!(OrderType::from_u8(1), None);
+        assert_eq!(OrderType::Market.to_u8(), 1);
+        assert_eq!(OrderType::from_u8(1), Some(OrderType::Market));
+        assert_eq!(OrderType::from_u8(2), None);
     }
     
     #[test]
@@ -354,9 +581,10 @@ mod tests {
         let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
         let bytes = ssz_rs::serialize(&order).expect("Failed to serialize");
         
-        // Expected size: 8+8+1+8+8+8+8+1 = 50 bytes
-        // (id + user_id + side_raw + price + quantity + remaining + timestamp + order_type_raw)
-        assert_eq!(bytes.len(), 50, "Order should serialize to 50 bytes");
+        // Expected size: 8+8+1+8+8+8+8+1+8+16+16 = 90 bytes
+        // (id + user_id + side_raw + price + quantity + remaining + timestamp + order_type_raw
+        //  + cumulative_filled + cumulative_notional + client_tag)
+        assert_eq!(bytes.len(), 90, "Order should serialize to 90 bytes");
     }
 }
 
@@ -29,6 +29,9 @@
 //! assert_eq!(s, "50000.12345678");
 //! ```
 
+use alloc::format;
+use alloc::string::{String, ToString};
+
 use rust_decimal::prelude::*;
 use rust_decimal::Decimal;
 
@@ -42,6 +45,31 @@ pub const SCALE: u64 = 100_000_000;
 /// u64::MAX / SCALE ≈ 184,467,440,737 (184 billion)
 pub const MAX_VALUE: u64 = u64::MAX / SCALE;
 
+/// [`MAX_VALUE`] in fixed-point: the largest whole-unit price or quantity
+/// that round-trips through `SCALE` (184,467,440,737.00000000)
+pub const MAX_FIXED: u64 = MAX_VALUE * SCALE;
+
+/// Largest checked notional (`price * quantity`, fixed-point quote units)
+///
+/// `i64::MAX` in fixed-point, about 92,233,720,368.5 quote units. A fee of
+/// up to 10,000 bps (100%) on a notional this size still fits in the
+/// `i64` fee fields, so no fee saturates.
+pub const MAX_NOTIONAL: u64 = i64::MAX as u64;
+
+/// Number of decimal places represented by SCALE
+const SCALE_DP: usize = 8;
+
+/// How to treat decimal input with more than 8 decimal places
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum PrecisionPolicy {
+    /// Refuse the input (default)
+    #[default]
+    Reject,
+    
+    /// Truncate toward zero to 8 decimal places
+    Floor,
+}
+
 // ============================================================================
 // Conversion Functions
 // ============================================================================
@@ -71,6 +99,65 @@ pub fn to_fixed(s: &str) -> Option<u64> {
     decimal_to_fixed(decimal)
 }
 
+/// Convert a decimal string to fixed-point u64, refusing over-precision
+///
+/// Unlike [`to_fixed`], which rounds, input with more than 8 significant
+/// decimal places is rejected so no value is ever silently changed.
+/// Trailing zeros do not count.
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::types::price::to_fixed_strict;
+///
+/// assert_eq!(to_fixed_strict("50000.12345678"), Some(5_000_012_345_678));
+/// assert_eq!(to_fixed_strict("1.5000000000"), Some(150_000_000));
+/// assert_eq!(to_fixed_strict("50000.123456789"), None);
+/// ```
+pub fn to_fixed_strict(s: &str) -> Option<u64> {
+    let decimal = Decimal::from_str(s).ok()?;
+    if decimal.normalize().scale() as usize > SCALE_DP {
+        return None;
+    }
+    decimal_to_fixed(decimal)
+}
+
+/// Convert a decimal quantity to fixed-point, refusing over-precision
+///
+/// Same as [`to_fixed_strict`], and also rejects zero, which is never a
+/// valid order quantity.
+pub fn quantity_to_fixed_strict(s: &str) -> Option<u64> {
+    quantity_to_fixed(s, PrecisionPolicy::Reject)
+}
+
+/// Convert a decimal quantity to fixed-point under a precision policy
+///
+/// # Returns
+///
+/// * `Some(u64)` - The positive fixed-point quantity
+/// * `None` - If parsing fails, the value is out of range, the input is
+///   over-precise under [`PrecisionPolicy::Reject`], or the result is zero
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::types::price::{quantity_to_fixed, PrecisionPolicy};
+///
+/// assert_eq!(quantity_to_fixed("1.123456789", PrecisionPolicy::Reject), None);
+/// assert_eq!(quantity_to_fixed("1.123456789", PrecisionPolicy::Floor), Some(112_345_678));
+/// assert_eq!(quantity_to_fixed("0.000000009", PrecisionPolicy::Floor), None);
+/// ```
+pub fn quantity_to_fixed(s: &str, policy: PrecisionPolicy) -> Option<u64> {
+    let quantity = match policy {
+        PrecisionPolicy::Reject => to_fixed_strict(s)?,
+        PrecisionPolicy::Floor => {
+            let decimal = Decimal::from_str(s).ok()?;
+            decimal_to_fixed(decimal.round_dp_with_strategy(SCALE_DP as u32, RoundingStrategy::ToZero))?
+        }
+    };
+    (quantity > 0).then_some(quantity)
+}
+
 /// Convert a Decimal to fixed-point u64
 ///
 /// # Arguments
@@ -144,6 +231,44 @@ pub fn from_fixed_trimmed(value: u64) -> String {
     s
 }
 
+/// Convert fixed-point u64 to a string with `dp` decimal places
+///
+/// Rounds half-up on the first dropped digit using integer arithmetic, so
+/// the result is deterministic. Storage precision is unchanged; asking for
+/// more than 8 places pads with zeros.
+///
+/// # Arguments
+///
+/// * `value` - Fixed-point value
+/// * `dp` - Number of decimal places to display
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::types::price::from_fixed_dp;
+///
+/// assert_eq!(from_fixed_dp(5_000_012_345_678, 2), "50000.12");
+/// assert_eq!(from_fixed_dp(5_000_012_500_000, 2), "50000.13");
+/// assert_eq!(from_fixed_dp(5_000_012_345_678, 0), "50000");
+/// ```
+pub fn from_fixed_dp(value: u64, dp: usize) -> String {
+    if dp >= SCALE_DP {
+        let (int, frac) = (value / SCALE, value % SCALE);
+        return format!("{}.{:08}{}", int, frac, "0".repeat(dp - SCALE_DP));
+    }
+    
+    let step = 10u128.pow((SCALE_DP - dp) as u32);
+    let unit = 10u128.pow(dp as u32);
+    let rounded = (value as u128 + step / 2) / step;
+    let (int, frac) = (rounded / unit, rounded % unit);
+    
+    if dp == 0 {
+        int.to_string()
+    } else {
+        format!("{}.{:0width$}", int, frac, width = dp)
+    }
+}
+
 // ============================================================================
 // Arithmetic Functions (using rust_decimal for safety)
 // ============================================================================
@@ -242,6 +367,59 @@ pub fn checked_sub(a: u64, b: u64) -> Option<u64> {
     a.checked_sub(b)
 }
 
+/// Compute `a * b / divisor` at full precision, rounding down
+///
+/// The product is taken in u128, so it cannot overflow for any pair of
+/// u64 inputs, and neither can the quotient. Used to scale fixed-point
+/// products back by [`SCALE`].
+///
+/// # Returns
+///
+/// * `Some(u128)` - `floor(a * b / divisor)`
+/// * `None` - If divisor is zero
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::types::price::{mul_div, SCALE};
+///
+/// // 50000.0 * 0.5 = 25000.0
+/// assert_eq!(mul_div(5_000_000_000_000, 50_000_000, SCALE), Some(2_500_000_000_000));
+/// ```
+pub fn mul_div(a: u64, b: u64, divisor: u64) -> Option<u128> {
+    (a as u128 * b as u128).checked_div(divisor as u128)
+}
+
+/// Midpoint of two fixed-point prices, rounding half up
+///
+/// When `a + b` is odd the exact midpoint falls on half a unit (half of
+/// 10^-8); it always rounds up to the next representable price. The
+/// rule is the same on every platform and independent of argument order,
+/// so `midpoint(a, b) == midpoint(b, a)`. Never overflows.
+///
+/// # Arguments
+///
+/// * `a` - First price
+/// * `b` - Second price
+///
+/// # Returns
+///
+/// `ceil((a + b) / 2)`
+///
+/// # Example
+///
+/// ```
+/// use dark_hypercore::types::price::midpoint;
+///
+/// assert_eq!(midpoint(5_000_000_000_000, 5_000_000_000_002), 5_000_000_000_001);
+/// // 50000.00000001 and 50000.00000002: the half unit rounds up
+/// assert_eq!(midpoint(5_000_000_000_001, 5_000_000_000_002), 5_000_000_000_002);
+/// ```
+pub fn midpoint(a: u64, b: u64) -> u64 {
+    // Widened so the sum cannot overflow; the result lies within [a, b]
+    (a as u128 + b as u128).div_ceil(2) as u64
+}
+
 // ============================================================================
 // Comparison Helpers
 // ============================================================================
@@ -278,6 +456,14 @@ mod tests {
         assert_eq!(SCALE, 100_000_000);
     }
     
+    #[test]
+    fn test_mul_div() {
+        assert_eq!(mul_div(3, 7, 2), Some(10));
+        assert_eq!(mul_div(MAX_FIXED, MAX_FIXED, SCALE), Some(MAX_FIXED as u128 * MAX_VALUE as u128));
+        assert_eq!(mul_div(u64::MAX, u64::MAX, 1), Some(u64::MAX as u128 * u64::MAX as u128));
+        assert_eq!(mul_div(1, 1, 0), None);
+    }
+    
     #[test]
     fn test_to_fixed_basic() {
         assert_eq!(to_fixed("1.0"), Some(100_000_000));
@@ -300,6 +486,24 @@ mod tests {
         assert_eq!(to_fixed(""), None);
     }
     
+    #[test]
+    fn test_over_precise_quantity() {
+        // Rejected under strict, floored under lenient, the same every time
+        for _ in 0..3 {
+            assert_eq!(quantity_to_fixed_strict("1.123456789"), None);
+            assert_eq!(quantity_to_fixed("1.123456789", PrecisionPolicy::Floor), Some(112_345_678));
+            assert_eq!(quantity_to_fixed("1.123456789999", PrecisionPolicy::Floor), Some(112_345_678));
+        }
+        
+        assert_eq!(quantity_to_fixed_strict("1.12345678"), Some(112_345_678));
+        assert_eq!(quantity_to_fixed_strict("0"), None);
+        assert_eq!(quantity_to_fixed_strict("-1"), None);
+        assert_eq!(to_fixed_strict("0"), Some(0));
+        
+        // The rounding parser still rounds
+        assert_eq!(to_fixed("1.123456789"), Some(112_345_679));
+    }
+    
     #[test]
     fn test_from_fixed() {
         assert_eq!(from_fixed(100_000_000), "1.00000000");
@@ -316,6 +520,24 @@ mod tests {
         assert_eq!(from_fixed_trimmed(123_456_789), "1.23456789");
     }
     
+    #[test]
+    fn test_from_fixed_dp() {
+        assert_eq!(from_fixed_dp(5_000_012_345_678, 2), "50000.12");
+        
+        // Half-up at the boundary
+        assert_eq!(from_fixed_dp(5_000_012_499_999, 2), "50000.12");
+        assert_eq!(from_fixed_dp(5_000_012_500_000, 2), "50000.13");
+        
+        // Rounding carries into the integer part
+        assert_eq!(from_fixed_dp(99_999_500_000, 2), "1000.00");
+        assert_eq!(from_fixed_dp(150_000_000, 0), "2");
+        
+        assert_eq!(from_fixed_dp(1, 8), "0.00000001");
+        assert_eq!(from_fixed_dp(1, 10), "0.0000000100");
+        assert_eq!(from_fixed_dp(0, 3), "0.000");
+        assert_eq!(from_fixed_dp(u64::MAX, 2), "184467440737.10");
+    }
+    
     #[test]
     fn test_roundtrip() {
         let values = ["1.0", "0.5", "50000.12345678", "0.00000001", "123456.78901234"];
@@ -379,6 +601,23 @@ mod tests {
         assert_eq!(checked_sub(0, 1), None);
     }
     
+    #[test]
+    fn test_midpoint() {
+        // 50000.00000001 and 50000.00000002 round half up, in either order
+        assert_eq!(midpoint(5_000_000_000_001, 5_000_000_000_002), 5_000_000_000_002);
+        assert_eq!(midpoint(5_000_000_000_002, 5_000_000_000_001), 5_000_000_000_002);
+        
+        // Even sums are exact
+        assert_eq!(midpoint(5_000_000_000_000, 5_100_000_000_000), 5_050_000_000_000);
+        assert_eq!(midpoint(7, 7), 7);
+        
+        // Stays between its arguments at the extremes
+        assert_eq!(midpoint(u64::MAX, u64::MAX), u64::MAX);
+        assert_eq!(midpoint(u64::MAX - 1, u64::MAX), u64::MAX);
+        assert_eq!(midpoint(0, 1), 1);
+        assert_eq!(midpoint(0, u64::MAX), 1 << 63);
+    }
+    
     #[test]
     fn test_approx_eq() {
         assert!(approx_eq(100, 100, 0));
@@ -3,9 +3,16 @@
 //! The ExecutionReceipt provides a summary of a batch of order operations,
 //! including the state root for verification.
 
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
 use ssz_rs::prelude::*;
 use sha2::{Sha256, Digest};
 
+use crate::types::{Order, Side, Trade};
+
 /// Execution receipt summarizing a batch of processed orders.
 ///
 /// ## Purpose
@@ -126,6 +133,175 @@ impl ExecutionReceipt {
     }
 }
 
+// ============================================================================
+// Receipt Builder
+// ============================================================================
+
+/// Per-user fill totals across a batch.
+///
+/// Notional values use the raw trade notional (`price * quantity`, scaled
+/// by 10^16, see [`Trade::notional_raw`]) so no rounding is involved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct UserFillSummary {
+    /// User/account ID
+    pub user_id: u64,
+    
+    /// Total quantity bought (fixed-point)
+    pub bought_quantity: u64,
+    
+    /// Total quantity sold (fixed-point)
+    pub sold_quantity: u64,
+    
+    /// Cash flow: notional received from sells minus notional paid for buys
+    pub net_notional: i128,
+}
+
+/// Accumulates processed orders and trades into an [`ExecutionReceipt`].
+///
+/// ## Example
+///
+/// ```
+/// use dark_hypercore::types::{ExecutionReceiptBuilder, Order, Side, Trade};
+///
+/// let mut builder = ExecutionReceiptBuilder::new(1);
+///
+/// // User 200 buys 1.0 from resting seller 100 at 50000.0
+/// let taker = Order::new(2, 200, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+/// let trade = Trade::new(1, 1, 2, 100, 200, 5_000_000_000_000, 100_000_000, 0);
+/// builder.record_order(&taker, &[trade]);
+///
+/// let summaries = builder.user_summaries();
+/// assert_eq!(summaries[0].user_id, 100);
+/// assert_eq!(summaries[0].sold_quantity, 100_000_000);
+/// assert_eq!(summaries[1].bought_quantity, 100_000_000);
+///
+/// let receipt = builder.build([0u8; 32], 0);
+/// assert_eq!(receipt.trades_executed, 1);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionReceiptBuilder {
+    /// Batch sequence number
+    batch_id: u64,
+    
+    /// Orders recorded so far
+    orders_processed: u64,
+    
+    /// Trades recorded so far
+    trades_executed: u64,
+    
+    /// Raw notional of every trade recorded so far (scaled by 10^16)
+    total_notional: u128,
+    
+    /// Whether `total_notional` overflowed u128
+    notional_overflow: bool,
+    
+    /// Per-user totals, keyed by user ID for deterministic ordering
+    users: BTreeMap<u64, UserFillSummary>,
+}
+
+impl ExecutionReceiptBuilder {
+    /// Create an empty builder for a batch
+    pub fn new(batch_id: u64) -> Self {
+        Self {
+            batch_id,
+            ..Self::default()
+        }
+    }
+    
+    /// Record one processed order and the trades it generated
+    ///
+    /// # Arguments
+    ///
+    /// * `taker` - The incoming order (its side gives each trade's direction)
+    /// * `trades` - Trades where `taker` was the taker
+    pub fn record_order(&mut self, taker: &Order, trades: &[Trade]) {
+        self.orders_processed += 1;
+        self.trades_executed += trades.len() as u64;
+        
+        let taker_side = taker.side();
+        for trade in trades {
+            match self.total_notional.checked_add(trade.notional_raw()) {
+                Some(total) => self.total_notional = total,
+                None => self.notional_overflow = true,
+            }
+            self.record_fill(trade.taker_user_id, taker_side, trade);
+            self.record_fill(trade.maker_user_id, taker_side.opposite(), trade);
+        }
+    }
+    
+    /// Add one side of a trade to a user's totals
+    fn record_fill(&mut self, user_id: u64, side: Side, trade: &Trade) {
+        let summary = self.users.entry(user_id).or_insert(UserFillSummary {
+            user_id,
+            ..UserFillSummary::default()
+        });
+        let notional = trade.notional_raw() as i128;
+        
+        match side {
+            Side::Buy => {
+                summary.bought_quantity += trade.quantity;
+                summary.net_notional -= notional;
+            }
+            Side::Sell => {
+                summary.sold_quantity += trade.quantity;
+                summary.net_notional += notional;
+            }
+        }
+    }
+    
+    /// Get the number of orders recorded
+    #[inline]
+    pub fn orders_processed(&self) -> u64 {
+        self.orders_processed
+    }
+    
+    /// Get the number of trades recorded
+    #[inline]
+    pub fn trades_executed(&self) -> u64 {
+        self.trades_executed
+    }
+    
+    /// Get the raw notional traded across the batch (scaled by 10^16)
+    ///
+    /// Each trade counts once. Accumulated in u128: a single trade's raw
+    /// notional can already exceed `u64::MAX`, so a u64 total would wrap on
+    /// any high-volume batch.
+    ///
+    /// # Returns
+    ///
+    /// The total, or None if it does not fit in u128. A trade of
+    /// `MAX_VALUE` at `MAX_VALUE` is already close to `u128::MAX`, so two
+    /// of them overflow.
+    #[inline]
+    pub fn total_notional_u128(&self) -> Option<u128> {
+        (!self.notional_overflow).then_some(self.total_notional)
+    }
+    
+    /// Get per-user fill totals, sorted by user ID
+    ///
+    /// A self-trade counts toward both bought and sold quantity for the
+    /// same user, netting to zero notional.
+    pub fn user_summaries(&self) -> Vec<UserFillSummary> {
+        self.users.values().copied().collect()
+    }
+    
+    /// Finish the batch into a receipt
+    ///
+    /// # Arguments
+    ///
+    /// * `state_root` - 32-byte hash of the order book state after the batch
+    /// * `timestamp` - Completion timestamp in milliseconds
+    pub fn build(&self, state_root: [u8; 32], timestamp: u64) -> ExecutionReceipt {
+        ExecutionReceipt::new(
+            self.batch_id,
+            self.orders_processed,
+            self.trades_executed,
+            state_root,
+            timestamp,
+        )
+    }
+}
+
 // ============================================================================
 // Unit Tests
 // ============================================================================
@@ -250,6 +426,69 @@ mod tests {
         assert_eq!(bytes.len(), 64, "ExecutionReceipt should serialize to 64 bytes");
     }
     
+    #[test]
+    fn test_builder_user_summaries() {
+        let mut builder = ExecutionReceiptBuilder::new(7);
+        
+        // User 20 buys 1.0 @ 50000 from user 10
+        let buy = Order::new(2, 20, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+        let t1 = Trade::new(1, 1, 2, 10, 20, 5_000_000_000_000, 100_000_000, 0);
+        builder.record_order(&buy, core::slice::from_ref(&t1));
+        
+        // User 20 sells 0.5 @ 51000 into user 10's bid
+        let sell = Order::new(4, 20, Side::Sell, 5_100_000_000_000, 50_000_000, 0);
+        let t2 = Trade::new(2, 3, 4, 10, 20, 5_100_000_000_000, 50_000_000, 0);
+        builder.record_order(&sell, core::slice::from_ref(&t2));
+        
+        // An order that only rests still counts as processed
+        builder.record_order(&Order::new(5, 30, Side::Buy, 4_000_000_000_000, 100_000_000, 0), &[]);
+        
+        let summaries = builder.user_summaries();
+        assert_eq!(summaries.len(), 2);
+        let (a, b) = (summaries[0], summaries[1]);
+        assert_eq!((a.user_id, b.user_id), (10, 20));
+        
+        // Equal and opposite
+        assert_eq!(a.sold_quantity, b.bought_quantity);
+        assert_eq!(a.bought_quantity, b.sold_quantity);
+        assert_eq!(b.bought_quantity, 100_000_000);
+        assert_eq!(b.sold_quantity, 50_000_000);
+        assert_eq!(a.net_notional + b.net_notional, 0);
+        assert_eq!(b.net_notional, -((t1.notional_raw() - t2.notional_raw()) as i128));
+        
+        assert_eq!(builder.total_notional_u128(), Some(t1.notional_raw() + t2.notional_raw()));
+        
+        let receipt = builder.build([1u8; 32], 99);
+        assert_eq!(receipt.batch_id, 7);
+        assert_eq!(receipt.orders_processed, 3);
+        assert_eq!(receipt.trades_executed, 2);
+    }
+    
+    #[test]
+    fn test_total_notional_does_not_wrap() {
+        use crate::types::price::MAX_FIXED;
+        
+        // Each trade is the smallest quantity at MAX_VALUE: raw notional just under u64::MAX
+        let mut builder = ExecutionReceiptBuilder::new(1);
+        let taker = Order::new(0, 20, Side::Buy, MAX_FIXED, 1, 0);
+        let trade = Trade::new(1, 1, 2, 10, 20, MAX_FIXED, 1, 0);
+        let per_trade = trade.notional_raw();
+        for _ in 0..10_000 {
+            builder.record_order(&taker, core::slice::from_ref(&trade));
+        }
+        
+        assert_eq!(builder.total_notional_u128(), Some(per_trade * 10_000));
+        
+        // Two trades of MAX_VALUE at MAX_VALUE exceed u128: reported, not saturated
+        for user in [30, 40] {
+            let extreme = Trade::new(2, 1, 2, user, user + 1, MAX_FIXED, MAX_FIXED, 0);
+            assert!(builder.total_notional_u128().is_some());
+            builder.record_order(&taker, core::slice::from_ref(&extreme));
+        }
+        assert_eq!(builder.total_notional_u128(), None);
+        assert_eq!(builder.trades_executed(), 10_002);
+    }
+    
     #[test]
     fn test_receipt_state_root_is_32_bytes() {
         let receipt = ExecutionReceipt::default();
@@ -4,9 +4,72 @@
 //!
 //! Trades are serialized using SSZ for deterministic encoding.
 //! This ensures identical state roots across all validators.
+//!
+//! ## Trade Batches
+//!
+//! Trades persisted for settlement are written as a batch whose header
+//! carries [`Trade::SCHEMA_VERSION`], so a reader built against a different
+//! `Trade` layout refuses the batch instead of misparsing it:
+//!
+//! ```text
+//! header:  schema_version u16 | trade_count u64
+//! trade:   trade (SSZ), in order
+//! ```
+//!
+//! Integers are little-endian, as in SSZ.
+
+use alloc::vec;
+use alloc::vec::Vec;
 
 use ssz_rs::prelude::*;
 
+use core::fmt;
+
+use crate::types::price::{from_fixed, mul_div, SCALE};
+use crate::types::Side;
+
+/// Encoded size of a trade batch header
+const BATCH_HEADER_LEN: usize = 2 + 8;
+
+/// SSZ size of a `Trade` (fixed-size container)
+const TRADE_SSZ_LEN: usize = 8 * 8 + 16 + 16;
+
+/// Error decoding a trade batch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TradeBatchError {
+    /// Input is shorter than the batch header
+    TruncatedHeader { expected: usize, actual: usize },
+    
+    /// Batch was written with a `Trade` layout this build cannot read
+    UnsupportedSchemaVersion { found: u16, supported: u16 },
+    
+    /// Body size does not match the header's trade count
+    BadLength { expected: usize, actual: usize },
+    
+    /// Trade record at this index failed to decode
+    BadTrade(usize),
+}
+
+impl fmt::Display for TradeBatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TruncatedHeader { expected, actual } => {
+                write!(f, "trade batch header truncated: need {expected} bytes, got {actual}")
+            }
+            Self::UnsupportedSchemaVersion { found, supported } => {
+                write!(f, "unsupported trade schema version {found} (this build reads {supported})")
+            }
+            Self::BadLength { expected, actual } => {
+                write!(f, "bad trade batch length: need {expected} bytes, got {actual}")
+            }
+            Self::BadTrade(index) => write!(f, "trade record {index} is malformed"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for TradeBatchError {}
+
 /// A trade represents a single match between a maker and taker order.
 ///
 /// ## Terminology
@@ -61,9 +124,63 @@ pub struct Trade {
     
     /// Execution timestamp in milliseconds
     pub timestamp: u64,
+    
+    /// Maker order's client tag (see `Order::client_tag`)
+    pub maker_client_tag: [u8; 16],
+    
+    /// Taker order's client tag (see `Order::client_tag`)
+    pub taker_client_tag: [u8; 16],
+}
+
+/// A trade with every counterparty identifier removed.
+///
+/// For public tapes: carries only what the market needs (price, size,
+/// aggressor side, time) and nothing that links the print to a user or
+/// an order.
+///
+/// ## Example
+///
+/// ```
+/// use dark_hypercore::types::{Side, Trade};
+///
+/// let trade = Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0);
+/// let anon = trade.anonymized(Side::Buy);
+///
+/// assert_eq!(anon.price, trade.price);
+/// assert_eq!(anon.aggressor_side(), Some(Side::Buy));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Default, SimpleSerialize)]
+pub struct AnonTrade {
+    /// Execution price in fixed-point (scaled by 10^8)
+    pub price: u64,
+    
+    /// Executed quantity in fixed-point (scaled by 10^8)
+    pub quantity: u64,
+    
+    /// Taker (aggressor) side as u8 (0=Buy, 1=Sell)
+    pub aggressor_side_raw: u8,
+    
+    /// Execution timestamp in milliseconds
+    pub timestamp: u64,
+}
+
+impl AnonTrade {
+    /// Get the aggressor side
+    ///
+    /// None if `aggressor_side_raw` is not a valid side, as in a corrupt
+    /// or foreign print, rather than guessing one.
+    #[inline]
+    pub fn aggressor_side(&self) -> Option<Side> {
+        Side::from_u8(self.aggressor_side_raw)
+    }
 }
 
 impl Trade {
+    /// Version of the `Trade` layout written in batch headers
+    ///
+    /// Bump this whenever a field is added, removed or reordered.
+    pub const SCHEMA_VERSION: u16 = 1;
+    
     /// Create a new trade
     ///
     /// # Arguments
@@ -76,6 +193,7 @@ impl Trade {
     /// * `price` - Execution price (fixed-point, scaled by 10^8)
     /// * `quantity` - Execution quantity (fixed-point, scaled by 10^8)
     /// * `timestamp` - Execution timestamp in milliseconds
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         id: u64,
         maker_order_id: u64,
@@ -95,9 +213,18 @@ impl Trade {
             price,
             quantity,
             timestamp,
+            maker_client_tag: [0; 16],
+            taker_client_tag: [0; 16],
         }
     }
     
+    /// Set the maker and taker client tags echoed from their orders
+    pub fn with_client_tags(mut self, maker: [u8; 16], taker: [u8; 16]) -> Self {
+        self.maker_client_tag = maker;
+        self.taker_client_tag = taker;
+        self
+    }
+    
     /// Calculate the notional value of this trade (price * quantity)
     ///
     /// Note: This returns the value in fixed-point representation.
@@ -106,6 +233,164 @@ impl Trade {
     pub fn notional_raw(&self) -> u128 {
         (self.price as u128) * (self.quantity as u128)
     }
+    
+    /// Get the base and quote amounts that change hands
+    ///
+    /// The quote amount is rounded down to the nearest 10^-8, the same on
+    /// every node.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::types::Trade;
+    ///
+    /// // 2.0 @ 100.0 settles 2.0 base against 200.0 quote
+    /// let trade = Trade::new(1, 100, 200, 10, 20, 10_000_000_000, 200_000_000, 0);
+    /// let settlement = trade.settlement();
+    ///
+    /// assert_eq!(settlement.base_qty, 200_000_000);
+    /// assert_eq!(settlement.quote_qty, 20_000_000_000);
+    /// ```
+    pub fn settlement(&self) -> Settlement {
+        Settlement {
+            base_qty: self.quantity,
+            quote_qty: mul_div(self.price, self.quantity, SCALE).expect("SCALE is non-zero"),
+        }
+    }
+    
+    /// Check if the maker and taker are the same user (a self-match)
+    ///
+    /// A self-match is still a single trade of `quantity`. Per-user volume
+    /// aggregates should count it once for that user, not once per side.
+    #[inline]
+    pub fn is_self_trade(&self) -> bool {
+        self.maker_user_id == self.taker_user_id
+    }
+    
+    /// Strip user and order identifiers for a public feed
+    ///
+    /// A trade does not record which side was aggressive, so the caller
+    /// passes the taker's side (see [`MatchResult::anonymized_trades`]).
+    ///
+    /// [`MatchResult::anonymized_trades`]: crate::engine::MatchResult::anonymized_trades
+    pub fn anonymized(&self, aggressor: Side) -> AnonTrade {
+        AnonTrade {
+            price: self.price,
+            quantity: self.quantity,
+            aggressor_side_raw: aggressor.to_u8(),
+            timestamp: self.timestamp,
+        }
+    }
+    
+    /// Encode trades as a versioned batch (see the module docs for the layout)
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use dark_hypercore::types::Trade;
+    ///
+    /// let trades = vec![Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0)];
+    /// let bytes = Trade::encode_batch(&trades);
+    ///
+    /// assert_eq!(Trade::decode_batch(&bytes), Ok(trades));
+    /// ```
+    pub fn encode_batch(trades: &[Trade]) -> Vec<u8> {
+        let mut out = Vec::with_capacity(BATCH_HEADER_LEN + trades.len() * TRADE_SSZ_LEN);
+        out.extend_from_slice(&Self::SCHEMA_VERSION.to_le_bytes());
+        out.extend_from_slice(&(trades.len() as u64).to_le_bytes());
+        for trade in trades {
+            let bytes = ssz_rs::serialize(trade).expect("fixed-size Trade always serializes");
+            out.extend_from_slice(&bytes);
+        }
+        out
+    }
+    
+    /// Decode a batch written by [`Trade::encode_batch`]
+    ///
+    /// The schema version is checked before anything else, so a batch from
+    /// a build with a different `Trade` layout is rejected with
+    /// [`TradeBatchError::UnsupportedSchemaVersion`].
+    pub fn decode_batch(bytes: &[u8]) -> Result<Vec<Trade>, TradeBatchError> {
+        if bytes.len() < BATCH_HEADER_LEN {
+            return Err(TradeBatchError::TruncatedHeader {
+                expected: BATCH_HEADER_LEN,
+                actual: bytes.len(),
+            });
+        }
+        
+        let version = u16::from_le_bytes([bytes[0], bytes[1]]);
+        if version != Self::SCHEMA_VERSION {
+            return Err(TradeBatchError::UnsupportedSchemaVersion {
+                found: version,
+                supported: Self::SCHEMA_VERSION,
+            });
+        }
+        
+        let mut count = [0u8; 8];
+        count.copy_from_slice(&bytes[2..BATCH_HEADER_LEN]);
+        let count = u64::from_le_bytes(count);
+        
+        // Checked so a hostile count cannot overflow the length check
+        let expected = usize::try_from(count)
+            .ok()
+            .and_then(|n| n.checked_mul(TRADE_SSZ_LEN))
+            .and_then(|n| n.checked_add(BATCH_HEADER_LEN))
+            .unwrap_or(usize::MAX);
+        if bytes.len() != expected {
+            return Err(TradeBatchError::BadLength { expected, actual: bytes.len() });
+        }
+        
+        let mut trades = Vec::with_capacity(count as usize);
+        for (index, record) in bytes[BATCH_HEADER_LEN..].chunks_exact(TRADE_SSZ_LEN).enumerate() {
+            let trade = ssz_rs::deserialize::<Trade>(record).map_err(|_| TradeBatchError::BadTrade(index))?;
+            trades.push(trade);
+        }
+        Ok(trades)
+    }
+}
+
+/// Base and quote amounts a trade moves between maker and taker.
+///
+/// The buyer receives `base_qty` and pays `quote_qty`; the seller the
+/// reverse. Both are fixed-point (scaled by 10^8). `quote_qty` is a u128
+/// because `price * quantity` for values near `price::MAX_VALUE` does not
+/// fit in a u64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Settlement {
+    /// Traded quantity of the base asset (fixed-point)
+    pub base_qty: u64,
+    
+    /// `price * quantity` in the quote asset (fixed-point, rounded down)
+    pub quote_qty: u128,
+}
+
+/// One-line summary with human-readable price and size, for logs.
+///
+/// `Debug` keeps the raw fixed-point fields.
+///
+/// ```
+/// use dark_hypercore::types::Trade;
+///
+/// let trade = Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0);
+/// assert_eq!(
+///     trade.to_string(),
+///     "Trade#1 0.50000000 @ 50000.00000000 maker=Order#100 (user 10) taker=Order#200 (user 20)"
+/// );
+/// ```
+impl fmt::Display for Trade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Trade#{} {} @ {} maker=Order#{} (user {}) taker=Order#{} (user {})",
+            self.id,
+            from_fixed(self.quantity),
+            from_fixed(self.price),
+            self.maker_order_id,
+            self.maker_user_id,
+            self.taker_order_id,
+            self.taker_user_id,
+        )
+    }
 }
 
 // ============================================================================
@@ -115,6 +400,7 @@ impl Trade {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::ToString;
     
     #[test]
     fn test_trade_new() {
@@ -139,6 +425,43 @@ mod tests {
         assert_eq!(trade.timestamp, 1703577600000);
     }
     
+    #[test]
+    fn test_trade_batch_roundtrip() {
+        let trades = vec![
+            Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 1703577600000)
+                .with_client_tags([1; 16], [2; 16]),
+            Trade::new(2, 101, 200, 11, 20, 5_001_000_000_000, 25_000_000, 1703577600000),
+        ];
+        
+        let bytes = Trade::encode_batch(&trades);
+        assert_eq!(bytes.len(), BATCH_HEADER_LEN + 2 * TRADE_SSZ_LEN);
+        assert_eq!(Trade::decode_batch(&bytes), Ok(trades));
+        assert_eq!(Trade::decode_batch(&Trade::encode_batch(&[])), Ok(vec![]));
+    }
+    
+    #[test]
+    fn test_trade_batch_rejects_unknown_schema_version() {
+        let trades = vec![Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0)];
+        let mut bytes = Trade::encode_batch(&trades);
+        
+        // A future layout with an extra field: newer version, longer records
+        let future = Trade::SCHEMA_VERSION + 1;
+        bytes[0..2].copy_from_slice(&future.to_le_bytes());
+        bytes.extend_from_slice(&[0u8; 8]);
+        
+        let err = Trade::decode_batch(&bytes).unwrap_err();
+        assert_eq!(
+            err,
+            TradeBatchError::UnsupportedSchemaVersion { found: future, supported: Trade::SCHEMA_VERSION }
+        );
+        assert!(err.to_string().contains("unsupported trade schema version"));
+        
+        // Truncated inputs are rejected too
+        assert!(matches!(Trade::decode_batch(&bytes[..5]), Err(TradeBatchError::TruncatedHeader { .. })));
+        bytes[0..2].copy_from_slice(&Trade::SCHEMA_VERSION.to_le_bytes());
+        assert!(matches!(Trade::decode_batch(&bytes), Err(TradeBatchError::BadLength { .. })));
+    }
+    
     #[test]
     fn test_trade_notional() {
         let trade = Trade::new(
@@ -154,6 +477,27 @@ mod tests {
         assert_eq!(trade.notional_raw(), expected);
     }
     
+    #[test]
+    fn test_trade_settlement() {
+        // 0.5 @ 50000 settles 0.5 base against 25000 quote
+        let trade = Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0);
+        assert_eq!(
+            trade.settlement(),
+            Settlement { base_qty: 50_000_000, quote_qty: 2_500_000_000_000 }
+        );
+        assert_eq!(trade.settlement().quote_qty, trade.notional_raw() / SCALE as u128);
+        
+        // Sub-unit quote rounds down: 0.00000003 @ 0.5 is 0.000000015
+        let dust = Trade::new(2, 100, 200, 10, 20, 50_000_000, 3, 0);
+        assert_eq!(dust.settlement().quote_qty, 1);
+    }
+    
+    #[test]
+    fn test_trade_is_self_trade() {
+        assert!(Trade::new(1, 100, 200, 10, 10, 5_000_000_000_000, 50_000_000, 0).is_self_trade());
+        assert!(!Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0).is_self_trade());
+    }
+    
     #[test]
     fn test_trade_ssz_roundtrip() {
         let trade = Trade::new(
@@ -183,13 +527,40 @@ mod tests {
         assert_eq!(bytes1, bytes2, "SSZ serialization must be deterministic");
     }
     
+    #[test]
+    fn test_trade_anonymized() {
+        let trade = Trade::new(7, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 1703577600000);
+        let anon = trade.anonymized(Side::Sell);
+        
+        assert_eq!(anon, AnonTrade {
+            price: 5_000_000_000_000,
+            quantity: 50_000_000,
+            aggressor_side_raw: Side::Sell.to_u8(),
+            timestamp: 1703577600000,
+        });
+        assert_eq!(anon.aggressor_side(), Some(Side::Sell));
+        assert_eq!(AnonTrade { aggressor_side_raw: 2, ..anon.clone() }.aggressor_side(), None);
+        
+        // Fixed layout: 3 u64 + 1 u8, and nothing else to leak
+        let bytes = ssz_rs::serialize(&anon).expect("Failed to serialize");
+        assert_eq!(bytes.len(), 25);
+        assert_eq!(bytes, ssz_rs::serialize(&anon).expect("Failed to serialize"));
+        
+        // Different counterparties, same public print
+        let other = Trade::new(8, 300, 400, 30, 40, 5_000_000_000_000, 50_000_000, 1703577600000);
+        assert_eq!(bytes, ssz_rs::serialize(&other.anonymized(Side::Sell)).unwrap());
+        
+        let decoded: AnonTrade = ssz_rs::deserialize(&bytes).expect("Failed to deserialize");
+        assert_eq!(decoded, anon);
+    }
+    
     #[test]
     fn test_trade_ssz_size() {
         let trade = Trade::new(1, 100, 200, 10, 20, 5_000_000_000_000, 50_000_000, 0);
         let bytes = ssz_rs::serialize(&trade).expect("Failed to serialize");
         
-        // Expected size: 8 fields * 8 bytes = 64 bytes
-        assert_eq!(bytes.len(), 64, "Trade should serialize to 64 bytes");
+        // Expected size: 8 fields * 8 bytes + 2 client tags * 16 bytes = 96 bytes
+        assert_eq!(bytes.len(), 96, "Trade should serialize to 96 bytes");
     }
 }
 
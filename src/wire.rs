@@ -0,0 +1,441 @@
+//! Compact binary order-entry messages for gateways.
+//!
+//! A full SSZ [`Order`] carries engine-owned state (remaining size and
+//! lifetime fill totals). Client requests only need the fields a client
+//! chooses, including its opaque client tag, so they use a smaller fixed
+//! layout with a 1-byte message type up front.
+//!
+//! ## Layout
+//!
+//! All integers are little-endian, as in SSZ.
+//!
+//! | Message | Type | Body | Total |
+//! |---------|------|------|-------|
+//! | New order | `0x01` | id u64, user u64, side u8, order type u8, price u64, quantity u64, timestamp u64, client tag \[u8; 16\] | 59 bytes |
+//! | Cancel | `0x02` | order id u64, user u64 | 17 bytes |
+//! | L2 delta | `0x03` | seq u64, count u32, then per level: side u8, price u64, quantity u64 | 13 + 17 per level |
+//!
+//! New-order and cancel requests flow from clients to the engine. L2
+//! deltas flow the other way, as market data: each carries the book's
+//! [`current_seq`](crate::orderbook::CLOB::current_seq) and the new
+//! aggregate quantity of every level that changed, 0 meaning the level
+//! is gone. Applying them in order to an [`L2Book`] reproduces the
+//! source book's depth without shipping individual orders.
+//!
+//! Decoding validates every field, so anything it returns is safe to hand
+//! to the engine: the side and order type are known values, the quantity
+//! is non-zero, and a limit order has a non-zero price.
+//!
+//! ## Example
+//!
+//! ```
+//! use dark_hypercore::types::{Order, Side};
+//! use dark_hypercore::wire::{self, WireMessage};
+//!
+//! let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 7);
+//! let bytes = wire::encode_new_order(&order);
+//!
+//! assert_eq!(bytes.len(), wire::NEW_ORDER_LEN);
+//! assert_eq!(wire::decode(&bytes), Ok(WireMessage::NewOrder(order)));
+//! ```
+
+use std::collections::BTreeMap;
+
+use crate::orderbook::CLOB;
+use crate::types::{Order, OrderType, Side};
+
+/// Message type of a new-order request
+pub const NEW_ORDER: u8 = 0x01;
+
+/// Message type of a cancel request
+pub const CANCEL: u8 = 0x02;
+
+/// Encoded size of a new-order request
+pub const NEW_ORDER_LEN: usize = 1 + 8 + 8 + 1 + 1 + 8 + 8 + 8 + 16;
+
+/// Encoded size of a cancel request
+pub const CANCEL_LEN: usize = 1 + 8 + 8;
+
+/// Message type of an L2 delta
+pub const L2_DELTA: u8 = 0x03;
+
+/// Encoded size of an L2 delta header (type, seq, level count)
+pub const L2_DELTA_HEADER_LEN: usize = 1 + 8 + 4;
+
+/// Encoded size of one changed level in an L2 delta
+pub const L2_LEVEL_LEN: usize = 1 + 8 + 8;
+
+/// Request to cancel a resting order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CancelRequest {
+    /// Order to cancel
+    pub order_id: u64,
+    
+    /// Requesting user, for the gateway's ownership check
+    pub user_id: u64,
+}
+
+/// A decoded client message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WireMessage {
+    /// New order, ready for `MatchingEngine::match_order`
+    NewOrder(Order),
+    
+    /// Cancel request
+    Cancel(CancelRequest),
+}
+
+/// New aggregate quantity of one price level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct LevelUpdate {
+    /// Side of the level
+    pub side: Side,
+    
+    /// Level price (fixed-point)
+    pub price: u64,
+    
+    /// Total resting quantity now at the level (fixed-point), 0 if removed
+    pub quantity: u64,
+}
+
+/// Every level that changed between two L2 states.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct L2Delta {
+    /// Book sequence number the delta brings a replica up to
+    pub seq: u64,
+    
+    /// Changed levels: bids then asks, each by ascending price
+    pub levels: Vec<LevelUpdate>,
+}
+
+/// Aggregate quantity per price on each side: the state an L2 feed carries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct L2Book {
+    /// Bid price to total quantity
+    pub bids: BTreeMap<u64, u64>,
+    
+    /// Ask price to total quantity
+    pub asks: BTreeMap<u64, u64>,
+}
+
+impl L2Book {
+    /// Capture the aggregate levels of a book
+    pub fn from_clob(clob: &CLOB) -> Self {
+        let side = |side| {
+            clob.level_summaries(side, usize::MAX)
+                .into_iter()
+                .map(|level| (level.price, level.total_quantity))
+                .collect()
+        };
+        Self {
+            bids: side(Side::Buy),
+            asks: side(Side::Sell),
+        }
+    }
+    
+    /// Get the levels that differ from `self` in `next`, tagged with `seq`
+    pub fn diff(&self, next: &L2Book, seq: u64) -> L2Delta {
+        let mut levels = Vec::new();
+        for (side, old, new) in [(Side::Buy, &self.bids, &next.bids), (Side::Sell, &self.asks, &next.asks)] {
+            let mut changed: BTreeMap<u64, u64> = old
+                .keys()
+                .filter(|price| !new.contains_key(price))
+                .map(|&price| (price, 0))
+                .collect();
+            changed.extend(new.iter().filter(|(price, quantity)| old.get(price) != Some(quantity)));
+            levels.extend(changed.into_iter().map(|(price, quantity)| LevelUpdate { side, price, quantity }));
+        }
+        L2Delta { seq, levels }
+    }
+    
+    /// Apply a delta, inserting, updating or removing each level
+    pub fn apply(&mut self, delta: &L2Delta) {
+        for level in &delta.levels {
+            let side = match level.side {
+                Side::Buy => &mut self.bids,
+                Side::Sell => &mut self.asks,
+            };
+            if level.quantity == 0 {
+                side.remove(&level.price);
+            } else {
+                side.insert(level.price, level.quantity);
+            }
+        }
+    }
+}
+
+/// Error decoding a client message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum WireError {
+    /// The message has no type byte
+    #[error("empty message")]
+    Empty,
+    
+    /// The type byte is not a known message type
+    #[error("unknown message type {0:#04x}")]
+    UnknownMessageType(u8),
+    
+    /// The message is not exactly the size its type requires
+    #[error("bad message length: need {expected} bytes, got {actual}")]
+    BadLength { expected: usize, actual: usize },
+    
+    /// Side is not 0 (buy) or 1 (sell)
+    #[error("unknown side {0}")]
+    UnknownSide(u8),
+    
+    /// Order type is not 0 (limit) or 1 (market)
+    #[error("unknown order type {0}")]
+    UnknownOrderType(u8),
+    
+    /// Quantity is zero
+    #[error("quantity must be non-zero")]
+    ZeroQuantity,
+    
+    /// A limit order has a zero price
+    #[error("limit price must be non-zero")]
+    ZeroPrice,
+}
+
+// ============================================================================
+// Encoding
+// ============================================================================
+
+/// Encode a new-order request
+///
+/// Only client-chosen fields are written; `remaining` and the lifetime
+/// fill totals are reset when the message is decoded.
+pub fn encode_new_order(order: &Order) -> [u8; NEW_ORDER_LEN] {
+    let mut out = [0u8; NEW_ORDER_LEN];
+    out[0] = NEW_ORDER;
+    out[1..9].copy_from_slice(&order.id.to_le_bytes());
+    out[9..17].copy_from_slice(&order.user_id.to_le_bytes());
+    out[17] = order.side_raw;
+    out[18] = order.order_type_raw;
+    out[19..27].copy_from_slice(&order.price.to_le_bytes());
+    out[27..35].copy_from_slice(&order.quantity.to_le_bytes());
+    out[35..43].copy_from_slice(&order.timestamp.to_le_bytes());
+    out[43..59].copy_from_slice(&order.client_tag);
+    out
+}
+
+/// Encode an L2 delta
+pub fn encode_l2_delta(delta: &L2Delta) -> Vec<u8> {
+    let mut out = Vec::with_capacity(L2_DELTA_HEADER_LEN + delta.levels.len() * L2_LEVEL_LEN);
+    out.push(L2_DELTA);
+    out.extend_from_slice(&delta.seq.to_le_bytes());
+    out.extend_from_slice(&(delta.levels.len() as u32).to_le_bytes());
+    for level in &delta.levels {
+        out.push(level.side.to_u8());
+        out.extend_from_slice(&level.price.to_le_bytes());
+        out.extend_from_slice(&level.quantity.to_le_bytes());
+    }
+    out
+}
+
+/// Encode a cancel request
+pub fn encode_cancel(cancel: &CancelRequest) -> [u8; CANCEL_LEN] {
+    let mut out = [0u8; CANCEL_LEN];
+    out[0] = CANCEL;
+    out[1..9].copy_from_slice(&cancel.order_id.to_le_bytes());
+    out[9..17].copy_from_slice(&cancel.user_id.to_le_bytes());
+    out
+}
+
+// ============================================================================
+// Decoding
+// ============================================================================
+
+/// Decode any client message by its type byte
+pub fn decode(bytes: &[u8]) -> Result<WireMessage, WireError> {
+    match bytes.first() {
+        None => Err(WireError::Empty),
+        Some(&NEW_ORDER) => decode_new_order(bytes).map(WireMessage::NewOrder),
+        Some(&CANCEL) => decode_cancel(bytes).map(WireMessage::Cancel),
+        Some(&other) => Err(WireError::UnknownMessageType(other)),
+    }
+}
+
+/// Decode and validate a new-order request
+pub fn decode_new_order(bytes: &[u8]) -> Result<Order, WireError> {
+    check_header(bytes, NEW_ORDER, NEW_ORDER_LEN)?;
+    
+    let id = read_u64(bytes, 1);
+    let user_id = read_u64(bytes, 9);
+    let side = Side::from_u8(bytes[17]).ok_or(WireError::UnknownSide(bytes[17]))?;
+    let order_type = OrderType::from_u8(bytes[18]).ok_or(WireError::UnknownOrderType(bytes[18]))?;
+    let price = read_u64(bytes, 19);
+    let quantity = read_u64(bytes, 27);
+    let timestamp = read_u64(bytes, 35);
+    let mut client_tag = [0u8; 16];
+    client_tag.copy_from_slice(&bytes[43..59]);
+    
+    if quantity == 0 {
+        return Err(WireError::ZeroQuantity);
+    }
+    
+    let order = match order_type {
+        OrderType::Limit if price == 0 => return Err(WireError::ZeroPrice),
+        OrderType::Limit => Order::new(id, user_id, side, price, quantity, timestamp),
+        OrderType::Market => Order::market(id, user_id, side, quantity, timestamp),
+    };
+    Ok(order.with_client_tag(client_tag))
+}
+
+/// Decode a cancel request
+pub fn decode_cancel(bytes: &[u8]) -> Result<CancelRequest, WireError> {
+    check_header(bytes, CANCEL, CANCEL_LEN)?;
+    
+    Ok(CancelRequest {
+        order_id: read_u64(bytes, 1),
+        user_id: read_u64(bytes, 9),
+    })
+}
+
+/// Decode an L2 delta
+pub fn decode_l2_delta(bytes: &[u8]) -> Result<L2Delta, WireError> {
+    if bytes.len() < L2_DELTA_HEADER_LEN {
+        check_header(bytes, L2_DELTA, L2_DELTA_HEADER_LEN)?;
+    }
+    let count = u32::from_le_bytes([bytes[9], bytes[10], bytes[11], bytes[12]]) as usize;
+    check_header(bytes, L2_DELTA, L2_DELTA_HEADER_LEN + count * L2_LEVEL_LEN)?;
+    
+    let levels = bytes[L2_DELTA_HEADER_LEN..]
+        .chunks_exact(L2_LEVEL_LEN)
+        .map(|chunk| {
+            Ok(LevelUpdate {
+                side: Side::from_u8(chunk[0]).ok_or(WireError::UnknownSide(chunk[0]))?,
+                price: read_u64(chunk, 1),
+                quantity: read_u64(chunk, 9),
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    
+    Ok(L2Delta { seq: read_u64(bytes, 1), levels })
+}
+
+/// Check the type byte and exact length of a message
+fn check_header(bytes: &[u8], message_type: u8, expected: usize) -> Result<(), WireError> {
+    match bytes.first() {
+        None => Err(WireError::Empty),
+        Some(&found) if found != message_type => Err(WireError::UnknownMessageType(found)),
+        Some(_) if bytes.len() != expected => Err(WireError::BadLength { expected, actual: bytes.len() }),
+        Some(_) => Ok(()),
+    }
+}
+
+/// Read a little-endian u64 at `offset` (length already checked)
+#[inline]
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut word = [0u8; 8];
+    word.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(word)
+}
+
+// ============================================================================
+// Unit Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_new_order_roundtrip() {
+        let limit = Order::new(42, 100, Side::Sell, 5_000_000_000_000, 150_000_000, 1703577600000);
+        let market = Order::market(43, 101, Side::Buy, 25_000_000, 1703577600001).with_client_tag(*b"desk-7/quote-042");
+        
+        for order in [limit, market] {
+            let bytes = encode_new_order(&order);
+            assert_eq!(bytes[0], NEW_ORDER);
+            assert_eq!(bytes[43..], order.client_tag);
+            assert_eq!(decode_new_order(&bytes), Ok(order.clone()));
+            assert_eq!(decode(&bytes), Ok(WireMessage::NewOrder(order)));
+        }
+    }
+    
+    #[test]
+    fn test_cancel_roundtrip() {
+        let cancel = CancelRequest { order_id: 42, user_id: 100 };
+        let bytes = encode_cancel(&cancel);
+        
+        assert_eq!(bytes.len(), CANCEL_LEN);
+        assert_eq!(decode_cancel(&bytes), Ok(cancel));
+        assert_eq!(decode(&bytes), Ok(WireMessage::Cancel(cancel)));
+    }
+    
+    #[test]
+    fn test_l2_delta_series_rebuilds_depth() {
+        use crate::engine::MatchingEngine;
+        
+        let mut clob = CLOB::with_capacity(1_000);
+        let mut engine = MatchingEngine::new();
+        let mut replica = L2Book::default();
+        let mut published = L2Book::from_clob(&clob);
+        
+        // A deterministic mix of resting, crossing and cancelled orders
+        for i in 1..=300u64 {
+            let side = if i % 3 == 0 { Side::Sell } else { Side::Buy };
+            let price = 4_990_000_000_000 + (i * 7919 % 20) * 1_000_000_000;
+            engine.match_order(&mut clob, Order::new(i, i % 5, side, price, (i % 4 + 1) * 10_000_000, i), 0);
+            if i % 7 == 0 {
+                clob.cancel_order(i - 3);
+            }
+            
+            let current = L2Book::from_clob(&clob);
+            let delta = published.diff(&current, clob.current_seq());
+            let bytes = encode_l2_delta(&delta);
+            assert_eq!(bytes.len(), L2_DELTA_HEADER_LEN + delta.levels.len() * L2_LEVEL_LEN);
+            
+            let decoded = decode_l2_delta(&bytes).unwrap();
+            assert_eq!(decoded, delta);
+            replica.apply(&decoded);
+            published = current;
+        }
+        
+        assert_eq!(replica, L2Book::from_clob(&clob));
+        let bids: Vec<_> = replica.bids.iter().rev().map(|(&p, &q)| (p, q)).collect();
+        let asks: Vec<_> = replica.asks.iter().map(|(&p, &q)| (p, q)).collect();
+        let depth = |side| -> Vec<_> {
+            clob.level_summaries(side, usize::MAX).iter().map(|l| (l.price, l.total_quantity)).collect()
+        };
+        assert_eq!(bids, depth(Side::Buy));
+        assert_eq!(asks, depth(Side::Sell));
+        
+        // Bad input is rejected
+        let mut bad = encode_l2_delta(&L2Delta {
+            seq: 1,
+            levels: vec![LevelUpdate { side: Side::Buy, price: 1, quantity: 1 }],
+        });
+        assert_eq!(decode_l2_delta(&bad[..20]), Err(WireError::BadLength { expected: 30, actual: 20 }));
+        assert_eq!(decode_l2_delta(&bad[..5]), Err(WireError::BadLength { expected: 13, actual: 5 }));
+        bad[13] = 7;
+        assert_eq!(decode_l2_delta(&bad), Err(WireError::UnknownSide(7)));
+    }
+    
+    #[test]
+    fn test_decode_rejects_bad_messages() {
+        let order = Order::new(1, 100, Side::Buy, 5_000_000_000_000, 100_000_000, 0);
+        let good = encode_new_order(&order);
+        
+        assert_eq!(decode(&[]), Err(WireError::Empty));
+        assert_eq!(decode(&[0x7f, 0, 0]), Err(WireError::UnknownMessageType(0x7f)));
+        assert_eq!(decode(&good[..40]), Err(WireError::BadLength { expected: NEW_ORDER_LEN, actual: 40 }));
+        assert_eq!(decode_cancel(&good), Err(WireError::UnknownMessageType(NEW_ORDER)));
+        
+        let mut bad = good;
+        bad[17] = 2;
+        assert_eq!(decode(&bad), Err(WireError::UnknownSide(2)));
+        
+        let mut bad = good;
+        bad[18] = 9;
+        assert_eq!(decode(&bad), Err(WireError::UnknownOrderType(9)));
+        
+        let mut bad = good;
+        bad[27..35].copy_from_slice(&0u64.to_le_bytes());
+        assert_eq!(decode(&bad), Err(WireError::ZeroQuantity));
+        
+        let mut bad = good;
+        bad[19..27].copy_from_slice(&0u64.to_le_bytes());
+        assert_eq!(decode(&bad), Err(WireError::ZeroPrice));
+    }
+}
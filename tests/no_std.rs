@@ -0,0 +1,33 @@
+//! `no_std` build check.
+//!
+//! Builds the library with `--no-default-features` (core types only, as
+//! `no_std` + `alloc`) so a std-only import in `types` fails the test suite
+//! rather than an embedded or TEE build later.
+//!
+//! Run with: cargo test --test no_std
+
+use std::path::Path;
+use std::process::Command;
+
+#[test]
+fn types_build_without_std() {
+    let manifest_dir = env!("CARGO_MANIFEST_DIR");
+    let cargo = std::env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
+    
+    // Separate target dir so the check does not contend for the build lock
+    // held by the running `cargo test`
+    let target_dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join("no_std");
+    
+    let output = Command::new(cargo)
+        .args(["check", "--lib", "--no-default-features", "--offline"])
+        .current_dir(manifest_dir)
+        .env("CARGO_TARGET_DIR", target_dir)
+        .output()
+        .expect("failed to run cargo");
+    
+    assert!(
+        output.status.success(),
+        "no_std build failed:\n{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
@@ -5,6 +5,7 @@
 //! 2. System remains stable under high load
 //! 3. Determinism is preserved across runs
 //! 4. Memory usage is reasonable
+//! 5. Matching work (steps per order) stays within deterministic bounds
 //!
 //! ## Running Stress Tests
 //!
@@ -16,6 +17,7 @@
 //! cargo test --release --test stress_test stress_1m_orders -- --nocapture
 //! ```
 
+use std::collections::{BTreeMap, HashSet};
 use std::time::Instant;
 
 use dark_hypercore::{CLOB, MatchingEngine, Order, Side};
@@ -92,6 +94,61 @@ fn run_deterministic_sequence(seed: u64, count: usize) -> [u8; 32] {
     clob.compute_state_root()
 }
 
+/// Run a deterministic order sequence and histogram `MatchResult::steps`.
+///
+/// Also checks each order's loop-counted work against its trades: with no
+/// retained levels, every step is a fill or a level traded against.
+///
+/// Returns step count -> number of orders that took that many steps.
+fn work_histogram(seed: u64, count: usize) -> BTreeMap<usize, usize> {
+    let orders = generate_deterministic_orders(count, seed);
+    
+    let mut clob = CLOB::with_capacity(count * 2);
+    let mut engine = MatchingEngine::new();
+    let mut histogram = BTreeMap::new();
+    
+    for order in orders {
+        let result = engine.match_order(&mut clob, order, 0);
+        let mut prices: Vec<u64> = result.trades.iter().map(|t| t.price).collect();
+        prices.dedup();
+        assert_eq!(result.levels_consumed, prices.len());
+        assert_eq!(result.steps, result.trades.len() + result.levels_consumed);
+        *histogram.entry(result.steps).or_insert(0) += 1;
+    }
+    
+    histogram
+}
+
+/// Smallest step count at or below which `quantile` of orders fall.
+fn histogram_quantile(histogram: &BTreeMap<usize, usize>, quantile: f64) -> usize {
+    let total: usize = histogram.values().sum();
+    let target = (total as f64 * quantile).ceil() as usize;
+    
+    let mut seen = 0;
+    for (&steps, &orders) in histogram {
+        seen += orders;
+        if seen >= target {
+            return steps;
+        }
+    }
+    0
+}
+
+/// Assert two state roots are identical, printing both in hex on mismatch.
+///
+/// Used wherever a state root is derived two different ways (replay,
+/// incremental maintenance, etc.) so a divergence fails loudly with
+/// enough context to debug.
+fn assert_state_roots_match(context: &str, expected: [u8; 32], actual: [u8; 32]) {
+    assert!(
+        expected == actual,
+        "{}: state roots diverged\n  expected: {}\n  actual:   {}",
+        context,
+        hex::encode(expected),
+        hex::encode(actual)
+    );
+}
+
 // ============================================================================
 // STRESS TESTS
 // ============================================================================
@@ -105,6 +162,7 @@ fn run_deterministic_sequence(seed: u64, count: usize) -> [u8; 32] {
 /// # Verification
 /// - No panics during execution
 /// - State root is computed correctly
+/// - State root matches an independent replay of the same sequence
 /// - Trade count is positive (some matching occurred)
 #[test]
 fn stress_1m_orders() {
@@ -180,6 +238,13 @@ fn stress_1m_orders() {
         elapsed_secs, MAX_TIME_SECONDS);
     assert!(trade_count > 0, "Expected some trades to occur");
     
+    // Correctness guard: the root maintained through the run must equal
+    // a fresh replay of the same 1M orders
+    println!("\nReplaying sequence to verify state root...");
+    let replay_root = run_deterministic_sequence(42, STRESS_ORDER_COUNT);
+    assert_state_roots_match("stress_1m_orders replay", state_root, replay_root);
+    println!("  Replay root:       {}", hex::encode(replay_root));
+    
     println!("\n=== STRESS TEST PASSED ===\n");
 }
 
@@ -204,7 +269,7 @@ fn verify_determinism() {
     println!("  Run 2 state root: {}", hex::encode(root2));
     
     // Verify identical
-    assert_eq!(root1, root2, "State roots must match for determinism");
+    assert_state_roots_match("verify_determinism", root1, root2);
     
     // Also verify different seeds produce different roots
     let root3 = run_deterministic_sequence(SEED + 1, TEST_COUNT);
@@ -316,6 +381,278 @@ fn stress_cancellations() {
     println!("\n=== CANCELLATION TEST PASSED ===\n");
 }
 
+/// Test FIFO linked-list integrity with every order at one price.
+///
+/// Interleaves adds with cancels of the head, tail, and random middle nodes
+/// of a single level, checking the level aggregates against a model as it
+/// goes and the surviving queue order at the end.
+#[test]
+fn stress_same_price_fifo() {
+    println!("\n=== SAME-PRICE FIFO STRESS TEST ===\n");
+    
+    const OPERATION_COUNT: usize = 100_000;
+    const PRICE: u64 = 5_000_000_000_000;
+    
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let mut clob = CLOB::with_capacity(OPERATION_COUNT);
+    
+    // Model of the level: (order_id, quantity) in insertion order
+    let mut model: Vec<(u64, u64)> = Vec::new();
+    let mut next_id = 1u64;
+    let (mut adds, mut cancels) = (0usize, 0usize);
+    
+    for op in 0..OPERATION_COUNT {
+        if model.is_empty() || rng.gen_bool(0.55) {
+            let quantity: u64 = rng.gen_range(1..=100_000_000);
+            clob.add_order(Order::new(next_id, 1, Side::Buy, PRICE, quantity, op as u64));
+            model.push((next_id, quantity));
+            next_id += 1;
+            adds += 1;
+        } else {
+            // Head, tail, or anywhere in between
+            let index = match rng.gen_range(0..3) {
+                0 => 0,
+                1 => model.len() - 1,
+                _ => rng.gen_range(0..model.len()),
+            };
+            let (order_id, quantity) = model.remove(index);
+            let cancelled = clob.cancel_order(order_id).expect("modelled order rests");
+            assert_eq!(cancelled.remaining, quantity);
+            cancels += 1;
+        }
+        
+        if op % 10_000 == 0 {
+            let summary = clob.level_summary(Side::Buy, PRICE);
+            let expected_quantity: u64 = model.iter().map(|(_, q)| q).sum();
+            assert_eq!(summary.map_or(0, |l| l.order_count), model.len(), "order_count desync at op {}", op);
+            assert_eq!(summary.map_or(0, |l| l.total_quantity), expected_quantity, "total_quantity desync at op {}", op);
+        }
+    }
+    
+    clob.verify_invariants().expect("invariants hold after same-price churn");
+    
+    // Walk the level head to tail: survivors must be in insertion order
+    let mut queue = Vec::with_capacity(model.len());
+    if let Some(level) = clob.best_bid_level() {
+        let mut current = level.head;
+        while let Some(key) = current {
+            let node = &clob.orders()[key];
+            queue.push((node.order.id, node.order.remaining));
+            current = node.next;
+        }
+    }
+    assert_eq!(queue, model);
+    
+    println!("  Adds:              {:>12}", adds);
+    println!("  Cancels:           {:>12}", cancels);
+    println!("  Surviving orders:  {:>12}", model.len());
+    
+    println!("\n=== SAME-PRICE FIFO TEST PASSED ===\n");
+}
+
+/// Test one user repeatedly crossing their own orders across levels.
+///
+/// With no self-trade prevention, an incoming order consumes its own
+/// user's resting makers out of the same slab it is being matched against.
+/// Checks that every consumed maker was live exactly once (no double
+/// free), that resting quantity is conserved, and the book invariants.
+#[test]
+fn stress_self_cross() {
+    println!("\n=== SELF-CROSS STRESS TEST ===\n");
+    
+    const OPERATION_COUNT: usize = 50_000;
+    const BASE_PRICE: u64 = 5_000_000_000_000;
+    const TICK: u64 = 1_000_000_000;
+    
+    let mut rng = ChaCha8Rng::seed_from_u64(11);
+    let mut clob = CLOB::with_capacity(OPERATION_COUNT);
+    let mut engine = MatchingEngine::new();
+    
+    let mut live: HashSet<u64> = HashSet::new();
+    let mut submitted: u128 = 0;
+    let mut traded: u128 = 0;
+    let mut self_trades = 0usize;
+    let mut multi_level = 0usize;
+    
+    for op in 0..OPERATION_COUNT {
+        let id = (op + 1) as u64;
+        let side = if rng.gen_bool(0.5) { Side::Buy } else { Side::Sell };
+        
+        // Mostly quote a ladder; sometimes price through several own levels
+        let offset = rng.gen_range(0..8) * TICK;
+        let price = match (side, rng.gen_bool(0.2)) {
+            (Side::Buy, false) => BASE_PRICE - TICK - offset,
+            (Side::Sell, false) => BASE_PRICE + TICK + offset,
+            (Side::Buy, true) => BASE_PRICE + 4 * TICK,
+            (Side::Sell, true) => BASE_PRICE - 4 * TICK,
+        };
+        let quantity: u64 = rng.gen_range(1..=500) * 1_000_000;
+        submitted += quantity as u128;
+        
+        let result = engine.match_order(&mut clob, Order::new(id, 1, side, price, quantity, op as u64), op as u64);
+        
+        let mut makers: Vec<u64> = result.trades.iter().map(|t| t.maker_order_id).collect();
+        makers.dedup();
+        for maker in makers {
+            assert!(live.contains(&maker), "op {}: maker {} was not resting", op, maker);
+            if !clob.contains_order(maker) {
+                live.remove(&maker);
+            }
+        }
+        if result.resting_key.is_some() {
+            assert!(live.insert(id), "op {}: order {} rested twice", op, id);
+        }
+        
+        self_trades += result.trades.iter().filter(|t| t.is_self_trade()).count();
+        traded += result.trades.iter().map(|t| t.quantity as u128).sum::<u128>();
+        if result.levels_consumed > 1 {
+            multi_level += 1;
+        }
+        
+        assert_eq!(live.len(), clob.order_count(), "op {}: live orders desync", op);
+        if op % 5_000 == 0 {
+            clob.verify_invariants().unwrap_or_else(|e| panic!("op {}: {}", op, e));
+        }
+    }
+    
+    clob.verify_invariants().expect("invariants hold after self-cross churn");
+    
+    // Each trade removes its quantity from both the maker and the taker
+    let resting: u128 = clob.orders().iter().map(|(_, node)| node.order.remaining as u128).sum();
+    assert_eq!(resting, submitted - 2 * traded);
+    assert!(multi_level > 0, "no order swept more than one level");
+    
+    println!("  Self trades:       {:>12}", self_trades);
+    println!("  Multi-level:       {:>12}", multi_level);
+    println!("  Resting orders:    {:>12}", clob.order_count());
+    
+    println!("\n=== SELF-CROSS TEST PASSED ===\n");
+}
+
+/// Deterministic work histogram: matching cost without wall-clock noise.
+///
+/// `MatchResult::steps` counts maker fills plus levels walked, so the
+/// distribution is identical on every machine and its shape can be
+/// asserted exactly where throughput can only be asserted loosely.
+#[test]
+fn stress_work_histogram() {
+    println!("\n=== WORK HISTOGRAM TEST ===\n");
+    
+    const ORDER_COUNT: usize = 100_000;
+    const SEED: u64 = 42;
+    
+    let histogram = work_histogram(SEED, ORDER_COUNT);
+    
+    println!("{:>8} {:>10}", "Steps", "Orders");
+    println!("{:-<8} {:-<10}", "", "");
+    for (steps, orders) in &histogram {
+        println!("{:>8} {:>10}", steps, orders);
+    }
+    
+    let median = histogram_quantile(&histogram, 0.5);
+    let p99 = histogram_quantile(&histogram, 0.99);
+    let max = histogram.keys().next_back().copied().unwrap_or(0);
+    println!("\n  Median steps:      {:>12}", median);
+    println!("  p99 steps:         {:>12}", p99);
+    println!("  Max steps:         {:>12}", max);
+    
+    assert_eq!(histogram.values().sum::<usize>(), ORDER_COUNT);
+    
+    // Random prices rarely repeat, so most orders rest untouched and a
+    // matching order touches only a few levels
+    assert!(median <= 2, "median match steps {} above 2", median);
+    assert!(p99 <= 10, "p99 match steps {} above 10", p99);
+    assert!(max <= 32, "max match steps {} above 32", max);
+    assert!(histogram.get(&0) < Some(&ORDER_COUNT), "expected some matching");
+    
+    // The loop counts a level and at least one fill for every match
+    assert_eq!(histogram.get(&1), None);
+    
+    // Same input, same work, on any machine
+    assert_eq!(work_histogram(SEED, ORDER_COUNT), histogram, "work histogram is not deterministic");
+    
+    println!("\n=== WORK HISTOGRAM PASSED ===\n");
+}
+
+/// Adversarial deep book: one large taker sweeping thousands of tiny levels.
+///
+/// Random data never builds this shape, so it pins the worst case
+/// directly. There is no cap on fills per match: the work is bounded by
+/// what the taker crosses. Steps are counted as the sweep runs, one per
+/// fill, level and dropped empty level, so with `k` makers per level a
+/// full sweep of `n` levels costs exactly `n * (k + 1)` steps, linear in
+/// the liquidity consumed, plus one per retained empty level in the way.
+#[test]
+fn stress_deep_book_sweep() {
+    println!("\n=== DEEP BOOK SWEEP TEST ===\n");
+    
+    const LEVELS: u64 = 10_000;
+    const BASE_PRICE: u64 = 5_000_000_000_000;
+    const TICK: u64 = 1_000_000;
+    
+    for makers_per_level in [1u64, 3] {
+        let mut clob = CLOB::with_capacity((LEVELS * makers_per_level) as usize);
+        let mut engine = MatchingEngine::new();
+        
+        // Every level holds `makers_per_level` orders of one raw unit
+        let mut id = 1;
+        for level in 0..LEVELS {
+            for _ in 0..makers_per_level {
+                clob.add_order(Order::new(id, 1, Side::Sell, BASE_PRICE + level * TICK, 1, id));
+                id += 1;
+            }
+        }
+        
+        // Sweep the first half of the levels, then everything left
+        let half = LEVELS / 2;
+        let sweeps = [
+            (BASE_PRICE + (half - 1) * TICK, half),
+            (BASE_PRICE + LEVELS * TICK, LEVELS - half),
+        ];
+        for (limit, levels) in sweeps {
+            let quantity = levels * makers_per_level;
+            let start = Instant::now();
+            let result = engine.match_order(&mut clob, Order::new(id, 2, Side::Buy, limit, quantity, id), 0);
+            let elapsed = start.elapsed();
+            id += 1;
+            
+            println!(
+                "  {} maker(s)/level, {:>5} levels: {:>6} steps in {:?}",
+                makers_per_level, levels, result.steps, elapsed
+            );
+            
+            assert!(result.fully_filled);
+            assert_eq!(result.trades.len() as u64, quantity);
+            assert_eq!(result.levels_consumed as u64, levels);
+            assert_eq!(result.steps as u64, levels * (makers_per_level + 1));
+        }
+        
+        assert!(clob.is_empty());
+        clob.verify_invariants().unwrap();
+    }
+    
+    // Retained empty levels in the taker's path each cost a step
+    let mut clob = CLOB::with_capacity(LEVELS as usize).with_level_retention(true);
+    let mut engine = MatchingEngine::new();
+    for level in 0..LEVELS {
+        let id = level + 1;
+        clob.add_order(Order::new(id, 1, Side::Sell, BASE_PRICE + level * TICK, 1, id));
+        if level % 2 == 0 {
+            clob.cancel_order(id);
+        }
+    }
+    let taker = Order::new(LEVELS + 1, 2, Side::Buy, BASE_PRICE + LEVELS * TICK, LEVELS / 2, 0);
+    let result = engine.match_order(&mut clob, taker, 0);
+    println!("  1 maker per odd level, {:>5} levels: {:>6} steps", LEVELS, result.steps);
+    
+    assert!(result.fully_filled);
+    assert_eq!(result.levels_consumed as u64, LEVELS / 2);
+    assert_eq!(result.steps as u64, LEVELS / 2 * 2 + LEVELS / 2);
+    assert_eq!(clob.bids().len() + clob.asks().len(), 0);
+    
+    println!("\n=== DEEP BOOK SWEEP PASSED ===\n");
+}
+
 /// Test memory efficiency by checking the book doesn't grow unbounded.
 #[test]
 fn stress_memory_stability() {
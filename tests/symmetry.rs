@@ -0,0 +1,241 @@
+//! Buy/Sell symmetry tests.
+//!
+//! Most book accessors have a bid branch and an ask branch, which invites
+//! copy-paste asymmetries. Each test here builds a book and its mirror
+//! image (every order on the other side at `MIRROR - price`) and checks
+//! that each accessor's answer on one side is the mirrored answer on the
+//! other.
+//!
+//! Run with: cargo test --test symmetry
+
+use dark_hypercore::engine::MatchingEngine;
+use dark_hypercore::orderbook::{LevelSummary, CLOB};
+use dark_hypercore::types::{Order, Side};
+
+/// Reflection point: price `p` mirrors to `MIRROR - p`
+const MIRROR: u64 = 10_000_000_000_000;
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+fn opposite(side: Side) -> Side {
+    match side {
+        Side::Buy => Side::Sell,
+        Side::Sell => Side::Buy,
+    }
+}
+
+/// The same order on the other side, at the mirrored price
+fn mirror_order(order: &Order) -> Order {
+    Order::new(
+        order.id,
+        order.user_id,
+        opposite(order.side()),
+        MIRROR - order.price,
+        order.quantity,
+        order.timestamp,
+    )
+}
+
+fn mirror_summary(level: &LevelSummary) -> LevelSummary {
+    LevelSummary {
+        price: MIRROR - level.price,
+        ..*level
+    }
+}
+
+/// A two-sided book with several levels and several orders per level
+fn sample_orders() -> Vec<Order> {
+    let mut orders = Vec::new();
+    let mut id = 1;
+    for (side, prices) in [
+        (Side::Buy, [4_990_000_000_000, 4_980_000_000_000, 4_950_000_000_000]),
+        (Side::Sell, [5_020_000_000_000, 5_030_000_000_000, 5_100_000_000_000]),
+    ] {
+        for (level, &price) in prices.iter().enumerate() {
+            for n in 0..=level as u64 {
+                let quantity = (level as u64 + 1) * 100_000_000 + n * 25_000_000;
+                orders.push(Order::new(id, 10 + n, side, price, quantity, id));
+                id += 1;
+            }
+        }
+    }
+    orders
+}
+
+/// Build a book and its mirror image from the same orders
+fn book_and_mirror(orders: &[Order]) -> (CLOB, CLOB) {
+    let mut book = CLOB::with_capacity(100);
+    let mut mirror = CLOB::with_capacity(100);
+    for order in orders {
+        book.add_order(order.clone());
+        mirror.add_order(mirror_order(order));
+    }
+    (book, mirror)
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[test]
+fn best_prices_and_spread_mirror() {
+    let (book, mirror) = book_and_mirror(&sample_orders());
+    
+    assert_eq!(book.best_bid().map(|p| MIRROR - p), mirror.best_ask());
+    assert_eq!(book.best_ask().map(|p| MIRROR - p), mirror.best_bid());
+    assert_eq!(book.spread(), mirror.spread());
+    
+    let top = book.top_of_book();
+    let mirrored = mirror.top_of_book();
+    assert_eq!(top.bid_price.map(|p| MIRROR - p), mirrored.ask_price);
+    assert_eq!(top.ask_price.map(|p| MIRROR - p), mirrored.bid_price);
+    assert_eq!(top.bid_size, mirrored.ask_size);
+    assert_eq!(top.ask_size, mirrored.bid_size);
+    
+    assert_eq!(
+        book.best_bid_level().map(|l| (l.total_quantity, l.order_count)),
+        mirror.best_ask_level().map(|l| (l.total_quantity, l.order_count))
+    );
+}
+
+#[test]
+fn counts_mirror() {
+    let (book, mirror) = book_and_mirror(&sample_orders());
+    
+    assert_eq!(book.bid_count(), mirror.ask_count());
+    assert_eq!(book.ask_count(), mirror.bid_count());
+    assert_eq!(book.bid_levels(), mirror.ask_levels());
+    assert_eq!(book.ask_levels(), mirror.bid_levels());
+    assert_eq!(book.order_count(), mirror.order_count());
+}
+
+#[test]
+fn depth_accessors_mirror() {
+    let (book, mirror) = book_and_mirror(&sample_orders());
+    
+    for side in [Side::Buy, Side::Sell] {
+        let other = opposite(side);
+        
+        for levels in [0, 1, 2, 10] {
+            let summaries: Vec<_> = book.level_summaries(side, levels).iter().map(mirror_summary).collect();
+            assert_eq!(summaries, mirror.level_summaries(other, levels), "{:?} summaries", side);
+            
+            let depth: Vec<_> = book
+                .cumulative_depth(side, levels)
+                .into_iter()
+                .map(|(price, quantity)| (MIRROR - price, quantity))
+                .collect();
+            assert_eq!(depth, mirror.cumulative_depth(other, levels), "{:?} depth", side);
+        }
+        
+        for level in book.level_summaries(side, 10) {
+            assert_eq!(
+                book.level_summary(side, level.price).as_ref().map(mirror_summary),
+                mirror.level_summary(other, MIRROR - level.price)
+            );
+        }
+    }
+}
+
+#[test]
+fn volume_accessors_mirror() {
+    let (book, mirror) = book_and_mirror(&sample_orders());
+    
+    for side in [Side::Buy, Side::Sell] {
+        // Averages round down in price, which a reflection turns into
+        // rounding up, so an inexact average may differ by one unit
+        let average = book.average_resting_price(side).unwrap();
+        let mirrored = mirror.average_resting_price(opposite(side)).unwrap();
+        assert!((MIRROR - average).abs_diff(mirrored) <= 1, "{:?}: {} vs {}", side, average, mirrored);
+    }
+    
+    for user_id in [10, 11, 12, 99] {
+        let (bid_qty, ask_qty, _, _) = book.user_resting_exposure(user_id);
+        let (mirror_bid_qty, mirror_ask_qty, _, _) = mirror.user_resting_exposure(user_id);
+        assert_eq!((bid_qty, ask_qty), (mirror_ask_qty, mirror_bid_qty), "user {}", user_id);
+    }
+}
+
+#[test]
+fn queue_positions_mirror() {
+    let orders = sample_orders();
+    let (book, mirror) = book_and_mirror(&orders);
+    
+    for order in &orders {
+        assert_eq!(book.queue_position(order.id), mirror.queue_position(order.id), "order {}", order.id);
+    }
+}
+
+#[test]
+fn mutations_mirror() {
+    let orders = sample_orders();
+    let (mut book, mut mirror) = book_and_mirror(&orders);
+    
+    // Remove the second bid level and the first ask level
+    let bid_level = book.cancel_price_level(Side::Buy, 4_980_000_000_000);
+    let ask_level = mirror.cancel_price_level(Side::Buy, MIRROR - 5_020_000_000_000);
+    assert_eq!(bid_level.len(), 2);
+    assert_eq!(ask_level.len(), 1);
+    assert_eq!(
+        mirror.cancel_price_level(Side::Sell, MIRROR - 4_980_000_000_000).iter().map(|o| o.id).collect::<Vec<_>>(),
+        bid_level.iter().map(|o| o.id).collect::<Vec<_>>()
+    );
+    book.cancel_price_level(Side::Sell, 5_020_000_000_000);
+    
+    // Reduce and cancel single orders
+    for (reduce, cancel) in [(1, 5), (8, 10)] {
+        assert_eq!(book.reduce_order(reduce, 10_000_000), mirror.reduce_order(reduce, 10_000_000));
+        assert_eq!(book.cancel_order(cancel).is_some(), mirror.cancel_order(cancel).is_some());
+    }
+    
+    assert_eq!(book.best_bid().map(|p| MIRROR - p), mirror.best_ask());
+    assert_eq!(book.best_ask().map(|p| MIRROR - p), mirror.best_bid());
+    for side in [Side::Buy, Side::Sell] {
+        let summaries: Vec<_> = book.level_summaries(side, 10).iter().map(mirror_summary).collect();
+        assert_eq!(summaries, mirror.level_summaries(opposite(side), 10));
+    }
+    book.verify_invariants().unwrap();
+    mirror.verify_invariants().unwrap();
+}
+
+#[test]
+fn matching_mirrors() {
+    let orders = sample_orders();
+    let (mut book, mut mirror) = book_and_mirror(&orders);
+    let mut engine = MatchingEngine::new();
+    let mut mirror_engine = MatchingEngine::new();
+    
+    // A buy sweeping two ask levels and resting, then a sell sweeping bids
+    let takers = [
+        Order::new(100, 1, Side::Buy, 5_030_000_000_000, 600_000_000, 100),
+        Order::new(101, 1, Side::Sell, 4_950_000_000_000, 250_000_000, 101),
+    ];
+    
+    for taker in &takers {
+        let result = engine.match_order(&mut book, taker.clone(), 0);
+        let mirrored = mirror_engine.match_order(&mut mirror, mirror_order(taker), 0);
+        
+        let trades: Vec<_> = result
+            .trades
+            .iter()
+            .map(|t| (t.maker_order_id, MIRROR - t.price, t.quantity))
+            .collect();
+        let mirrored_trades: Vec<_> = mirrored
+            .trades
+            .iter()
+            .map(|t| (t.maker_order_id, t.price, t.quantity))
+            .collect();
+        assert_eq!(trades, mirrored_trades, "taker {}", taker.id);
+        assert_eq!(result.remaining, mirrored.remaining);
+        assert_eq!(result.resting_key.is_some(), mirrored.resting_key.is_some());
+        assert_eq!(result.levels_consumed, mirrored.levels_consumed);
+        assert_eq!(result.price_improvement, mirrored.price_improvement);
+    }
+    
+    assert_eq!(book.best_bid().map(|p| MIRROR - p), mirror.best_ask());
+    assert_eq!(book.best_ask().map(|p| MIRROR - p), mirror.best_bid());
+    assert_eq!(book.bid_count(), mirror.ask_count());
+    assert_eq!(book.ask_count(), mirror.bid_count());
+}